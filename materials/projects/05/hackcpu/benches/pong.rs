@@ -0,0 +1,88 @@
+//! Compares the pre-decoded `Engine` with `Computer::run`, which decodes
+//! the bits of each instruction on every cycle, on the Pong program of
+//! projects/06 (run with `cargo bench`)
+
+use asm2hack::code::CodeConfiguration;
+use hackcpu::computer::Computer;
+use hackcpu::engine::Engine;
+use hackcpu::loader::load_program;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// number of cycles executed by each run
+const CYCLES: u64 = 50_000_000;
+
+/// number of runs of each engine, the fastest one is reported
+const RUNS: usize = 5;
+
+/// speedup of the engine expected over `Computer::run`, it measures
+/// about 4x on an optimized build
+const EXPECTED_SPEEDUP: f64 = 3.0;
+
+/// Returns the fastest of the runs and the state (A, D, PC, RAM) it ends with
+fn measure<F: FnMut() -> (Duration, (i16, i16, u16, Vec<i16>))>(
+    mut run: F,
+) -> (Duration, (i16, i16, u16, Vec<i16>)) {
+    (0..RUNS)
+        .map(|_| run())
+        .min_by_key(|(elapsed, _)| *elapsed)
+        .unwrap()
+}
+
+fn measure_computer<F: FnMut(&mut Computer)>(
+    program: &[u16],
+    mut run: F,
+) -> (Duration, (i16, i16, u16, Vec<i16>)) {
+    measure(|| {
+        let mut computer = Computer::new();
+        computer.load_rom(program);
+
+        let start = Instant::now();
+        run(&mut computer);
+        let elapsed = start.elapsed();
+
+        assert_eq!(computer.cycles, CYCLES);
+        (elapsed, (computer.a, computer.d, computer.pc, computer.memory()))
+    })
+}
+
+fn report(name: &str, elapsed: Duration) {
+    println!(
+        "{:<10} {:>8.3} s {:>10.1} Mcycles/s",
+        name,
+        elapsed.as_secs_f64(),
+        CYCLES as f64 / elapsed.as_secs_f64() / 1_000_000.0
+    );
+}
+
+fn main() {
+    let program = load_program(
+        Path::new("../../06/pong/Pong.asm"),
+        &CodeConfiguration::default(),
+    )
+    .unwrap();
+
+    let (decoder, decoder_state) = measure_computer(&program, |computer| {
+        computer.run(Some(CYCLES));
+    });
+
+    let (engine, engine_state) = measure_computer(&program, |computer| {
+        Engine::new(computer).run(computer, Some(CYCLES));
+    });
+
+    // both implementations end in the same state
+    assert!(decoder_state == engine_state);
+
+    println!("pong, {} cycles (best of {} runs)", CYCLES, RUNS);
+    report("decoder", decoder);
+    report("engine", engine);
+
+    let speedup = decoder.as_secs_f64() / engine.as_secs_f64();
+    println!("speedup: {:.1}x over decoder", speedup);
+
+    assert!(
+        speedup >= EXPECTED_SPEEDUP,
+        "the engine should run at least {}x faster than Computer::run",
+        EXPECTED_SPEEDUP
+    );
+}
@@ -0,0 +1,31 @@
+use clap::Parser as ClapParser;
+use hackcpu::compare::{compare, log_failure};
+use hackcpu::logs::log_success;
+use hackcpu::utils;
+
+/// compares an output file (.out) with a compare file (.cmp) column by
+/// column, ignoring whitespace and honouring `*` wildcard cells
+#[derive(ClapParser, Debug)]
+#[command(author = "txxnano", version, about)]
+pub struct Args {
+    /// output file to check (.out)
+    output: String,
+
+    /// compare file with the expected output (.cmp)
+    compare: String,
+}
+
+pub fn main() {
+    let args = Args::parse();
+
+    let output = utils::read_file(&args.output);
+    let expected = utils::read_file(&args.compare);
+
+    match compare(&output, &expected) {
+        Some(failure) => {
+            log_failure(&failure);
+            std::process::exit(1);
+        }
+        None => log_success("comparison ended successfully"),
+    }
+}
@@ -0,0 +1,202 @@
+use crate::logs::log_error;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ComparisonFailure {
+    /// line of the output file that does not match (starting at 1)
+    pub line: usize,
+    /// name of the first column that does not match (table rows only)
+    pub column: Option<String>,
+    pub expected: String,
+    pub actual: String,
+}
+
+/// Splits a row of a `|`-delimited table into its trimmed cells,
+/// lines that are not table rows return None
+pub fn split_cells(line: &str) -> Option<Vec<&str>> {
+    let line = line.trim();
+
+    if line.len() < 2 || !line.starts_with('|') || !line.ends_with('|') {
+        return None;
+    }
+
+    Some(
+        line[1..line.len() - 1]
+            .split('|')
+            .map(|cell| cell.trim())
+            .collect(),
+    )
+}
+
+/// Checks if a cell of the output matches the cell of the compare file,
+/// a compare cell made only of `*` matches any value
+fn cell_matches(expected: &str, actual: &str) -> bool {
+    (!expected.is_empty() && expected.chars().all(|char| char == '*')) || expected == actual
+}
+
+/// Checks if a row is a header, which means none of its cells is a value
+fn is_header(cells: &[&str]) -> bool {
+    cells
+        .iter()
+        .all(|cell| cell.parse::<i64>().is_err() && !cell.chars().all(|char| char == '*'))
+}
+
+/// Compares a line of the output with a line of the compare file, table
+/// rows are compared cell by cell and any other line ignoring whitespace
+///
+/// # Returns
+///
+/// * None when the lines match, otherwise the index of the first cell that does not match
+pub fn compare_line(expected: &str, actual: &str) -> Option<usize> {
+    match (split_cells(expected), split_cells(actual)) {
+        (Some(expected), Some(actual)) => {
+            let mismatch = expected
+                .iter()
+                .zip(actual.iter())
+                .position(|(expected, actual)| !cell_matches(expected, actual));
+
+            match mismatch {
+                Some(index) => Some(index),
+                None if expected.len() != actual.len() => Some(expected.len().min(actual.len())),
+                None => None,
+            }
+        }
+        _ => {
+            let expected: Vec<&str> = expected.split_whitespace().collect();
+            let actual: Vec<&str> = actual.split_whitespace().collect();
+
+            if expected == actual {
+                None
+            } else {
+                Some(0)
+            }
+        }
+    }
+}
+
+/// Compares an output file with a compare file (.out against .cmp),
+/// column names are taken from the last header row of the compare file
+///
+/// # Arguments
+///
+/// * `output` - The content of the output file
+/// * `compare` - The content of the compare file
+///
+/// # Returns
+///
+/// * None when both files match, otherwise the first line that does not match
+pub fn compare(output: &str, compare: &str) -> Option<ComparisonFailure> {
+    let output: Vec<&str> = output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    let compare: Vec<&str> = compare
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+
+    let mut columns: Vec<String> = Vec::new();
+
+    for index in 0..output.len().max(compare.len()) {
+        let expected = compare.get(index).copied().unwrap_or("");
+        let actual = output.get(index).copied().unwrap_or("");
+
+        if let Some(cells) = split_cells(expected).filter(|cells| index == 0 || is_header(cells)) {
+            columns = cells.iter().map(|cell| cell.to_string()).collect();
+        }
+
+        if let Some(cell) = compare_line(expected, actual) {
+            return Some(ComparisonFailure {
+                line: index + 1,
+                column: split_cells(expected).and(columns.get(cell).cloned()),
+                expected: expected.trim_end().to_string(),
+                actual: actual.trim_end().to_string(),
+            });
+        }
+    }
+
+    None
+}
+
+/// Logs where a comparison failed, with the expected and the actual line
+pub fn log_failure(failure: &ComparisonFailure) {
+    match &failure.column {
+        Some(column) => log_error(
+            format!(
+                "comparison failure at line {}, column {}",
+                failure.line, column
+            )
+            .as_str(),
+        ),
+        None => log_error(format!("comparison failure at line {}", failure.line).as_str()),
+    }
+
+    log_error(format!("expected: {}", failure.expected).as_str());
+    log_error(format!("actual:   {}", failure.actual).as_str());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_rows_ignoring_whitespace() {
+        assert_eq!(compare_line("|  RAM[0]  | 15 |", "|RAM[0]|15|"), None);
+        assert_eq!(
+            compare_line("|   257  |  15  |", "|   257  |  0  |"),
+            Some(1)
+        );
+        assert_eq!(compare_line("| 1 | 2 |", "| 1 |"), Some(1));
+        assert_eq!(compare_line("Comparison  ended", "Comparison ended "), None);
+    }
+
+    #[test]
+    fn compare_rows_with_wildcard_cells() {
+        assert_eq!(
+            compare_line("|   257  |*******|", "|   257  |  -3  |"),
+            None
+        );
+        assert_eq!(
+            compare_line("|   257  |*******|", "|   256  |  -3  |"),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn compare_files_with_column_names() {
+        let compare_file = "|  RAM[0]  | RAM[256] |\r\n|     257  |      15  |\r\n";
+        let output_file = "|  RAM[0]  | RAM[256] |\n|     257  |       0  |\n";
+
+        assert_eq!(
+            compare(output_file, compare_file),
+            Some(ComparisonFailure {
+                line: 2,
+                column: Some("RAM[256]".to_string()),
+                expected: "|     257  |      15  |".to_string(),
+                actual: "|     257  |       0  |".to_string(),
+            })
+        );
+
+        assert_eq!(compare(compare_file, compare_file), None);
+    }
+
+    #[test]
+    fn compare_files_with_several_headers() {
+        let compare_file =
+            "| RAM[0] |\n|    266 |\n| RAM[261] | RAM[262] |\n|        0 |       -1 |\n";
+        let output_file =
+            "| RAM[0] |\n|    266 |\n| RAM[261] | RAM[262] |\n|        0 |        0 |\n";
+
+        let failure = compare(output_file, compare_file).unwrap();
+
+        assert_eq!(failure.line, 4);
+        assert_eq!(failure.column, Some("RAM[262]".to_string()));
+    }
+
+    #[test]
+    fn compare_files_with_missing_lines() {
+        let failure = compare("| RAM[0] |\n", "| RAM[0] |\n|    266 |\n").unwrap();
+
+        assert_eq!(failure.line, 2);
+        assert_eq!(failure.actual, "");
+    }
+}
@@ -0,0 +1,856 @@
+use std::cell::Ref;
+
+use crate::device::Devices;
+use crate::keyboard::KeyboardScript;
+use crate::monitor::Monitor;
+use crate::profiler::Profile;
+use crate::trace::{MemoryWrite, Trace, TraceEntry};
+
+/// number of instructions that fit in the instruction memory
+pub const ROM_SIZE: usize = 32768;
+
+/// number of words addressable by the A register (15 bits)
+pub const RAM_SIZE: usize = 32768;
+
+/// base address of the memory-mapped screen
+pub const SCREEN_ADDRESS: u16 = 16384;
+
+/// number of words used by the screen (512x256 pixels, 16 pixels per word)
+pub const SCREEN_SIZE: usize = 8192;
+
+/// address of the memory-mapped keyboard
+pub const KBD_ADDRESS: u16 = 24576;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum StopReason {
+    /// the program reached a tight loop such as `(END) @END 0;JMP`
+    Halted,
+    /// the program counter went past the last loaded instruction
+    EndOfProgram,
+    /// the maximum number of cycles was executed
+    CycleLimit,
+    /// the user stopped the program (e.g. with Ctrl-C in the terminal runner)
+    Interrupted,
+    /// a watchpoint set to break or an invariant was triggered
+    Watchpoint,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Computer {
+    /// instruction memory (ROM32K)
+    pub rom: Vec<u16>,
+    /// data memory, the words of the I/O memory are only used at the
+    /// addresses without device
+    pub ram: Vec<i16>,
+    /// number of instructions loaded in the ROM
+    pub program_size: usize,
+    pub a: i16,
+    pub d: i16,
+    pub pc: u16,
+    /// number of instructions executed since the last reset
+    pub cycles: u64,
+    /// scripted key presses applied to the keyboard register while running
+    pub keyboard: Option<KeyboardScript>,
+    /// instruction counts per ROM address, recorded while running
+    pub profile: Option<Profile>,
+    /// executed instructions, recorded to inspect or undo them
+    pub trace: Option<Trace>,
+    /// watchpoints and invariants checked on every write to the RAM
+    pub monitor: Option<Monitor>,
+    /// devices mapped in the I/O memory, the screen and the keyboard
+    /// included (a clone only copies these two)
+    pub devices: Devices,
+}
+
+impl Default for Computer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parses the text `.hack` format, one 16-bit binary instruction per line
+///
+/// # Arguments
+///
+/// * `input` - The content of the `.hack` file
+pub fn parse_hack(input: &str) -> Vec<u16> {
+    let mut instructions: Vec<u16> = Vec::new();
+
+    for (index, line) in input.lines().enumerate() {
+        let line = line.trim();
+
+        // discard empty lines
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.len() != 16 {
+            panic!("Invalid binary instruction at line {}: {}", index + 1, line);
+        }
+
+        match u16::from_str_radix(line, 2) {
+            Ok(instruction) => instructions.push(instruction),
+            Err(_) => panic!("Invalid binary instruction at line {}: {}", index + 1, line),
+        }
+    }
+
+    instructions
+}
+
+/// Computes the output of the Hack ALU based on the control bits of the
+/// instruction (zx, nx, zy, ny, f, no) from the most significant one
+pub fn alu(control: u16, x: i16, y: i16) -> i16 {
+    let x = if control & 0b100000 != 0 { 0 } else { x };
+    let x = if control & 0b010000 != 0 { !x } else { x };
+    let y = if control & 0b001000 != 0 { 0 } else { y };
+    let y = if control & 0b000100 != 0 { !y } else { y };
+
+    let out = if control & 0b000010 != 0 {
+        x.wrapping_add(y)
+    } else {
+        x & y
+    };
+
+    if control & 0b000001 != 0 {
+        !out
+    } else {
+        out
+    }
+}
+
+/// Computes the output of the shift instructions of the extended
+/// instruction set, the right shift keeps the sign of the value
+pub fn shift(control: u16, d: i16, a_or_m: i16) -> i16 {
+    let value = if control & 0b010000 != 0 { d } else { a_or_m };
+
+    if control & 0b100000 != 0 {
+        value << 1
+    } else {
+        value >> 1
+    }
+}
+
+impl Computer {
+    pub fn new() -> Self {
+        Self {
+            rom: vec![0; ROM_SIZE],
+            ram: vec![0; RAM_SIZE],
+            program_size: 0,
+            a: 0,
+            d: 0,
+            pc: 0,
+            cycles: 0,
+            keyboard: None,
+            profile: None,
+            trace: None,
+            monitor: None,
+            devices: Devices::default(),
+        }
+    }
+
+    /// Loads a program in the ROM, the rest of the ROM is cleared
+    pub fn load_rom(&mut self, instructions: &[u16]) {
+        if instructions.len() > ROM_SIZE {
+            panic!(
+                "Program does not fit in the ROM: {} instructions",
+                instructions.len()
+            );
+        }
+
+        self.rom = vec![0; ROM_SIZE];
+        self.rom[..instructions.len()].copy_from_slice(instructions);
+        self.program_size = instructions.len();
+    }
+
+    /// Loads a program written in the text `.hack` format
+    pub fn load_hack(&mut self, input: &str) {
+        self.load_rom(&parse_hack(input));
+    }
+
+    /// Resets the CPU, the memory keeps its content as in the hardware
+    pub fn reset(&mut self) {
+        self.pc = 0;
+        self.cycles = 0;
+
+        if let Some(keyboard) = &mut self.keyboard {
+            keyboard.rewind();
+        }
+
+        if let Some(trace) = &mut self.trace {
+            trace.entries.clear();
+        }
+    }
+
+    /// Reads a word of RAM as the program does, the I/O memory goes
+    /// through the mapped devices (with the side effects of their reads)
+    pub fn read_memory(&self, address: u16) -> i16 {
+        let address = address % RAM_SIZE as u16;
+
+        if address < SCREEN_ADDRESS {
+            return self.ram[address as usize];
+        }
+
+        self.devices.read(&self.ram, address)
+    }
+
+    /// Inspects a word of RAM without the side effects of a read (e.g. for
+    /// the debugger), a device that cannot tell its value without them
+    /// shows the word of RAM under it
+    pub fn peek_memory(&self, address: u16) -> i16 {
+        let address = address % RAM_SIZE as u16;
+
+        if address >= SCREEN_ADDRESS {
+            if let Some(value) = self
+                .devices
+                .find(address)
+                .and_then(|device| device.borrow().peek(address))
+            {
+                return value;
+            }
+        }
+
+        self.ram[address as usize]
+    }
+
+    /// Writes a word of RAM, the I/O memory goes through the mapped devices
+    pub fn write_memory(&mut self, address: u16, value: i16) {
+        let address = address % RAM_SIZE as u16;
+
+        if address < SCREEN_ADDRESS {
+            self.ram[address as usize] = value;
+            return;
+        }
+
+        self.devices.write(&mut self.ram, address, value);
+    }
+
+    /// Returns every word of RAM as the program sees it, without the side
+    /// effects of the reads (e.g. to save a snapshot)
+    pub fn memory(&self) -> Vec<i16> {
+        (0..RAM_SIZE as u16)
+            .map(|address| self.peek_memory(address))
+            .collect()
+    }
+
+    /// Puts back every word of RAM, the devices with side effects keep
+    /// their state
+    pub fn restore_memory(&mut self, words: &[i16]) {
+        self.ram.copy_from_slice(words);
+
+        for mapped in self.devices.mapped.iter() {
+            let mut device = mapped.device.borrow_mut();
+
+            for address in mapped.start..mapped.start + mapped.size {
+                device.restore(address, words[address as usize]);
+            }
+        }
+    }
+
+    /// Sets the key currently pressed (0 when no key is pressed)
+    pub fn set_key(&mut self, key: i16) {
+        self.write_memory(KBD_ADDRESS, key);
+    }
+
+    /// Returns the words of the memory-mapped screen
+    pub fn screen(&self) -> Ref<'_, [i16]> {
+        Ref::map(self.devices.screen.borrow(), |screen| {
+            screen.words.as_slice()
+        })
+    }
+
+    /// Checks if the current instruction is a tight loop jumping to itself,
+    /// `@N` at address N followed by an unconditional jump like `0;JMP`
+    pub fn is_halted(&self) -> bool {
+        let pc = self.pc as usize;
+
+        if pc + 1 >= ROM_SIZE {
+            return false;
+        }
+
+        let load = self.rom[pc];
+        let jump = self.rom[pc + 1];
+
+        load & 0x8000 == 0 && load as usize == pc && jump & 0xE03F == 0xE007
+    }
+
+    /// Executes the instruction pointed by the program counter
+    pub fn step(&mut self) {
+        let (cycle, pc, a, d) = (self.cycles, self.pc, self.a, self.d);
+
+        // apply the key presses of the keyboard script
+        let key = self
+            .keyboard
+            .as_mut()
+            .and_then(|keyboard| keyboard.poll(self.cycles))
+            .map(|key| MemoryWrite {
+                address: KBD_ADDRESS,
+                previous: self.peek_memory(KBD_ADDRESS),
+                value: key,
+            });
+
+        if let Some(key) = key {
+            self.set_key(key.value);
+        }
+
+        if let Some(profile) = &mut self.profile {
+            profile.record(pc);
+        }
+
+        let instruction = self.rom[pc as usize % ROM_SIZE];
+
+        self.cycles += 1;
+
+        let write = self.execute(instruction);
+
+        if self.devices.has_peripherals() {
+            self.devices.tick(self.cycles);
+        }
+
+        if let Some(monitor) = &mut self.monitor {
+            for write in [key, write].into_iter().flatten() {
+                monitor.check(cycle, pc, write);
+            }
+        }
+
+        if let Some(trace) = &mut self.trace {
+            trace.record(TraceEntry {
+                cycle,
+                pc,
+                a,
+                d,
+                key,
+                write,
+            });
+        }
+    }
+
+    /// Executes an instruction and updates the registers and the memory
+    ///
+    /// # Returns
+    ///
+    /// * The word of RAM written by the instruction, if any
+    fn execute(&mut self, instruction: u16) -> Option<MemoryWrite> {
+        // A-instruction: load the value into the A register
+        if instruction & 0x8000 == 0 {
+            self.a = instruction as i16;
+            self.pc = self.pc.wrapping_add(1);
+            return None;
+        }
+
+        let a_or_m = if instruction & 0x1000 != 0 {
+            self.read_memory(self.a as u16)
+        } else {
+            self.a
+        };
+
+        let control = (instruction >> 6) & 0b111111;
+
+        // the extended instruction set uses the prefix 101 instead of 111
+        let out = if instruction & 0x6000 == 0x6000 {
+            alu(control, self.d, a_or_m)
+        } else if instruction & 0x6000 == 0x2000 {
+            shift(control, self.d, a_or_m)
+        } else {
+            panic!("Invalid instruction at {}: {:016b}", self.pc, instruction);
+        };
+
+        // the address of M and the jump target use the A register before
+        // it is updated by the current instruction
+        let address = self.a as u16;
+        let mut write: Option<MemoryWrite> = None;
+
+        if instruction & 0b001000 != 0 {
+            // only the trace and the monitor use the write, the previous
+            // word is peeked so that devices are only read by the
+            // instructions of the program
+            if self.trace.is_some() || self.monitor.is_some() {
+                let address = address % RAM_SIZE as u16;
+
+                write = Some(MemoryWrite {
+                    address,
+                    previous: self.peek_memory(address),
+                    value: out,
+                });
+            }
+
+            self.write_memory(address, out);
+        }
+
+        if instruction & 0b100000 != 0 {
+            self.a = out;
+        }
+
+        if instruction & 0b010000 != 0 {
+            self.d = out;
+        }
+
+        let jump = (instruction & 0b100 != 0 && out < 0)
+            || (instruction & 0b010 != 0 && out == 0)
+            || (instruction & 0b001 != 0 && out > 0);
+
+        self.pc = if jump {
+            address & 0x7FFF
+        } else {
+            self.pc.wrapping_add(1)
+        };
+
+        write
+    }
+
+    /// Undoes the last instruction recorded in the trace, restoring the
+    /// registers, the word of RAM it wrote and the keyboard register.
+    /// Devices with side effects keep the writes, see `Device::restore`
+    ///
+    /// # Returns
+    ///
+    /// * false when the trace is disabled or has no instruction left to undo
+    pub fn step_back(&mut self) -> bool {
+        let entry = match self
+            .trace
+            .as_mut()
+            .and_then(|trace| trace.entries.pop_back())
+        {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        // undo the writes in the reverse order they were made, restored so
+        // the devices do not see them as new writes
+        for write in [entry.write, entry.key].iter().flatten() {
+            match self.devices.find(write.address) {
+                Some(device) => device.borrow_mut().restore(write.address, write.previous),
+                None => self.ram[write.address as usize] = write.previous,
+            }
+        }
+
+        if let Some(keyboard) = &mut self.keyboard {
+            keyboard.seek(entry.cycle);
+        }
+
+        self.pc = entry.pc;
+        self.a = entry.a;
+        self.d = entry.d;
+        self.cycles = entry.cycle;
+
+        true
+    }
+
+    /// Runs the program until it halts, runs past the end of the program,
+    /// triggers a watchpoint or executes the maximum number of cycles
+    ///
+    /// # Arguments
+    ///
+    /// * `max_cycles` - The maximum number of cycles to execute (None to run until the program stops)
+    pub fn run(&mut self, max_cycles: Option<u64>) -> StopReason {
+        let mut executed: u64 = 0;
+
+        loop {
+            if self.is_halted() {
+                return StopReason::Halted;
+            }
+
+            if self.pc as usize >= self.program_size {
+                return StopReason::EndOfProgram;
+            }
+
+            if max_cycles.is_some_and(|max_cycles| executed >= max_cycles) {
+                return StopReason::CycleLimit;
+            }
+
+            self.step();
+            executed += 1;
+
+            if self
+                .monitor
+                .as_mut()
+                .is_some_and(|monitor| monitor.take_stop())
+            {
+                return StopReason::Watchpoint;
+            }
+        }
+    }
+
+    /// Runs the program like `run` and calls `on_frame` every time the
+    /// given number of cycles is executed (e.g. to capture the screen)
+    ///
+    /// # Arguments
+    ///
+    /// * `max_cycles` - The maximum number of cycles to execute (None to run until the program stops)
+    /// * `every` - The number of cycles of each frame (at least 1)
+    /// * `on_frame` - The function to call at the end of each frame
+    pub fn run_with_frames<F: FnMut(&mut Computer)>(
+        &mut self,
+        max_cycles: Option<u64>,
+        every: u64,
+        mut on_frame: F,
+    ) -> StopReason {
+        let mut executed: u64 = 0;
+
+        // empty frames would never advance the program
+        let every = every.max(1);
+
+        loop {
+            let budget = max_cycles.map_or(every, |max_cycles| every.min(max_cycles - executed));
+            let start = self.cycles;
+
+            let reason = self.run(Some(budget));
+            executed += self.cycles - start;
+
+            if reason != StopReason::CycleLimit {
+                return reason;
+            }
+
+            on_frame(self);
+
+            if max_cycles.is_some_and(|max_cycles| executed >= max_cycles) {
+                return reason;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::{Device, RandomSource, Timer};
+    use asm2hack::code::CodeConfiguration;
+
+    fn load(program: &str) -> Computer {
+        let mut computer = Computer::new();
+        computer.load_hack(program);
+        computer
+    }
+
+    #[test]
+    fn parse_hack_skips_empty_lines() {
+        let instructions = parse_hack("0000000000000010\n\n1110110000010000\n");
+
+        assert_eq!(instructions, vec![2, 0b1110110000010000]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid binary instruction at line 2: 11101100000")]
+    fn parse_hack_with_invalid_instruction() {
+        parse_hack("0000000000000010\n11101100000");
+    }
+
+    #[test]
+    fn execute_a_instruction() {
+        let mut computer = load("0000000000000111");
+
+        computer.step();
+
+        assert_eq!(computer.a, 7);
+        assert_eq!(computer.pc, 1);
+        assert_eq!(computer.cycles, 1);
+    }
+
+    #[test]
+    fn execute_c_instruction_with_memory() {
+        // @3, D=A, @0, M=D+1, AM=M-1
+        let mut computer = load(
+            "0000000000000011\n1110110000010000\n0000000000000000\n1110011111001000\n1111110010101000",
+        );
+
+        computer.run(Some(4));
+        assert_eq!(computer.read_memory(0), 4);
+
+        // AM=M-1 writes M using the previous value of A
+        computer.step();
+        assert_eq!(computer.read_memory(0), 3);
+        assert_eq!(computer.a, 3);
+    }
+
+    #[test]
+    fn dispatch_io_memory_to_devices() {
+        let mut computer = Computer::new();
+        computer.load_rom(
+            &crate::loader::assemble(
+                "@24577\nD=M\n@16\nM=D\n@24577\nM=-1",
+                &CodeConfiguration::default(),
+            )
+            .unwrap(),
+        );
+
+        // the timer counts every cycle, the keyboard is a device too
+        let timer = computer.devices.map(24577, 1, Timer::new(1)).unwrap();
+        computer.set_key(65);
+
+        assert_eq!(computer.run(None), StopReason::EndOfProgram);
+        assert_eq!(computer.read_memory(16), 1);
+        assert_eq!(timer.borrow().count, 0);
+        assert_eq!(computer.ram[24577], 0);
+        assert_eq!(computer.read_memory(KBD_ADDRESS), 65);
+        assert_eq!(computer.devices.keyboard.borrow().key, 65);
+        assert_eq!(computer.ram[KBD_ADDRESS as usize], 0);
+    }
+
+    #[test]
+    fn read_devices_only_on_program_reads() {
+        let mut computer = Computer::new();
+        computer.load_rom(
+            &crate::loader::assemble("@24577\nM=1\n@24577\nD=M", &CodeConfiguration::default())
+                .unwrap(),
+        );
+        computer.trace = Some(Trace::new());
+
+        let random = computer
+            .devices
+            .map(24577, 1, RandomSource::new(7))
+            .unwrap();
+        let mut expected = RandomSource::new(1);
+
+        // the write recorded in the trace does not read the device
+        computer.run(None);
+        assert_eq!(computer.d, expected.read(24577));
+        assert_eq!(random.borrow().state, expected.state);
+
+        // neither does peeking it
+        assert_eq!(computer.peek_memory(24577), expected.peek(24577).unwrap());
+        assert_eq!(random.borrow().state, expected.state);
+    }
+
+    #[test]
+    fn execute_negative_values() {
+        // @1, D=-A, D=D-1, D=!D
+        let mut computer =
+            load("0000000000000001\n1110110011010000\n1110001110010000\n1110001101010000");
+
+        computer.run(Some(3));
+        assert_eq!(computer.d, -2);
+
+        computer.step();
+        assert_eq!(computer.d, 1);
+    }
+
+    #[test]
+    fn execute_extended_shift_instructions() {
+        // @3, D=A, D=D<<, @0, M=D, M=M>>
+        let mut computer = load(
+            "0000000000000011\n1110110000010000\n1010110000010000\n0000000000000000\n1110001100001000\n1011000000001000",
+        );
+
+        computer.run(None);
+        assert_eq!(computer.d, 6);
+        assert_eq!(computer.read_memory(0), 3);
+
+        // the right shift keeps the sign of the value
+        assert_eq!(shift(0b000000, 0, -4), -2);
+    }
+
+    #[test]
+    fn execute_conditional_jump() {
+        // @5, D=A, @5, D;JGT, @0, @1 (the jump skips @0)
+        let mut computer = load(
+            "0000000000000101\n1110110000010000\n0000000000000101\n1110001100000001\n0000000000000000\n0000000000000001",
+        );
+
+        computer.run(Some(4));
+        assert_eq!(computer.pc, 5);
+
+        // D;JLT does not jump with a positive value
+        computer.rom[3] = 0b1110001100000100;
+        computer.reset();
+        computer.run(Some(4));
+        assert_eq!(computer.pc, 4);
+    }
+
+    #[test]
+    fn run_until_halted() {
+        // @1, D=A, (END) @2, 0;JMP
+        let mut computer =
+            load("0000000000000001\n1110110000010000\n0000000000000010\n1110101010000111");
+
+        assert_eq!(computer.run(Some(1000)), StopReason::Halted);
+        assert_eq!(computer.pc, 2);
+        assert_eq!(computer.cycles, 2);
+    }
+
+    #[test]
+    fn run_until_end_of_program() {
+        let mut computer = load("0000000000000001\n1110110000010000");
+
+        assert_eq!(computer.run(None), StopReason::EndOfProgram);
+        assert_eq!(computer.d, 1);
+    }
+
+    #[test]
+    fn run_until_cycle_limit() {
+        // (LOOP) @0, M=M+1, @0, 0;JMP (not a tight loop)
+        let mut computer =
+            load("0000000000000000\n1111110111001000\n0000000000000000\n1110101010000111");
+
+        assert_eq!(computer.run(Some(40)), StopReason::CycleLimit);
+        assert_eq!(computer.read_memory(0), 10);
+    }
+
+    #[test]
+    fn run_with_frames_until_cycle_limit() {
+        // (LOOP) @0, M=M+1, @0, 0;JMP
+        let mut computer =
+            load("0000000000000000\n1111110111001000\n0000000000000000\n1110101010000111");
+
+        let mut frames: Vec<i16> = Vec::new();
+        let reason = computer.run_with_frames(Some(40), 16, |computer| {
+            frames.push(computer.read_memory(0));
+        });
+
+        // two full frames and the last one at the cycle limit
+        assert_eq!(reason, StopReason::CycleLimit);
+        assert_eq!(frames, vec![4, 8, 10]);
+    }
+
+    #[test]
+    fn run_with_empty_frames() {
+        // (LOOP) @0, M=M+1, @0, 0;JMP
+        let mut computer =
+            load("0000000000000000\n1111110111001000\n0000000000000000\n1110101010000111");
+
+        // frames of zero cycles run one cycle each instead of looping forever
+        let mut frames: usize = 0;
+        let reason = computer.run_with_frames(Some(8), 0, |_| frames += 1);
+
+        assert_eq!(reason, StopReason::CycleLimit);
+        assert_eq!(frames, 8);
+        assert_eq!(computer.read_memory(0), 2);
+    }
+
+    #[test]
+    fn run_with_keyboard_script() {
+        // (LOOP) @24576, D=M, @0, M=D, @0, 0;JMP
+        let mut computer = load(
+            "0110000000000000\n1111110000010000\n0000000000000000\n1110001100001000\n0000000000000000\n1110101010000111",
+        );
+        computer.keyboard = Some(KeyboardScript::parse("6 K\n18 RELEASE"));
+
+        computer.run(Some(12));
+        assert_eq!(computer.read_memory(0), 75);
+
+        computer.run(Some(12));
+        assert_eq!(computer.read_memory(0), 0);
+
+        // the script starts again after a reset
+        computer.reset();
+        computer.run(Some(12));
+        assert_eq!(computer.read_memory(0), 75);
+    }
+
+    #[test]
+    fn step_back_restores_registers_and_memory() {
+        // @3, D=A, @0, M=D+1, AM=M-1
+        let mut computer = load(
+            "0000000000000011\n1110110000010000\n0000000000000000\n1110011111001000\n1111110010101000",
+        );
+        computer.trace = Some(Trace::new());
+
+        computer.run(Some(5));
+        assert_eq!(computer.read_memory(0), 3);
+
+        assert!(computer.step_back());
+        assert_eq!(computer.read_memory(0), 4);
+        assert_eq!((computer.a, computer.d, computer.pc), (0, 3, 4));
+        assert_eq!(computer.cycles, 4);
+
+        while computer.step_back() {}
+        assert_eq!(computer.read_memory(0), 0);
+        assert_eq!((computer.a, computer.d, computer.pc), (0, 0, 0));
+        assert_eq!(computer.cycles, 0);
+    }
+
+    #[test]
+    fn step_back_replays_keyboard_script() {
+        // (LOOP) @24576, D=M, @0, M=D, @0, 0;JMP
+        let mut computer = load(
+            "0110000000000000\n1111110000010000\n0000000000000000\n1110001100001000\n0000000000000000\n1110101010000111",
+        );
+        computer.keyboard = Some(KeyboardScript::parse("6 K"));
+        computer.trace = Some(Trace::new());
+
+        computer.run(Some(12));
+        assert_eq!(computer.read_memory(0), 75);
+
+        for _ in 0..12 {
+            computer.step_back();
+        }
+        assert_eq!(computer.read_memory(KBD_ADDRESS), 0);
+        assert_eq!(computer.read_memory(0), 0);
+
+        // the key is pressed again when the same cycles are executed
+        computer.run(Some(12));
+        assert_eq!(computer.read_memory(0), 75);
+    }
+
+    #[test]
+    fn step_back_keeps_device_writes() {
+        let mut computer = Computer::new();
+        computer.load_rom(
+            &crate::loader::assemble("@24577\nM=-1\n@16\nM=1", &CodeConfiguration::default())
+                .unwrap(),
+        );
+        computer.trace = Some(Trace::new());
+
+        let timer = computer.devices.map(24577, 1, Timer::new(1000)).unwrap();
+
+        computer.run(None);
+        assert_eq!(timer.borrow().count, -1);
+
+        while computer.step_back() {}
+
+        // the RAM is restored but the device is not written again
+        assert_eq!(computer.read_memory(16), 0);
+        assert_eq!(timer.borrow().count, -1);
+        assert_eq!(computer.pc, 0);
+    }
+
+    #[test]
+    fn step_back_restores_screen() {
+        let mut computer = Computer::new();
+        computer.load_rom(
+            &crate::loader::assemble("@16384\nM=-1\nM=1", &CodeConfiguration::default()).unwrap(),
+        );
+        computer.trace = Some(Trace::new());
+
+        computer.run(None);
+        assert_eq!(computer.screen()[0], 1);
+
+        computer.step_back();
+        assert_eq!(computer.screen()[0], -1);
+
+        computer.step_back();
+        assert_eq!(computer.screen()[0], 0);
+    }
+
+    #[test]
+    fn clone_copies_screen_and_keyboard() {
+        let mut computer = Computer::new();
+        computer.write_memory(SCREEN_ADDRESS, -1);
+        computer.set_key(75);
+
+        let clone = computer.clone();
+        computer.write_memory(SCREEN_ADDRESS, 0);
+
+        assert_eq!(clone.peek_memory(SCREEN_ADDRESS), -1);
+        assert_eq!(clone.peek_memory(KBD_ADDRESS), 75);
+        assert_ne!(clone, computer);
+    }
+
+    #[test]
+    fn step_back_without_trace() {
+        let mut computer = load("0000000000000011");
+
+        computer.step();
+
+        assert!(!computer.step_back());
+        assert_eq!(computer.pc, 1);
+    }
+
+    #[test]
+    fn set_key_writes_keyboard_register() {
+        let mut computer = Computer::new();
+
+        computer.set_key(75);
+
+        assert_eq!(computer.read_memory(KBD_ADDRESS), 75);
+        assert_eq!(computer.screen().len(), SCREEN_SIZE);
+    }
+}
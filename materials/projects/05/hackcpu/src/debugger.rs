@@ -0,0 +1,729 @@
+use std::collections::BTreeSet;
+
+use asm2hack::code::disassemble;
+
+use crate::computer::{Computer, ROM_SIZE};
+use crate::loader::ProgramSymbols;
+use crate::monitor::{Invariant, Monitor, WatchAction};
+use crate::snapshot::Snapshot;
+use crate::trace::Trace;
+
+/// pointers of the virtual machine shown with the registers
+const POINTERS: [&str; 5] = ["SP", "LCL", "ARG", "THIS", "THAT"];
+
+/// number of instructions shown by `list` when no count is given
+const LIST_SIZE: u16 = 10;
+
+/// number of executed instructions kept to step backwards
+const TRACE_LIMIT: usize = 1_000_000;
+
+/// number of writes shown by `writes` when no count is given
+const WRITES_SIZE: usize = 10;
+
+pub const HELP: &str = "\
+step [n]                 execute the next n instructions (s)
+back [n]                 undo the last n instructions (bs)
+continue [cycles]        run until a breakpoint or the program stops (c)
+break <address|label>    add a breakpoint (b)
+delete <address|label>   remove a breakpoint (d)
+breakpoints              list the breakpoints
+registers                show A, D, PC and the pointers SP, LCL, ARG, THIS, THAT (r)
+print <address|symbol> [n]  show n words of RAM (p)
+set <A|D|PC|address|symbol> <value>  change a register or a word of RAM
+writes <address|symbol> [n]  show the last n instructions that wrote a word of RAM (w)
+watch <address|symbol> [log]  stop (or only log) when a word of RAM changes
+invariant <symbol> [not] in <min>..<max>  stop when a write breaks the invariant
+list [address|label] [n] disassemble n instructions, around PC by default (l)
+save <file>              save a snapshot of the registers and the memory
+restore <file>           restore a snapshot saved on the same program
+reset                    reset the CPU (the memory keeps its content)
+help                     show this help (h)
+quit                     leave the debugger (q)";
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum DebuggerCommand {
+    Step(u64),
+    Back(u64),
+    Continue(Option<u64>),
+    Break(String),
+    Delete(String),
+    Breakpoints,
+    Registers,
+    Print(String, u16),
+    Set(String, i16),
+    Writes(String, usize),
+    Watch(String, WatchAction),
+    Invariant(String),
+    List(Option<String>, u16),
+    Save(String),
+    Restore(String),
+    Reset,
+    Help,
+    Quit,
+}
+
+/// Parses a command typed in the debugger
+///
+/// # Arguments
+///
+/// * `line` - The command and its arguments separated by whitespace
+pub fn parse_command(line: &str) -> Result<DebuggerCommand, String> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    let invalid = || format!("Invalid command: {}", line.trim());
+
+    let count = |value: Option<&&str>| -> Result<u64, String> {
+        match value {
+            Some(value) => value.parse::<u64>().map_err(|_| invalid()),
+            None => Ok(1),
+        }
+    };
+
+    let command = match parts.as_slice() {
+        ["step" | "s", rest @ ..] if rest.len() <= 1 => DebuggerCommand::Step(count(rest.first())?),
+        ["back" | "bs", rest @ ..] if rest.len() <= 1 => {
+            DebuggerCommand::Back(count(rest.first())?)
+        }
+        ["continue" | "c"] => DebuggerCommand::Continue(None),
+        ["continue" | "c", cycles] => DebuggerCommand::Continue(Some(count(Some(cycles))?)),
+        ["break" | "b", target] => DebuggerCommand::Break(target.to_string()),
+        ["delete" | "d", target] => DebuggerCommand::Delete(target.to_string()),
+        ["breakpoints"] => DebuggerCommand::Breakpoints,
+        ["registers" | "r"] => DebuggerCommand::Registers,
+        ["print" | "p", target] => DebuggerCommand::Print(target.to_string(), 1),
+        ["print" | "p", target, words] => {
+            let words = words.parse::<u16>().map_err(|_| invalid())?;
+            DebuggerCommand::Print(target.to_string(), words)
+        }
+        ["set", target, value] => {
+            let value = value.parse::<i16>().map_err(|_| invalid())?;
+            DebuggerCommand::Set(target.to_string(), value)
+        }
+        ["writes" | "w", target] => DebuggerCommand::Writes(target.to_string(), WRITES_SIZE),
+        ["writes" | "w", target, size] => {
+            let size = size.parse::<usize>().map_err(|_| invalid())?;
+            DebuggerCommand::Writes(target.to_string(), size)
+        }
+        ["watch", target] => DebuggerCommand::Watch(target.to_string(), WatchAction::Break),
+        ["watch", target, "log"] => DebuggerCommand::Watch(target.to_string(), WatchAction::Log),
+        ["invariant", rest @ ..] if !rest.is_empty() => DebuggerCommand::Invariant(rest.join(" ")),
+        ["list" | "l"] => DebuggerCommand::List(None, LIST_SIZE),
+        ["list" | "l", target] => DebuggerCommand::List(Some(target.to_string()), LIST_SIZE),
+        ["list" | "l", target, size] => {
+            let size = size.parse::<u16>().map_err(|_| invalid())?;
+            DebuggerCommand::List(Some(target.to_string()), size)
+        }
+        ["save", file] => DebuggerCommand::Save(file.to_string()),
+        ["restore", file] => DebuggerCommand::Restore(file.to_string()),
+        ["reset"] => DebuggerCommand::Reset,
+        ["help" | "h"] => DebuggerCommand::Help,
+        ["quit" | "q" | "exit"] => DebuggerCommand::Quit,
+        _ => return Err(invalid()),
+    };
+
+    Ok(command)
+}
+
+pub struct Debugger {
+    pub computer: Computer,
+    pub symbols: ProgramSymbols,
+    /// ROM addresses where `continue` stops
+    pub breakpoints: BTreeSet<u16>,
+}
+
+impl Debugger {
+    /// Creates a debugger for a computer, the executed instructions are
+    /// recorded (unless a trace is already set) to be able to step backwards
+    pub fn new(mut computer: Computer, symbols: ProgramSymbols) -> Self {
+        if computer.trace.is_none() {
+            computer.trace = Some(Trace::with_limit(TRACE_LIMIT));
+        }
+
+        Self {
+            computer,
+            symbols,
+            breakpoints: BTreeSet::new(),
+        }
+    }
+
+    /// Resolves a ROM address given as a number or as a label
+    fn rom_address(&self, target: &str) -> Result<u16, String> {
+        match target.parse::<u16>() {
+            Ok(address) if (address as usize) < ROM_SIZE => Ok(address),
+            Ok(_) => Err(format!("Invalid ROM address: {}", target)),
+            Err(_) => self
+                .symbols
+                .labels
+                .get(target)
+                .copied()
+                .ok_or_else(|| format!("Unknown label: {}", target)),
+        }
+    }
+
+    /// Resolves a RAM address given as a number, `RAM[n]` or a symbol
+    fn ram_address(&self, target: &str) -> Result<u16, String> {
+        self.symbols.ram_address(target)
+    }
+
+    /// Returns the labels pointing to a ROM address, sorted by name
+    fn labels_at(&self, address: u16) -> Vec<&str> {
+        let mut labels: Vec<&str> = self
+            .symbols
+            .labels
+            .iter()
+            .filter(|(_, label_address)| **label_address == address)
+            .map(|(label, _)| label.as_str())
+            .collect();
+
+        labels.sort();
+        labels
+    }
+
+    /// Disassembles the instruction at a ROM address, marking the program
+    /// counter with `>` and breakpoints with `*`
+    pub fn instruction_line(&self, address: u16) -> String {
+        let instruction = self.computer.rom[address as usize];
+
+        let marker = match (
+            self.breakpoints.contains(&address),
+            self.computer.pc == address,
+        ) {
+            (true, true) => "*>",
+            (false, true) => " >",
+            (true, false) => "* ",
+            (false, false) => "  ",
+        };
+
+        let assembly = disassemble(instruction).unwrap_or(format!("?? {:016b}", instruction));
+
+        match self.symbols.references.get(&address) {
+            Some(symbol) => format!("{} {:5}  {:<20}// {}", marker, address, assembly, symbol),
+            None => format!("{} {:5}  {}", marker, address, assembly),
+        }
+    }
+
+    /// Disassembles a range of the ROM, labels are shown before the
+    /// instruction they point to
+    fn listing(&self, start: u16, size: u16) -> String {
+        let end = (start as usize + size as usize).min(ROM_SIZE) as u16;
+        let mut lines: Vec<String> = Vec::new();
+
+        for address in start..end {
+            for label in self.labels_at(address) {
+                lines.push(format!("         ({})", label));
+            }
+
+            lines.push(self.instruction_line(address));
+        }
+
+        lines.join("\n")
+    }
+
+    /// Describes why the program cannot run further, if it stopped
+    fn stopped(&self) -> Option<String> {
+        if self.computer.is_halted() {
+            Some(format!("program halted at {}", self.computer.pc))
+        } else if self.computer.pc as usize >= self.computer.program_size {
+            Some(String::from("reached the end of the program"))
+        } else {
+            None
+        }
+    }
+
+    /// Executes the next instruction, checking the watchpoints
+    ///
+    /// # Returns
+    ///
+    /// * true when a watchpoint or an invariant asks to stop
+    fn step(&mut self) -> bool {
+        self.computer.step();
+
+        self.computer
+            .monitor
+            .as_mut()
+            .is_some_and(|monitor| monitor.take_stop())
+    }
+
+    /// Describes the watch events recorded since a number of events,
+    /// followed by the current instruction
+    fn watch_report(&self, seen: usize) -> String {
+        let mut lines: Vec<String> = match &self.computer.monitor {
+            Some(monitor) => monitor.events[seen..]
+                .iter()
+                .map(|event| event.describe(&self.symbols))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        lines.push(self.instruction_line(self.computer.pc));
+        lines.join("\n")
+    }
+
+    fn registers(&self) -> String {
+        let computer = &self.computer;
+
+        let pointers: Vec<String> = POINTERS
+            .iter()
+            .enumerate()
+            .map(|(address, pointer)| {
+                format!("{}: {}", pointer, computer.peek_memory(address as u16))
+            })
+            .collect();
+
+        format!(
+            "A: {}  D: {}  PC: {}  cycles: {}\n{}",
+            computer.a,
+            computer.d,
+            computer.pc,
+            computer.cycles,
+            pointers.join("  ")
+        )
+    }
+
+    /// Executes a debugger command and returns the text to show
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command to execute (`Quit` is handled by the caller)
+    pub fn execute(&mut self, command: &DebuggerCommand) -> Result<String, String> {
+        let seen = self
+            .computer
+            .monitor
+            .as_ref()
+            .map_or(0, |monitor| monitor.events.len());
+
+        let output = match command {
+            DebuggerCommand::Step(count) => {
+                for _ in 0..*count {
+                    if let Some(reason) = self.stopped() {
+                        return Ok(reason);
+                    }
+
+                    if self.step() {
+                        break;
+                    }
+                }
+
+                self.watch_report(seen)
+            }
+            DebuggerCommand::Back(count) => {
+                for _ in 0..*count {
+                    if !self.computer.step_back() {
+                        return Ok(format!(
+                            "no instruction left to undo\n{}",
+                            self.instruction_line(self.computer.pc)
+                        ));
+                    }
+                }
+
+                self.instruction_line(self.computer.pc)
+            }
+            DebuggerCommand::Continue(max_cycles) => {
+                let mut executed: u64 = 0;
+
+                // always move past the breakpoint of the current instruction
+                loop {
+                    if let Some(reason) = self.stopped() {
+                        return Ok(reason);
+                    }
+
+                    if max_cycles.is_some_and(|max_cycles| executed >= max_cycles) {
+                        break;
+                    }
+
+                    let watched = self.step();
+                    executed += 1;
+
+                    if watched || self.breakpoints.contains(&self.computer.pc) {
+                        break;
+                    }
+                }
+
+                self.watch_report(seen)
+            }
+            DebuggerCommand::Break(target) => {
+                let address = self.rom_address(target)?;
+                self.breakpoints.insert(address);
+                format!("breakpoint at {}", address)
+            }
+            DebuggerCommand::Delete(target) => {
+                let address = self.rom_address(target)?;
+
+                if !self.breakpoints.remove(&address) {
+                    return Err(format!("No breakpoint at {}", address));
+                }
+
+                format!("breakpoint deleted at {}", address)
+            }
+            DebuggerCommand::Breakpoints => {
+                if self.breakpoints.is_empty() {
+                    String::from("no breakpoints")
+                } else {
+                    let lines: Vec<String> = self
+                        .breakpoints
+                        .iter()
+                        .map(|address| self.instruction_line(*address))
+                        .collect();
+
+                    lines.join("\n")
+                }
+            }
+            DebuggerCommand::Registers => self.registers(),
+            DebuggerCommand::Print(target, words) => {
+                let start = self.ram_address(target)?;
+                let symbol = self.symbols.variables.contains_key(target.as_str());
+
+                let lines: Vec<String> = (0..*words)
+                    .map(|offset| {
+                        let address = start.wrapping_add(offset);
+                        let value = self.computer.peek_memory(address);
+
+                        if offset == 0 && symbol {
+                            format!("RAM[{}] ({}): {}", address, target, value)
+                        } else {
+                            format!("RAM[{}]: {}", address, value)
+                        }
+                    })
+                    .collect();
+
+                lines.join("\n")
+            }
+            DebuggerCommand::Set(target, value) => {
+                match target.as_str() {
+                    "A" => self.computer.a = *value,
+                    "D" => self.computer.d = *value,
+                    "PC" => self.computer.pc = self.rom_address(&value.to_string())?,
+                    _ => {
+                        let address = self.ram_address(target)?;
+                        self.computer.write_memory(address, *value);
+                    }
+                }
+
+                format!("{} = {}", target, value)
+            }
+            DebuggerCommand::Writes(target, size) => {
+                let address = self.ram_address(target)?;
+
+                let writes = match &self.computer.trace {
+                    Some(trace) => trace.writes_to(address, *size),
+                    None => Vec::new(),
+                };
+
+                if writes.is_empty() {
+                    format!("no writes to RAM[{}] in the trace", address)
+                } else {
+                    let lines: Vec<String> = writes
+                        .iter()
+                        .map(|(entry, write)| {
+                            format!(
+                                "cycle {}: RAM[{}] {} -> {}\n{}",
+                                entry.cycle,
+                                address,
+                                write.previous,
+                                write.value,
+                                self.instruction_line(entry.pc)
+                            )
+                        })
+                        .collect();
+
+                    lines.join("\n")
+                }
+            }
+            DebuggerCommand::Watch(target, action) => {
+                let monitor = self.computer.monitor.get_or_insert_with(Monitor::new);
+                monitor.watch(target, *action, &self.symbols)?;
+
+                let address = monitor.watchpoints.last().unwrap().address;
+                format!("watchpoint on RAM[{}] ({})", address, target)
+            }
+            DebuggerCommand::Invariant(text) => {
+                let invariant = Invariant::parse(text, &self.symbols)?;
+                let output = format!(
+                    "invariant on RAM[{}]: {}",
+                    invariant.address, invariant.text
+                );
+
+                self.computer
+                    .monitor
+                    .get_or_insert_with(Monitor::new)
+                    .invariants
+                    .push(invariant);
+
+                output
+            }
+            DebuggerCommand::List(target, size) => {
+                let start = match target {
+                    Some(target) => self.rom_address(target)?,
+                    None => self.computer.pc,
+                };
+
+                self.listing(start, *size)
+            }
+            DebuggerCommand::Save(file) => {
+                std::fs::write(file, Snapshot::capture(&self.computer).to_text())
+                    .map_err(|error| format!("Cannot save snapshot {}: {}", file, error))?;
+
+                format!("snapshot saved at cycle {}: {}", self.computer.cycles, file)
+            }
+            DebuggerCommand::Restore(file) => {
+                let input = std::fs::read_to_string(file)
+                    .map_err(|error| format!("Cannot read snapshot {}: {}", file, error))?;
+                Snapshot::parse(&input)?.restore(&mut self.computer)?;
+
+                self.instruction_line(self.computer.pc)
+            }
+            DebuggerCommand::Reset => {
+                self.computer.reset();
+                self.instruction_line(self.computer.pc)
+            }
+            DebuggerCommand::Help => String::from(HELP),
+            DebuggerCommand::Quit => String::new(),
+        };
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::assemble_with_symbols;
+    use asm2hack::code::CodeConfiguration;
+
+    const PROGRAM: &str =
+        "@i\nM=1\n(LOOP)\n@i\nM=M+1\nD=M\n@3\nD=D-A\n@LOOP\nD;JLT\n(END)\n@END\n0;JMP";
+
+    fn debugger() -> Debugger {
+        let (instructions, symbols) =
+            assemble_with_symbols(PROGRAM, &CodeConfiguration::default()).unwrap();
+
+        let mut computer = Computer::new();
+        computer.load_rom(&instructions);
+
+        Debugger::new(computer, symbols)
+    }
+
+    fn run(debugger: &mut Debugger, line: &str) -> Result<String, String> {
+        debugger.execute(&parse_command(line)?)
+    }
+
+    #[test]
+    fn parse_debugger_commands() {
+        assert_eq!(parse_command("s"), Ok(DebuggerCommand::Step(1)));
+        assert_eq!(parse_command("step 5"), Ok(DebuggerCommand::Step(5)));
+        assert_eq!(parse_command("c"), Ok(DebuggerCommand::Continue(None)));
+        assert_eq!(
+            parse_command("b LOOP"),
+            Ok(DebuggerCommand::Break(String::from("LOOP")))
+        );
+        assert_eq!(
+            parse_command("p SP 3"),
+            Ok(DebuggerCommand::Print(String::from("SP"), 3))
+        );
+        assert_eq!(
+            parse_command("set D -1"),
+            Ok(DebuggerCommand::Set(String::from("D"), -1))
+        );
+        assert_eq!(parse_command("l"), Ok(DebuggerCommand::List(None, 10)));
+        assert_eq!(
+            parse_command("step x"),
+            Err(String::from("Invalid command: step x"))
+        );
+        assert_eq!(
+            parse_command("jump"),
+            Err(String::from("Invalid command: jump"))
+        );
+    }
+
+    #[test]
+    fn step_through_instructions() {
+        let mut debugger = debugger();
+
+        assert_eq!(
+            run(&mut debugger, "step 2"),
+            Ok(String::from(" >     2  @16                 // i"))
+        );
+        assert_eq!(debugger.computer.read_memory(16), 1);
+        assert_eq!(debugger.computer.cycles, 2);
+    }
+
+    #[test]
+    fn continue_until_breakpoint() {
+        let mut debugger = debugger();
+
+        assert_eq!(
+            run(&mut debugger, "break LOOP"),
+            Ok(String::from("breakpoint at 2"))
+        );
+        assert_eq!(
+            run(&mut debugger, "c"),
+            Ok(String::from("*>     2  @16                 // i"))
+        );
+        assert_eq!(
+            run(&mut debugger, "continue"),
+            Ok(String::from("*>     2  @16                 // i"))
+        );
+        assert_eq!(debugger.computer.read_memory(16), 2);
+
+        // without breakpoints the program runs until it halts
+        assert_eq!(
+            run(&mut debugger, "delete 2"),
+            Ok(String::from("breakpoint deleted at 2"))
+        );
+        assert_eq!(
+            run(&mut debugger, "c"),
+            Ok(String::from("program halted at 9"))
+        );
+        assert_eq!(debugger.computer.read_memory(16), 3);
+    }
+
+    #[test]
+    fn stop_on_watchpoints_and_invariants() {
+        let mut debugger = debugger();
+
+        assert_eq!(
+            run(&mut debugger, "watch i"),
+            Ok(String::from("watchpoint on RAM[16] (i)"))
+        );
+        assert_eq!(
+            run(&mut debugger, "c"),
+            Ok(String::from(
+                "i changed: RAM[16] 0 -> 1 at PC 1 (line 2), cycle 1\n >     2  @16                 // i"
+            ))
+        );
+
+        let mut debugger = self::debugger();
+
+        assert_eq!(
+            run(&mut debugger, "invariant i in 0..2"),
+            Ok(String::from("invariant on RAM[16]: i in 0..2"))
+        );
+        assert_eq!(
+            run(&mut debugger, "c").unwrap().lines().next(),
+            Some("invariant violated (i in 0..2): RAM[16] 2 -> 3 at PC 3 (line 5), cycle 10")
+        );
+        assert_eq!(
+            run(&mut debugger, "invariant i 0..2"),
+            Err(String::from("Invalid invariant: i 0..2"))
+        );
+    }
+
+    #[test]
+    fn step_backwards_through_trace() {
+        let mut debugger = debugger();
+
+        run(&mut debugger, "c").unwrap();
+        assert_eq!(debugger.computer.read_memory(16), 3);
+
+        assert_eq!(
+            run(&mut debugger, "back 4"),
+            Ok(String::from(" >     5  @3"))
+        );
+        assert_eq!(debugger.computer.read_memory(16), 3);
+        assert_eq!(debugger.computer.d, 3);
+
+        // going back past the increment restores the previous value
+        run(&mut debugger, "bs 3").unwrap();
+        assert_eq!(debugger.computer.read_memory(16), 2);
+
+        assert_eq!(
+            run(&mut debugger, "back 100"),
+            Ok(String::from(
+                "no instruction left to undo\n >     0  @16                 // i"
+            ))
+        );
+        assert_eq!(debugger.computer.cycles, 0);
+    }
+
+    #[test]
+    fn find_last_writes() {
+        let mut debugger = debugger();
+
+        run(&mut debugger, "c").unwrap();
+
+        assert_eq!(
+            run(&mut debugger, "writes i 2"),
+            Ok(String::from(
+                "cycle 10: RAM[16] 2 -> 3\n       3  M=M+1\ncycle 3: RAM[16] 1 -> 2\n       3  M=M+1"
+            ))
+        );
+        assert_eq!(
+            run(&mut debugger, "w SP"),
+            Ok(String::from("no writes to RAM[0] in the trace"))
+        );
+    }
+
+    #[test]
+    fn save_and_restore_snapshots() {
+        let mut debugger = debugger();
+        let file = std::env::temp_dir().join("hackcpu-debugger.snapshot");
+        let file = file.to_string_lossy();
+
+        run(&mut debugger, "step 4").unwrap();
+        assert_eq!(
+            run(&mut debugger, &format!("save {}", file)),
+            Ok(format!("snapshot saved at cycle 4: {}", file))
+        );
+
+        run(&mut debugger, "c").unwrap();
+        assert_eq!(
+            run(&mut debugger, &format!("restore {}", file)),
+            Ok(String::from(" >     4  D=M"))
+        );
+        assert_eq!(debugger.computer.read_memory(16), 2);
+        assert_eq!(debugger.computer.cycles, 4);
+
+        std::fs::remove_file(file.as_ref()).unwrap();
+    }
+
+    #[test]
+    fn inspect_registers_and_memory() {
+        let mut debugger = debugger();
+
+        run(&mut debugger, "set SP 256").unwrap();
+        run(&mut debugger, "set RAM[1] 300").unwrap();
+        run(&mut debugger, "set D -7").unwrap();
+        run(&mut debugger, "step").unwrap();
+
+        assert_eq!(
+            run(&mut debugger, "registers"),
+            Ok(String::from(
+                "A: 16  D: -7  PC: 1  cycles: 1\nSP: 256  LCL: 300  ARG: 0  THIS: 0  THAT: 0"
+            ))
+        );
+        assert_eq!(
+            run(&mut debugger, "print SP 2"),
+            Ok(String::from("RAM[0] (SP): 256\nRAM[1]: 300"))
+        );
+        assert_eq!(
+            run(&mut debugger, "p i"),
+            Ok(String::from("RAM[16] (i): 0"))
+        );
+        assert_eq!(
+            run(&mut debugger, "p j"),
+            Err(String::from("Unknown symbol: j"))
+        );
+        assert_eq!(
+            run(&mut debugger, "b FOO"),
+            Err(String::from("Unknown label: FOO"))
+        );
+    }
+
+    #[test]
+    fn list_disassembled_instructions() {
+        let mut debugger = debugger();
+        run(&mut debugger, "b 8").unwrap();
+
+        assert_eq!(
+            run(&mut debugger, "list LOOP 3"),
+            Ok(String::from(
+                "         (LOOP)\n       2  @16                 // i\n       3  M=M+1\n       4  D=M"
+            ))
+        );
+        assert_eq!(
+            run(&mut debugger, "l 7 4"),
+            Ok(String::from(
+                "       7  @2                  // LOOP\n*      8  D;JLT\n         (END)\n       9  @9                  // END\n      10  0;JMP"
+            ))
+        );
+    }
+}
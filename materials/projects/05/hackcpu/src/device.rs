@@ -0,0 +1,432 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::computer::{KBD_ADDRESS, RAM_SIZE, SCREEN_ADDRESS, SCREEN_SIZE};
+
+/// Peripheral mapped in the I/O memory (from the screen), addresses given
+/// to the device are absolute RAM addresses
+pub trait Device {
+    /// Returns the value of a word of the device when the CPU reads it
+    fn read(&mut self, address: u16) -> i16;
+
+    /// Returns the value of a word without the side effects of a read
+    /// (e.g. for the debugger), None when it cannot be known without them
+    fn peek(&self, _address: u16) -> Option<i16> {
+        None
+    }
+
+    /// Stores a word written by the CPU
+    fn write(&mut self, address: u16, value: i16);
+
+    /// Puts back a word when a write is undone (stepping back or restoring
+    /// a snapshot), devices with side effects keep their state by default
+    fn restore(&mut self, _address: u16, _value: i16) {}
+
+    /// Called after each instruction with the number of cycles executed
+    fn tick(&mut self, _cycle: u64) {}
+}
+
+/// Stock memory-mapped screen, 512x256 pixels with 16 pixels per word
+#[derive(Debug, PartialEq, Clone)]
+pub struct Screen {
+    pub words: Vec<i16>,
+}
+
+impl Default for Screen {
+    fn default() -> Self {
+        Self {
+            words: vec![0; SCREEN_SIZE],
+        }
+    }
+}
+
+impl Device for Screen {
+    fn read(&mut self, address: u16) -> i16 {
+        self.words[(address - SCREEN_ADDRESS) as usize]
+    }
+
+    fn peek(&self, address: u16) -> Option<i16> {
+        Some(self.words[(address - SCREEN_ADDRESS) as usize])
+    }
+
+    fn write(&mut self, address: u16, value: i16) {
+        self.words[(address - SCREEN_ADDRESS) as usize] = value;
+    }
+
+    fn restore(&mut self, address: u16, value: i16) {
+        self.write(address, value);
+    }
+}
+
+/// Stock keyboard register, holds the code of the key currently pressed
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Keyboard {
+    pub key: i16,
+}
+
+impl Device for Keyboard {
+    fn read(&mut self, _address: u16) -> i16 {
+        self.key
+    }
+
+    fn peek(&self, _address: u16) -> Option<i16> {
+        Some(self.key)
+    }
+
+    fn write(&mut self, _address: u16, value: i16) {
+        self.key = value;
+    }
+
+    fn restore(&mut self, address: u16, value: i16) {
+        self.write(address, value);
+    }
+}
+
+pub struct MappedDevice {
+    /// first RAM address of the device
+    pub start: u16,
+    /// number of words of the device
+    pub size: u16,
+    /// shared with the code that registered the device to inspect it
+    pub device: Rc<RefCell<dyn Device>>,
+}
+
+/// Devices of the memory map, the stock screen and keyboard are always
+/// mapped and the addresses that no device handles use the words of the RAM
+pub struct Devices {
+    pub screen: Rc<RefCell<Screen>>,
+    pub keyboard: Rc<RefCell<Keyboard>>,
+    /// every mapped device, the screen and the keyboard first
+    pub mapped: Vec<MappedDevice>,
+}
+
+impl Default for Devices {
+    fn default() -> Self {
+        Self::with_stock(Screen::default(), Keyboard::default())
+    }
+}
+
+/// A clone copies the screen and the keyboard, the other devices are not
+/// cloned since they may own resources (e.g. the input of a console)
+impl Clone for Devices {
+    fn clone(&self) -> Self {
+        Self::with_stock(self.screen.borrow().clone(), self.keyboard.borrow().clone())
+    }
+}
+
+impl fmt::Debug for Devices {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_list()
+            .entries(
+                self.mapped
+                    .iter()
+                    .map(|mapped| mapped.start..mapped.start + mapped.size),
+            )
+            .finish()
+    }
+}
+
+/// Compares the words of the screen and the keyboard, the other devices
+/// are left out like in a clone
+impl PartialEq for Devices {
+    fn eq(&self, other: &Self) -> bool {
+        *self.screen.borrow() == *other.screen.borrow()
+            && *self.keyboard.borrow() == *other.keyboard.borrow()
+    }
+}
+
+impl Devices {
+    /// Creates the stock memory map with the given screen and keyboard
+    fn with_stock(screen: Screen, keyboard: Keyboard) -> Self {
+        let screen = Rc::new(RefCell::new(screen));
+        let keyboard = Rc::new(RefCell::new(keyboard));
+
+        Self {
+            mapped: vec![
+                MappedDevice {
+                    start: SCREEN_ADDRESS,
+                    size: SCREEN_SIZE as u16,
+                    device: screen.clone(),
+                },
+                MappedDevice {
+                    start: KBD_ADDRESS,
+                    size: 1,
+                    device: keyboard.clone(),
+                },
+            ],
+            screen,
+            keyboard,
+        }
+    }
+
+    /// Checks if devices other than the screen and the keyboard are mapped
+    pub fn has_peripherals(&self) -> bool {
+        self.mapped.len() > 2
+    }
+
+    /// Maps a device over a free range of the I/O memory and returns a
+    /// handle to inspect it while the program runs
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - The first RAM address of the device (in the I/O memory)
+    /// * `size` - The number of words of the device
+    /// * `device` - The device to map
+    pub fn map<D: Device + 'static>(
+        &mut self,
+        start: u16,
+        size: u16,
+        device: D,
+    ) -> Result<Rc<RefCell<D>>, String> {
+        let end = start as usize + size as usize;
+
+        if start < SCREEN_ADDRESS || size == 0 || end > RAM_SIZE {
+            return Err(format!("Invalid device range: {}..{}", start, end));
+        }
+
+        if let Some(mapped) = self
+            .mapped
+            .iter()
+            .find(|mapped| start < mapped.start + mapped.size && mapped.start < end as u16)
+        {
+            return Err(format!(
+                "Device range {}..{} overlaps {}..{}",
+                start,
+                end,
+                mapped.start,
+                mapped.start + mapped.size
+            ));
+        }
+
+        let device = Rc::new(RefCell::new(device));
+
+        self.mapped.push(MappedDevice {
+            start,
+            size,
+            device: device.clone(),
+        });
+
+        Ok(device)
+    }
+
+    /// Returns the device mapped at a RAM address, if any
+    pub fn find(&self, address: u16) -> Option<&Rc<RefCell<dyn Device>>> {
+        self.mapped
+            .iter()
+            .find(|mapped| (mapped.start..mapped.start + mapped.size).contains(&address))
+            .map(|mapped| &mapped.device)
+    }
+
+    /// Reads a word of the I/O memory through the device mapped at its
+    /// address, the words without device come from the RAM
+    pub fn read(&self, ram: &[i16], address: u16) -> i16 {
+        match self.find(address) {
+            Some(device) => device.borrow_mut().read(address),
+            None => ram[address as usize],
+        }
+    }
+
+    /// Writes a word of the I/O memory through the device mapped at its
+    /// address, the words without device go to the RAM
+    pub fn write(&self, ram: &mut [i16], address: u16, value: i16) {
+        match self.find(address) {
+            Some(device) => device.borrow_mut().write(address, value),
+            None => ram[address as usize] = value,
+        }
+    }
+
+    /// Lets every device advance after an instruction
+    pub fn tick(&self, cycle: u64) {
+        for mapped in self.mapped.iter() {
+            mapped.device.borrow_mut().tick(cycle);
+        }
+    }
+}
+
+/// Counter incremented every period of cycles, writing sets its value
+#[derive(Debug, PartialEq, Clone)]
+pub struct Timer {
+    /// number of cycles between two increments
+    pub period: u64,
+    pub count: i16,
+}
+
+impl Timer {
+    pub fn new(period: u64) -> Self {
+        Self {
+            period: period.max(1),
+            count: 0,
+        }
+    }
+}
+
+impl Device for Timer {
+    fn read(&mut self, _address: u16) -> i16 {
+        self.count
+    }
+
+    fn peek(&self, _address: u16) -> Option<i16> {
+        Some(self.count)
+    }
+
+    fn write(&mut self, _address: u16, value: i16) {
+        self.count = value;
+    }
+
+    fn tick(&mut self, cycle: u64) {
+        if cycle.is_multiple_of(self.period) {
+            self.count = self.count.wrapping_add(1);
+        }
+    }
+}
+
+/// Source of pseudo-random words (16-bit xorshift), each read returns a
+/// new word and writing a word sets the seed
+#[derive(Debug, PartialEq, Clone)]
+pub struct RandomSource {
+    pub state: u16,
+}
+
+impl RandomSource {
+    pub fn new(seed: u16) -> Self {
+        Self { state: seed.max(1) }
+    }
+
+    /// Returns the state that follows the current one
+    fn next(&self) -> u16 {
+        let mut state = self.state;
+
+        state ^= state << 7;
+        state ^= state >> 9;
+        state ^= state << 8;
+        state
+    }
+}
+
+impl Device for RandomSource {
+    fn read(&mut self, _address: u16) -> i16 {
+        self.state = self.next();
+        self.state as i16
+    }
+
+    /// The word the next read returns
+    fn peek(&self, _address: u16) -> Option<i16> {
+        Some(self.next() as i16)
+    }
+
+    fn write(&mut self, _address: u16, value: i16) {
+        self.state = (value as u16).max(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_mapped_devices() {
+        let mut devices = Devices::default();
+        devices.map(24577, 1, Timer::new(10)).unwrap();
+        devices.map(24578, 2, RandomSource::new(1)).unwrap();
+
+        assert!(devices.find(16383).is_none());
+        assert!(devices.find(16384).is_some());
+        assert!(devices.find(24576).is_some());
+        assert!(devices.find(24577).is_some());
+        assert!(devices.find(24579).is_some());
+        assert!(devices.find(24580).is_none());
+        assert_eq!(
+            format!("{:?}", devices),
+            "[16384..24576, 24576..24577, 24577..24578, 24578..24580]"
+        );
+    }
+
+    #[test]
+    fn dispatch_screen_and_keyboard() {
+        let devices = Devices::default();
+        let mut ram = vec![0; RAM_SIZE];
+
+        devices.write(&mut ram, 16385, -1);
+        devices.write(&mut ram, 24576, 75);
+        devices.write(&mut ram, 24577, 3);
+
+        assert_eq!(devices.screen.borrow().words[1], -1);
+        assert_eq!(devices.keyboard.borrow().key, 75);
+        assert_eq!(ram[16385], 0);
+        assert_eq!(ram[24577], 3);
+        assert_eq!(devices.read(&ram, 24576), 75);
+    }
+
+    #[test]
+    fn clone_screen_and_keyboard_only() {
+        let mut devices = Devices::default();
+        devices.map(24577, 1, Timer::new(10)).unwrap();
+        devices.screen.borrow_mut().words[0] = 1;
+
+        let clone = devices.clone();
+        devices.screen.borrow_mut().words[0] = 2;
+
+        assert_eq!(clone.screen.borrow().words[0], 1);
+        assert!(!clone.has_peripherals());
+        assert_ne!(clone, devices);
+    }
+
+    #[test]
+    fn reject_overlapping_devices() {
+        let mut devices = Devices::default();
+        devices.map(24578, 1, Timer::new(10)).unwrap();
+
+        assert_eq!(
+            devices.map(24577, 2, Timer::new(10)).err(),
+            Some(String::from(
+                "Device range 24577..24579 overlaps 24578..24579"
+            ))
+        );
+    }
+
+    #[test]
+    fn reject_devices_outside_io_memory() {
+        assert_eq!(
+            Devices::default().map(100, 1, Timer::new(10)).err(),
+            Some(String::from("Invalid device range: 100..101"))
+        );
+    }
+
+    #[test]
+    fn reject_devices_over_screen_and_keyboard() {
+        assert_eq!(
+            Devices::default().map(24575, 2, Timer::new(10)).err(),
+            Some(String::from(
+                "Device range 24575..24577 overlaps 16384..24576"
+            ))
+        );
+    }
+
+    #[test]
+    fn count_cycles_with_timer() {
+        let mut timer = Timer::new(3);
+
+        for cycle in 1..=10 {
+            timer.tick(cycle);
+        }
+        assert_eq!(timer.read(0), 3);
+
+        timer.write(0, -1);
+        assert_eq!(timer.read(0), -1);
+    }
+
+    #[test]
+    fn generate_random_words() {
+        let mut random = RandomSource::new(1);
+        let words: Vec<i16> = (0..3).map(|_| random.read(0)).collect();
+
+        assert_ne!(words[0], words[1]);
+        assert_ne!(words[1], words[2]);
+
+        random.write(0, 1);
+        assert_eq!(random.peek(0), Some(words[0]));
+        assert_eq!(random.read(0), words[0]);
+    }
+}
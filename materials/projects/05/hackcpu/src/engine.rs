@@ -0,0 +1,666 @@
+use crate::computer::{alu, shift, Computer, StopReason, RAM_SIZE, ROM_SIZE, SCREEN_ADDRESS};
+use crate::device::Devices;
+
+/// mask of the 15-bit addresses of the ROM and the RAM
+const ADDRESS_MASK: usize = 0x7FFF;
+
+/// destination bits of a C-instruction
+const DEST_A: u8 = 0b100;
+const DEST_D: u8 = 0b010;
+const DEST_M: u8 = 0b001;
+
+/// Operation of the ALU (or the shifter) decoded from the comp bits, the
+/// usual ones are computed directly and any other combination of control
+/// bits falls back to the bit-level ALU
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Comp {
+    Zero,
+    One,
+    MinusOne,
+    D,
+    Y,
+    NotD,
+    NotY,
+    NegD,
+    NegY,
+    DPlusOne,
+    YPlusOne,
+    DMinusOne,
+    YMinusOne,
+    DPlusY,
+    DMinusY,
+    YMinusD,
+    DAndY,
+    DOrY,
+    ShiftLeftD,
+    ShiftLeftY,
+    ShiftRightD,
+    ShiftRightY,
+    Alu(u16),
+}
+
+/// C-instruction decoded once, `Y` is the A register or M
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Compute {
+    pub comp: Comp,
+    /// the comp reads M instead of A
+    pub memory: bool,
+    /// destination bits (A, D, M)
+    pub dest: u8,
+    /// jump bits (lower than zero, zero, greater than zero)
+    pub jump: u8,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Operation {
+    /// A-instruction
+    Load(i16),
+    Compute(Compute),
+    /// tight loop such as `(END) @END 0;JMP`, the program halted
+    Halt,
+    /// past the last loaded instruction
+    End,
+    /// bits that do not encode a valid instruction
+    Invalid(u16),
+}
+
+impl Comp {
+    /// Decodes the comp bits of a C-instruction (regular or extended)
+    pub fn decode(instruction: u16) -> Option<Self> {
+        let control = (instruction >> 6) & 0b111111;
+
+        // the extended instruction set uses the prefix 101 instead of 111
+        if instruction & 0xE000 == 0xA000 {
+            let comp = match (control & 0b100000 != 0, control & 0b010000 != 0) {
+                (true, true) => Comp::ShiftLeftD,
+                (true, false) => Comp::ShiftLeftY,
+                (false, true) => Comp::ShiftRightD,
+                (false, false) => Comp::ShiftRightY,
+            };
+
+            return Some(comp);
+        }
+
+        if instruction & 0xE000 != 0xE000 {
+            return None;
+        }
+
+        let comp = match control {
+            0b101010 => Comp::Zero,
+            0b111111 => Comp::One,
+            0b111010 => Comp::MinusOne,
+            0b001100 => Comp::D,
+            0b110000 => Comp::Y,
+            0b001101 => Comp::NotD,
+            0b110001 => Comp::NotY,
+            0b001111 => Comp::NegD,
+            0b110011 => Comp::NegY,
+            0b011111 => Comp::DPlusOne,
+            0b110111 => Comp::YPlusOne,
+            0b001110 => Comp::DMinusOne,
+            0b110010 => Comp::YMinusOne,
+            0b000010 => Comp::DPlusY,
+            0b010011 => Comp::DMinusY,
+            0b000111 => Comp::YMinusD,
+            0b000000 => Comp::DAndY,
+            0b010101 => Comp::DOrY,
+            control => Comp::Alu(control),
+        };
+
+        Some(comp)
+    }
+
+    #[inline(always)]
+    fn compute(self, d: i16, y: i16) -> i16 {
+        match self {
+            Comp::Zero => 0,
+            Comp::One => 1,
+            Comp::MinusOne => -1,
+            Comp::D => d,
+            Comp::Y => y,
+            Comp::NotD => !d,
+            Comp::NotY => !y,
+            Comp::NegD => d.wrapping_neg(),
+            Comp::NegY => y.wrapping_neg(),
+            Comp::DPlusOne => d.wrapping_add(1),
+            Comp::YPlusOne => y.wrapping_add(1),
+            Comp::DMinusOne => d.wrapping_sub(1),
+            Comp::YMinusOne => y.wrapping_sub(1),
+            Comp::DPlusY => d.wrapping_add(y),
+            Comp::DMinusY => d.wrapping_sub(y),
+            Comp::YMinusD => y.wrapping_sub(d),
+            Comp::DAndY => d & y,
+            Comp::DOrY => d | y,
+            Comp::ShiftLeftD => shift(0b110000, d, y),
+            Comp::ShiftLeftY => shift(0b100000, d, y),
+            Comp::ShiftRightD => shift(0b010000, d, y),
+            Comp::ShiftRightY => shift(0b000000, d, y),
+            Comp::Alu(control) => alu(control, d, y),
+        }
+    }
+}
+
+/// Returns the jump bit matching the sign of the ALU output, without
+/// branches since the sign of the values is hard to predict
+#[inline(always)]
+fn sign(out: i16) -> u8 {
+    ((out < 0) as u8) << 2 | ((out == 0) as u8) << 1 | (out > 0) as u8
+}
+
+/// Memory of the computer split for the engine, the words before the
+/// screen are used directly and the I/O memory goes through the devices
+struct Memory<'a> {
+    ram: &'a mut [i16; RAM_SIZE],
+    devices: &'a Devices,
+}
+
+impl Memory<'_> {
+    #[inline(always)]
+    fn read(&self, address: usize) -> i16 {
+        if address < SCREEN_ADDRESS as usize {
+            self.ram[address]
+        } else {
+            self.devices.read(self.ram, address as u16)
+        }
+    }
+
+    #[inline(always)]
+    fn write(&mut self, address: usize, value: i16) {
+        if address < SCREEN_ADDRESS as usize {
+            self.ram[address] = value;
+        } else {
+            self.devices.write(self.ram, address as u16, value);
+        }
+    }
+}
+
+/// Computes a decoded C-instruction and stores its output, the address
+/// of M uses the A register before it is updated
+///
+/// # Returns
+///
+/// * The output of the ALU and the address of M (also the jump target)
+#[inline(always)]
+fn compute(compute: Compute, a: &mut i16, d: &mut i16, memory: &mut Memory) -> (i16, usize) {
+    let address = *a as u16 as usize & ADDRESS_MASK;
+    let y = if compute.memory {
+        memory.read(address)
+    } else {
+        *a
+    };
+    let out = compute.comp.compute(*d, y);
+
+    if compute.dest & DEST_M != 0 {
+        memory.write(address, out);
+    }
+
+    if compute.dest & DEST_A != 0 {
+        *a = out;
+    }
+
+    if compute.dest & DEST_D != 0 {
+        *d = out;
+    }
+
+    (out, address)
+}
+
+/// Executes a decoded operation with an instruction (load or compute)
+///
+/// # Returns
+///
+/// * The address of the next instruction
+#[inline(always)]
+fn step(operation: Operation, pc: usize, a: &mut i16, d: &mut i16, memory: &mut Memory) -> usize {
+    if let Operation::Compute(decoded) = operation {
+        let (out, address) = compute(decoded, a, d, memory);
+
+        if decoded.jump & sign(out) != 0 {
+            return address;
+        }
+    } else if let Operation::Load(value) = operation {
+        *a = value;
+    }
+
+    (pc + 1) & ADDRESS_MASK
+}
+
+/// Execution engine that decodes the ROM once and runs the decoded
+/// operations by basic blocks, much faster than decoding the bits of every
+/// instruction on each cycle like `Computer::step`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Engine {
+    /// decoded operation of each ROM address
+    pub operations: Vec<Operation>,
+    /// end (exclusive) of the basic block that starts at each address, a
+    /// block runs straight until a C-instruction that may jump
+    pub blocks: Vec<u32>,
+}
+
+impl Engine {
+    /// Decodes the program loaded in the ROM of a computer
+    pub fn new(computer: &Computer) -> Self {
+        let rom = &computer.rom;
+
+        let operations: Vec<Operation> = (0..ROM_SIZE)
+            .map(|address| {
+                if address >= computer.program_size {
+                    return Operation::End;
+                }
+
+                let instruction = rom[address];
+
+                let is_halt = address + 1 < ROM_SIZE
+                    && instruction as usize == address
+                    && rom[address + 1] & 0xE03F == 0xE007;
+
+                if is_halt {
+                    return Operation::Halt;
+                }
+
+                if instruction & 0x8000 == 0 {
+                    return Operation::Load(instruction as i16);
+                }
+
+                match Comp::decode(instruction) {
+                    Some(comp) => Operation::Compute(Compute {
+                        comp,
+                        memory: instruction & 0x1000 != 0,
+                        dest: ((instruction >> 3) & 0b111) as u8,
+                        jump: (instruction & 0b111) as u8,
+                    }),
+                    None => Operation::Invalid(instruction),
+                }
+            })
+            .collect();
+
+        // the blocks are built from the end, each one extends the next
+        let mut blocks: Vec<u32> = vec![0; ROM_SIZE];
+
+        for address in (0..ROM_SIZE).rev() {
+            blocks[address] = match operations[address] {
+                Operation::Load(_) | Operation::Compute(Compute { jump: 0, .. })
+                    if address + 1 < ROM_SIZE =>
+                {
+                    blocks[address + 1].max(address as u32 + 1)
+                }
+                Operation::Load(_) | Operation::Compute(_) => address as u32 + 1,
+                _ => address as u32,
+            };
+        }
+
+        Self { operations, blocks }
+    }
+
+    /// Returns why the program cannot go on at an address without
+    /// instruction (halt, end of the program or invalid instruction)
+    fn stop(&self, pc: usize, budget_left: bool) -> StopReason {
+        match self.operations[pc] {
+            Operation::Halt => StopReason::Halted,
+            Operation::Invalid(instruction) if budget_left => {
+                panic!("Invalid instruction at {}: {:016b}", pc, instruction)
+            }
+            Operation::Invalid(_) => StopReason::CycleLimit,
+            _ => StopReason::EndOfProgram,
+        }
+    }
+
+    /// Runs the decoded operations by basic blocks until the program stops
+    /// or the budget of cycles is executed, without keyboard events, the
+    /// budget is checked once per block
+    ///
+    /// # Returns
+    ///
+    /// * The reason to stop and the number of cycles executed
+    fn execute(&self, computer: &mut Computer, budget: u64) -> (StopReason, u64) {
+        let (mut a, mut d, mut pc) = (computer.a, computer.d, computer.pc as usize);
+        let mut memory = Memory {
+            ram: computer.ram.as_mut_slice().try_into().unwrap(),
+            devices: &computer.devices,
+        };
+        let operations = self.operations.as_slice();
+        let mut executed: u64 = 0;
+
+        let reason = loop {
+            let end = self.blocks[pc] as usize;
+
+            if end == pc {
+                break self.stop(pc, executed < budget);
+            }
+
+            // without budget for the whole block the operations run one by one
+            if executed + (end - pc) as u64 > budget {
+                if executed >= budget {
+                    break StopReason::CycleLimit;
+                }
+
+                pc = step(operations[pc], pc, &mut a, &mut d, &mut memory);
+                executed += 1;
+                continue;
+            }
+
+            // the operations before the last one never jump
+            for operation in operations[pc..end - 1].iter() {
+                match *operation {
+                    Operation::Load(value) => a = value,
+                    Operation::Compute(decoded) => {
+                        compute(decoded, &mut a, &mut d, &mut memory);
+                    }
+                    _ => {}
+                }
+            }
+
+            executed += (end - pc) as u64;
+            pc = step(operations[end - 1], end - 1, &mut a, &mut d, &mut memory);
+        };
+
+        computer.a = a;
+        computer.d = d;
+        computer.pc = pc as u16;
+        computer.cycles += executed;
+
+        (reason, executed)
+    }
+
+    /// Runs the decoded operations one at a time like `execute`, the reads
+    /// and writes of M go through the mapped devices, which tick after
+    /// each instruction, and the profile counts each instruction
+    ///
+    /// # Returns
+    ///
+    /// * The reason to stop and the number of cycles executed
+    fn execute_io(&self, computer: &mut Computer, budget: u64) -> (StopReason, u64) {
+        let mut executed: u64 = 0;
+
+        let reason = loop {
+            let pc = computer.pc as usize;
+
+            if self.blocks[pc] as usize == pc {
+                break self.stop(pc, executed < budget);
+            }
+
+            if executed >= budget {
+                break StopReason::CycleLimit;
+            }
+
+            if let Some(profile) = &mut computer.profile {
+                profile.record(pc as u16);
+            }
+
+            computer.cycles += 1;
+            executed += 1;
+
+            computer.pc = match self.operations[pc] {
+                Operation::Compute(compute) => {
+                    let address = computer.a as u16;
+                    let y = if compute.memory {
+                        computer.read_memory(address)
+                    } else {
+                        computer.a
+                    };
+                    let out = compute.comp.compute(computer.d, y);
+
+                    if compute.dest & DEST_M != 0 {
+                        computer.write_memory(address, out);
+                    }
+
+                    if compute.dest & DEST_A != 0 {
+                        computer.a = out;
+                    }
+
+                    if compute.dest & DEST_D != 0 {
+                        computer.d = out;
+                    }
+
+                    if compute.jump & sign(out) != 0 {
+                        address & ADDRESS_MASK as u16
+                    } else {
+                        (pc as u16 + 1) & ADDRESS_MASK as u16
+                    }
+                }
+                operation => {
+                    if let Operation::Load(value) = operation {
+                        computer.a = value;
+                    }
+
+                    (pc as u16 + 1) & ADDRESS_MASK as u16
+                }
+            };
+
+            if computer.devices.has_peripherals() {
+                computer.devices.tick(computer.cycles);
+            }
+        };
+
+        (reason, executed)
+    }
+
+    /// Runs the program like `Computer::run`, keyboard scripts are applied
+    /// at their cycle. Profiles and peripherals are handled one
+    /// instruction at a time, while the computer steps by itself when it
+    /// traces or monitors the execution since it records every instruction
+    ///
+    /// # Arguments
+    ///
+    /// * `computer` - The computer with the program decoded by this engine
+    /// * `max_cycles` - The maximum number of cycles to execute (None to run until the program stops)
+    pub fn run(&self, computer: &mut Computer, max_cycles: Option<u64>) -> StopReason {
+        if computer.trace.is_some() || computer.monitor.is_some() {
+            return computer.run(max_cycles);
+        }
+
+        let io = computer.profile.is_some() || computer.devices.has_peripherals();
+        let mut executed: u64 = 0;
+
+        loop {
+            let mut budget = max_cycles.map_or(u64::MAX, |max_cycles| max_cycles - executed);
+
+            // stop at the next key event, applied like `Computer::step` does
+            // (only when an instruction is about to be executed)
+            let stopped = matches!(
+                self.operations[computer.pc as usize],
+                Operation::Halt | Operation::End
+            );
+
+            let cycles = computer.cycles;
+            let (key, next_cycle) = match computer.keyboard.as_mut() {
+                Some(keyboard) if !stopped && budget > 0 => {
+                    (keyboard.poll(cycles), keyboard.next_cycle())
+                }
+                _ => (None, None),
+            };
+
+            if let Some(key) = key {
+                computer.set_key(key);
+            }
+
+            if let Some(cycle) = next_cycle {
+                budget = budget.min(cycle - cycles);
+            }
+
+            let (reason, cycles) = if io {
+                self.execute_io(computer, budget)
+            } else {
+                self.execute(computer, budget)
+            };
+            executed += cycles;
+
+            let finished = max_cycles.is_some_and(|max_cycles| executed >= max_cycles);
+
+            if reason != StopReason::CycleLimit || finished {
+                return reason;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::Timer;
+    use crate::keyboard::KeyboardScript;
+    use crate::loader::assemble;
+    use crate::profiler::Profile;
+    use asm2hack::code::CodeConfiguration;
+
+    fn load(program: &str) -> Computer {
+        let mut computer = Computer::new();
+        computer.load_hack(program);
+        computer
+    }
+
+    #[test]
+    fn decode_comp_like_the_alu() {
+        let values: [i16; 7] = [0, 1, -1, 7, -32768, 32767, 12345];
+
+        for control in 0..64u16 {
+            let comp = Comp::decode(0xE000 | (control << 6)).unwrap();
+
+            for d in values.iter() {
+                for y in values.iter() {
+                    assert_eq!(comp.compute(*d, *y), alu(control, *d, *y));
+                }
+            }
+        }
+
+        assert_eq!(Comp::decode(0b1010110000001000), Some(Comp::ShiftLeftD));
+        assert_eq!(Comp::decode(0b1010000000010000), Some(Comp::ShiftRightY));
+        assert_eq!(Comp::decode(0b1000000000000000), None);
+    }
+
+    #[test]
+    fn decode_program() {
+        // @2, D=A, (END) @2, 0;JMP
+        let computer =
+            load("0000000000000010\n1110110000010000\n0000000000000010\n1110101010000111");
+        let engine = Engine::new(&computer);
+
+        assert_eq!(engine.operations[0], Operation::Load(2));
+        assert_eq!(
+            engine.operations[1],
+            Operation::Compute(Compute {
+                comp: Comp::Y,
+                memory: false,
+                dest: DEST_D,
+                jump: 0,
+            })
+        );
+        assert_eq!(engine.operations[2], Operation::Halt);
+        assert_eq!(engine.operations[4], Operation::End);
+
+        // the first block stops before the halt
+        assert_eq!(engine.blocks[0..5], [2, 2, 2, 4, 4]);
+    }
+
+    #[test]
+    fn split_program_in_blocks() {
+        // @i, M=1, (LOOP) @i, M=M+1, D=M, @LOOP, D;JLT, @i, D=M
+        let mut computer = Computer::new();
+        computer.load_rom(
+            &assemble(
+                "@i\nM=1\n(LOOP)\n@i\nM=M+1\nD=M\n@LOOP\nD;JLT\n@i\nD=M",
+                &CodeConfiguration::default(),
+            )
+            .unwrap(),
+        );
+        let engine = Engine::new(&computer);
+
+        // blocks end after the conditional jump or before the end
+        assert_eq!(engine.blocks[0..10], [7, 7, 7, 7, 7, 7, 7, 9, 9, 9]);
+    }
+
+    #[test]
+    fn run_like_the_computer() {
+        // counts down from 3 and halts
+        let program = assemble(
+            "@3\nD=A\n@i\nM=D\n(LOOP)\n@i\nAM=M-1\nD=A\n@LOOP\nD;JGT\n(END)\n@END\n0;JMP",
+            &CodeConfiguration::default(),
+        )
+        .unwrap();
+
+        for max_cycles in [
+            None,
+            Some(0),
+            Some(1),
+            Some(5),
+            Some(6),
+            Some(11),
+            Some(100),
+        ] {
+            let mut expected = Computer::new();
+            expected.load_rom(&program);
+            let expected_reason = expected.run(max_cycles);
+
+            let mut computer = Computer::new();
+            computer.load_rom(&program);
+            let reason = Engine::new(&computer).run(&mut computer, max_cycles);
+
+            assert_eq!(reason, expected_reason);
+            assert_eq!(computer, expected);
+        }
+    }
+
+    #[test]
+    fn run_with_keyboard_script() {
+        // (LOOP) @24576, D=M, @0, M=D, @0, 0;JMP
+        let program = "0110000000000000\n1111110000010000\n0000000000000000\n1110001100001000\n0000000000000000\n1110101010000111";
+        let script = KeyboardScript::parse("5 K\n19 RELEASE\n20 A");
+
+        let mut expected = load(program);
+        expected.keyboard = Some(script.clone());
+
+        let mut computer = load(program);
+        computer.keyboard = Some(script);
+        let engine = Engine::new(&computer);
+
+        // the runs stop between key events and in the middle of the loop
+        for max_cycles in [4, 17, 9, 30] {
+            expected.run(Some(max_cycles));
+            engine.run(&mut computer, Some(max_cycles));
+
+            assert_eq!(computer, expected);
+        }
+    }
+
+    #[test]
+    fn run_with_devices_and_profile_like_the_computer() {
+        // reads the timer while counting down from 3
+        let program = assemble(
+            "@3\nD=A\n@i\nM=D\n(LOOP)\n@24577\nD=M\n@16\nM=D\n@i\nMD=M-1\n@LOOP\nD;JGT\n(END)\n@END\n0;JMP", &CodeConfiguration::default(),
+        ).unwrap();
+
+        let mut expected = Computer::new();
+        expected.load_rom(&program);
+        expected.profile = Some(Profile::new());
+        let expected_timer = expected.devices.map(24577, 1, Timer::new(2)).unwrap();
+
+        let mut computer = Computer::new();
+        computer.load_rom(&program);
+        computer.profile = Some(Profile::new());
+        let timer = computer.devices.map(24577, 1, Timer::new(2)).unwrap();
+
+        for max_cycles in [Some(7), Some(3), None] {
+            let expected_reason = expected.run(max_cycles);
+            let reason = Engine::new(&computer).run(&mut computer, max_cycles);
+
+            assert_eq!(reason, expected_reason);
+            assert_eq!(
+                (computer.a, computer.d, computer.pc, computer.cycles),
+                (expected.a, expected.d, expected.pc, expected.cycles)
+            );
+            assert_eq!(computer.ram, expected.ram);
+            assert_eq!(computer.profile, expected.profile);
+            assert_eq!(*timer.borrow(), *expected_timer.borrow());
+        }
+
+        assert_ne!(computer.read_memory(16), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid instruction at 1: 1000000000000000")]
+    fn run_invalid_instruction() {
+        let mut computer = load("0000000000000010\n1000000000000000");
+
+        Engine::new(&computer).run(&mut computer, None);
+    }
+}
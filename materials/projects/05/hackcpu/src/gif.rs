@@ -0,0 +1,352 @@
+use crate::screen::{is_black, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// shortest delay between frames in hundredths of a second, browsers
+/// slow down shorter delays
+const MIN_DELAY: u64 = 2;
+
+/// number of bits of the pixels given to the LZW encoder, GIF does not
+/// allow less than 2 bits even for 2 colors
+const MIN_CODE_SIZE: u8 = 2;
+
+/// codes of the LZW encoder, the pixels use the codes below `CLEAR_CODE`
+const CLEAR_CODE: u16 = 1 << MIN_CODE_SIZE;
+const END_CODE: u16 = CLEAR_CODE + 1;
+
+/// largest code of the LZW encoder (12 bits)
+const MAX_CODE: u16 = 4095;
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct GifRecorder {
+    /// cycle of each captured screen and its words, a screen is only
+    /// captured when it differs from the previous one
+    pub frames: Vec<(u64, Vec<i16>)>,
+}
+
+/// Writes the codes of the LZW encoder as a stream of bits, the first
+/// code in the least significant bits of the first byte
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    /// Writes a code of the LZW encoder, the decoder adds an entry to its
+    /// table after reading each code so the size grows once the next
+    /// entry no longer fits
+    fn emit(&mut self, code: u16, next: u16, size: &mut u8) {
+        self.write(code, *size);
+
+        if next >= 1 << *size && *size < 12 {
+            *size += 1;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+
+        self.bytes
+    }
+}
+
+/// Compresses the color indexes of an image with the variable-length LZW
+/// of GIF, the code size grows up to 12 bits and the table is cleared
+/// once it is full
+///
+/// # Arguments
+///
+/// * `pixels` - The color indexes (0 or 1) of the image, row by row
+pub fn lzw_encode(pixels: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter {
+        bytes: Vec::new(),
+        buffer: 0,
+        bits: 0,
+    };
+
+    // codes of the sequences that extend a sequence with each pixel
+    let mut table: Vec<[u16; 2]> = vec![[0; 2]; MAX_CODE as usize + 1];
+    let mut next = END_CODE + 1;
+    let mut size = MIN_CODE_SIZE + 1;
+
+    writer.emit(CLEAR_CODE, next, &mut size);
+
+    let Some((first, pixels)) = pixels.split_first() else {
+        writer.emit(END_CODE, next, &mut size);
+        return writer.finish();
+    };
+
+    let mut prefix = *first as u16;
+
+    for pixel in pixels.iter().map(|pixel| *pixel as usize) {
+        match table[prefix as usize][pixel] {
+            0 => {
+                writer.emit(prefix, next, &mut size);
+
+                if next >= MAX_CODE {
+                    writer.emit(CLEAR_CODE, next, &mut size);
+                    table.iter_mut().for_each(|codes| *codes = [0; 2]);
+                    next = END_CODE + 1;
+                    size = MIN_CODE_SIZE + 1;
+                } else {
+                    table[prefix as usize][pixel] = next;
+                    next += 1;
+                }
+
+                prefix = pixel as u16;
+            }
+            code => prefix = code,
+        }
+    }
+
+    writer.emit(prefix, next, &mut size);
+    writer.emit(END_CODE, next, &mut size);
+    writer.finish()
+}
+
+/// Returns the color index of each pixel of the screen, 1 for black
+fn color_indexes(screen: &[i16]) -> Vec<u8> {
+    (0..SCREEN_HEIGHT)
+        .flat_map(|y| (0..SCREEN_WIDTH).map(move |x| is_black(screen, x, y) as u8))
+        .collect()
+}
+
+/// Appends data split in the sub-blocks of GIF (up to 255 bytes each)
+fn push_sub_blocks(bytes: &mut Vec<u8>, data: &[u8]) {
+    for block in data.chunks(255) {
+        bytes.push(block.len() as u8);
+        bytes.extend_from_slice(block);
+    }
+
+    bytes.push(0);
+}
+
+impl GifRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Captures the screen if it changed since the last capture
+    ///
+    /// # Arguments
+    ///
+    /// * `cycle` - The number of cycles executed when the screen is captured
+    /// * `screen` - The words of the memory-mapped screen
+    pub fn capture(&mut self, cycle: u64, screen: &[i16]) {
+        if self.frames.last().map(|(_, last)| last.as_slice()) != Some(screen) {
+            self.frames.push((cycle, screen.to_vec()));
+        }
+    }
+
+    /// Encodes the captured screens as an animated GIF that loops forever,
+    /// each frame lasts until the next change at the given speed and
+    /// changes closer than the shortest delay only keep the last screen
+    ///
+    /// # Arguments
+    ///
+    /// * `end_cycle` - The cycle where the recording stopped
+    /// * `speed` - The number of cycles executed per second
+    pub fn to_gif(&self, end_cycle: u64, speed: u64) -> Vec<u8> {
+        let start = self.frames.first().map_or(0, |(cycle, _)| *cycle);
+        let time = |cycle: u64| cycle.saturating_sub(start) * 100 / speed.max(1);
+
+        // time of each frame in hundredths of a second
+        let mut frames: Vec<(u64, &[i16])> = Vec::new();
+
+        for (cycle, screen) in self.frames.iter() {
+            match frames.last_mut() {
+                Some((last, last_screen)) if time(*cycle) < *last + MIN_DELAY => {
+                    *last_screen = screen;
+                }
+                _ => frames.push((time(*cycle), screen)),
+            }
+        }
+
+        let mut bytes: Vec<u8> = b"GIF89a".to_vec();
+
+        // logical screen with a global table of 2 colors (white and black)
+        bytes.extend_from_slice(&(SCREEN_WIDTH as u16).to_le_bytes());
+        bytes.extend_from_slice(&(SCREEN_HEIGHT as u16).to_le_bytes());
+        bytes.extend_from_slice(&[0x80, 0, 0]);
+        bytes.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00]);
+
+        // application extension to loop the animation forever
+        bytes.extend_from_slice(&[0x21, 0xFF, 0x0B]);
+        bytes.extend_from_slice(b"NETSCAPE2.0");
+        bytes.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+
+        for (index, (at, screen)) in frames.iter().enumerate() {
+            let end = match frames.get(index + 1) {
+                Some((next, _)) => *next,
+                None => time(end_cycle),
+            };
+            let delay = end.saturating_sub(*at).clamp(MIN_DELAY, u16::MAX as u64) as u16;
+
+            // graphic control extension with the delay of the frame
+            bytes.extend_from_slice(&[0x21, 0xF9, 0x04, 0x00]);
+            bytes.extend_from_slice(&delay.to_le_bytes());
+            bytes.extend_from_slice(&[0x00, 0x00]);
+
+            // image descriptor covering the whole screen
+            bytes.push(0x2C);
+            bytes.extend_from_slice(&[0, 0, 0, 0]);
+            bytes.extend_from_slice(&(SCREEN_WIDTH as u16).to_le_bytes());
+            bytes.extend_from_slice(&(SCREEN_HEIGHT as u16).to_le_bytes());
+            bytes.push(0);
+
+            bytes.push(MIN_CODE_SIZE);
+            push_sub_blocks(&mut bytes, &lzw_encode(&color_indexes(screen)));
+        }
+
+        bytes.push(0x3B);
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::SCREEN_SIZE;
+
+    /// Decodes a GIF LZW stream following the specification, to check
+    /// the encoder without external tools
+    fn lzw_decode(bytes: &[u8]) -> Vec<u8> {
+        let (mut position, mut pixels) = (0usize, Vec::new());
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut size = MIN_CODE_SIZE + 1;
+        let mut previous: Option<usize> = None;
+
+        let reset = |table: &mut Vec<Vec<u8>>| {
+            *table = (0..=END_CODE).map(|code| vec![code as u8]).collect();
+        };
+        reset(&mut table);
+
+        loop {
+            let code = (0..size as usize).fold(0usize, |code, bit| {
+                let bit_position = position + bit;
+                let value = (bytes[bit_position / 8] >> (bit_position % 8)) & 1;
+                code | (value as usize) << bit
+            });
+            position += size as usize;
+
+            if code == CLEAR_CODE as usize {
+                reset(&mut table);
+                size = MIN_CODE_SIZE + 1;
+                previous = None;
+                continue;
+            }
+
+            if code == END_CODE as usize {
+                return pixels;
+            }
+
+            let entry = match previous {
+                None => table[code].clone(),
+                Some(previous) => {
+                    let mut entry = table[previous].clone();
+                    let entry = if code < table.len() {
+                        let current = table[code].clone();
+                        entry.push(current[0]);
+                        table.push(entry);
+                        current
+                    } else {
+                        entry.push(entry[0]);
+                        table.push(entry.clone());
+                        entry
+                    };
+
+                    if table.len() == 1 << size && size < 12 {
+                        size += 1;
+                    }
+
+                    entry
+                }
+            };
+
+            pixels.extend_from_slice(&entry);
+            previous = Some(code);
+        }
+    }
+
+    #[test]
+    fn encode_pixels_with_lzw() {
+        let empty: Vec<u8> = Vec::new();
+        assert_eq!(lzw_decode(&lzw_encode(&empty)), empty);
+
+        let pixels = vec![0, 1, 1, 0, 0, 0, 1, 0, 1, 0, 1];
+        assert_eq!(lzw_decode(&lzw_encode(&pixels)), pixels);
+
+        // long and irregular images grow the code size and fill the table
+        let pixels: Vec<u8> = (0u32..200_000)
+            .map(|index| (index.wrapping_mul(2654435761) >> 13) as u8 & (index % 7 == 0) as u8)
+            .collect();
+        assert_eq!(lzw_decode(&lzw_encode(&pixels)), pixels);
+    }
+
+    #[test]
+    fn capture_changed_screens() {
+        let mut screen = vec![0i16; SCREEN_SIZE];
+        let mut recorder = GifRecorder::new();
+
+        recorder.capture(0, &screen);
+        recorder.capture(100, &screen);
+        screen[0] = 1;
+        recorder.capture(200, &screen);
+
+        let cycles: Vec<u64> = recorder.frames.iter().map(|(cycle, _)| *cycle).collect();
+        assert_eq!(cycles, vec![0, 200]);
+    }
+
+    #[test]
+    fn encode_animated_gif() {
+        let mut screen = vec![0i16; SCREEN_SIZE];
+        let mut recorder = GifRecorder::new();
+
+        recorder.capture(0, &screen);
+        screen[0] = 1;
+        recorder.capture(1_000, &screen);
+        screen[0] = 3;
+        recorder.capture(100_000, &screen);
+
+        // at 100000 cycles per second the second frame is merged into the
+        // first one (less than 2 hundredths of a second)
+        let gif = recorder.to_gif(300_000, 100_000);
+
+        assert!(gif.starts_with(b"GIF89a\x00\x02\x00\x01\x80\x00\x00"));
+        assert_eq!(gif.last(), Some(&0x3B));
+
+        let controls: Vec<u16> = gif
+            .windows(4)
+            .enumerate()
+            .filter(|(_, window)| window == &[0x21, 0xF9, 0x04, 0x00])
+            .map(|(index, _)| u16::from_le_bytes([gif[index + 4], gif[index + 5]]))
+            .collect();
+        assert_eq!(controls, vec![100, 200]);
+
+        // the first frame shows the pixel of the second capture
+        let start = gif.iter().position(|byte| *byte == 0x2C).unwrap() + 11;
+        let mut data: Vec<u8> = Vec::new();
+        let mut position = start;
+        while gif[position] != 0 {
+            let length = gif[position] as usize;
+            data.extend_from_slice(&gif[position + 1..position + 1 + length]);
+            position += length + 1;
+        }
+
+        let pixels = lzw_decode(&data);
+        assert_eq!(pixels.len(), SCREEN_WIDTH * SCREEN_HEIGHT);
+        assert_eq!(&pixels[..3], &[1, 0, 0]);
+    }
+}
@@ -0,0 +1,238 @@
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct KeyEvent {
+    /// number of cycles executed before the key is set
+    pub cycle: u64,
+    /// key code written in the keyboard register (0 releases the key)
+    pub key: i16,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct KeyboardScript {
+    /// events sorted by cycle
+    pub events: Vec<KeyEvent>,
+    /// index of the next event to apply
+    next: usize,
+}
+
+/// Returns the nand2tetris key code of a key, special keys use the codes
+/// of the Hack keyboard (NEWLINE 128 ... F12 152) and any other single
+/// character uses its own code
+///
+/// # Arguments
+///
+/// * `key` - The name of the key, a character or a number
+pub fn key_code(key: &str) -> Option<i16> {
+    let code = match key.to_uppercase().as_str() {
+        "RELEASE" | "NONE" => 0,
+        "SPACE" => 32,
+        "NEWLINE" | "ENTER" => 128,
+        "BACKSPACE" => 129,
+        "LEFT" => 130,
+        "UP" => 131,
+        "RIGHT" => 132,
+        "DOWN" => 133,
+        "HOME" => 134,
+        "END" => 135,
+        "PAGEUP" => 136,
+        "PAGEDOWN" => 137,
+        "INSERT" => 138,
+        "DELETE" => 139,
+        "ESC" => 140,
+        name => match name.strip_prefix('F').map(|number| number.parse::<i16>()) {
+            Some(Ok(number)) if (1..=12).contains(&number) => 140 + number,
+            _ => {
+                let mut chars = key.chars();
+
+                match (chars.next(), chars.next()) {
+                    (Some(char), None) if !char.is_ascii_digit() => char as i16,
+                    _ => return key.parse::<i16>().ok().filter(|code| *code >= 0),
+                }
+            }
+        },
+    };
+
+    Some(code)
+}
+
+impl KeyboardScript {
+    /// Parses a keyboard script, each line sets a key at a given cycle or
+    /// at a given frame when the script defines the cycles of each frame
+    /// (a key stays pressed until it is released with `RELEASE` or `0`):
+    ///
+    /// ```text
+    /// // press the right arrow for a while
+    /// frame-cycles 20000
+    /// 1000 RIGHT
+    /// frame 5 RELEASE
+    /// frame 6 K
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The content of the keyboard script
+    pub fn parse(input: &str) -> Self {
+        let mut events: Vec<KeyEvent> = Vec::new();
+        let mut frame_cycles: Option<u64> = None;
+
+        for (index, line) in input.lines().enumerate() {
+            let line_number = index + 1;
+
+            // remove comments
+            let line = line.split("//").next().unwrap_or("").trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let parts: Vec<&str> = line.split_whitespace().collect();
+
+            let event = match parts.as_slice() {
+                ["frame-cycles", cycles] => {
+                    frame_cycles = cycles.parse::<u64>().ok().filter(|cycles| *cycles > 0);
+
+                    if frame_cycles.is_none() {
+                        panic!("Invalid keyboard script at line {}: {}", line_number, line);
+                    }
+
+                    continue;
+                }
+                ["frame", frame, key] => match frame_cycles {
+                    Some(frame_cycles) => {
+                        frame
+                            .parse::<u64>()
+                            .ok()
+                            .zip(key_code(key))
+                            .map(|(frame, key)| KeyEvent {
+                                cycle: frame * frame_cycles,
+                                key,
+                            })
+                    }
+                    None => panic!(
+                        "Keyboard script uses frames without frame-cycles at line {}: {}",
+                        line_number, line
+                    ),
+                },
+                [cycle, key] => cycle
+                    .parse::<u64>()
+                    .ok()
+                    .zip(key_code(key))
+                    .map(|(cycle, key)| KeyEvent { cycle, key }),
+                _ => None,
+            };
+
+            match event {
+                Some(event) => events.push(event),
+                None => panic!("Invalid keyboard script at line {}: {}", line_number, line),
+            }
+        }
+
+        // keep the order of the script for events at the same cycle
+        events.sort_by_key(|event| event.cycle);
+
+        Self { events, next: 0 }
+    }
+
+    /// Returns the key to set once the given number of cycles is executed,
+    /// the last one if several events happened since the previous call
+    pub fn poll(&mut self, cycle: u64) -> Option<i16> {
+        let mut key: Option<i16> = None;
+
+        while self.next < self.events.len() && self.events[self.next].cycle <= cycle {
+            key = Some(self.events[self.next].key);
+            self.next += 1;
+        }
+
+        key
+    }
+
+    /// Returns the cycle of the next event to apply, if any
+    pub fn next_cycle(&self) -> Option<u64> {
+        self.events.get(self.next).map(|event| event.cycle)
+    }
+
+    /// Starts the script again from the first event
+    pub fn rewind(&mut self) {
+        self.next = 0;
+    }
+
+    /// Moves the script back or forward so the next poll at the given
+    /// cycle applies the events of that cycle again (e.g. to step backwards)
+    pub fn seek(&mut self, cycle: u64) {
+        self.next = self.events.partition_point(|event| event.cycle < cycle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_key_codes() {
+        assert_eq!(key_code("NEWLINE"), Some(128));
+        assert_eq!(key_code("left"), Some(130));
+        assert_eq!(key_code("ESC"), Some(140));
+        assert_eq!(key_code("F1"), Some(141));
+        assert_eq!(key_code("F12"), Some(152));
+        assert_eq!(key_code("K"), Some(75));
+        assert_eq!(key_code("k"), Some(107));
+        assert_eq!(key_code("7"), Some(7));
+        assert_eq!(key_code("65"), Some(65));
+        assert_eq!(key_code("F13"), None);
+        assert_eq!(key_code("-1"), None);
+    }
+
+    #[test]
+    fn parse_keyboard_script() {
+        let script = KeyboardScript::parse(
+            "// arrows\nframe-cycles 100\n\n500 RIGHT\nframe 2 UP // before\nframe 7 RELEASE\n",
+        );
+
+        assert_eq!(
+            script.events,
+            vec![
+                KeyEvent {
+                    cycle: 200,
+                    key: 131
+                },
+                KeyEvent {
+                    cycle: 500,
+                    key: 132
+                },
+                KeyEvent { cycle: 700, key: 0 },
+            ]
+        );
+    }
+
+    #[test]
+    fn poll_keyboard_events() {
+        let mut script = KeyboardScript::parse("0 A\n10 B\n10 C\n20 0");
+
+        assert_eq!(script.poll(0), Some(65));
+        assert_eq!(script.poll(5), None);
+        assert_eq!(script.poll(15), Some(67));
+        assert_eq!(script.poll(30), Some(0));
+        assert_eq!(script.poll(40), None);
+
+        script.rewind();
+        assert_eq!(script.poll(0), Some(65));
+
+        script.seek(10);
+        assert_eq!(script.next_cycle(), Some(10));
+        assert_eq!(script.poll(12), Some(67));
+        assert_eq!(script.next_cycle(), Some(20));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Keyboard script uses frames without frame-cycles at line 1: frame 2 UP"
+    )]
+    fn parse_keyboard_script_without_frame_cycles() {
+        KeyboardScript::parse("frame 2 UP");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid keyboard script at line 2: 100 UNKNOWN")]
+    fn parse_keyboard_script_with_invalid_key() {
+        KeyboardScript::parse("0 UP\n100 UNKNOWN");
+    }
+}
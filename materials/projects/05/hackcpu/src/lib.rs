@@ -0,0 +1,18 @@
+pub mod compare;
+pub mod computer;
+pub mod debugger;
+pub mod device;
+pub mod engine;
+pub mod gif;
+pub mod keyboard;
+pub mod loader;
+pub mod logs;
+pub mod monitor;
+pub mod profiler;
+pub mod screen;
+pub mod script;
+pub mod serial;
+pub mod snapshot;
+pub mod trace;
+pub mod tui;
+pub mod utils;
@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use asm2hack::code::{process_fields_with_config, BinaryInstruction, CodeConfiguration};
+use asm2hack::parser::{Parser, ParserInstructionType};
+use asm2hack::symbols::{resolve_labels, resolve_symbols, SymbolTable};
+
+use crate::computer::parse_hack;
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ProgramSymbols {
+    /// labels of the program and the ROM address they point to
+    pub labels: HashMap<String, u16>,
+    /// predefined symbols and variables with their RAM address
+    pub variables: HashMap<String, u16>,
+    /// symbol used by the A-instruction at each ROM address
+    pub references: HashMap<u16, String>,
+    /// line of the source file of the instruction at each ROM address
+    pub lines: HashMap<u16, usize>,
+}
+
+impl ProgramSymbols {
+    /// Creates the symbols of a program without debug information
+    /// (binary programs), only the predefined symbols are known
+    pub fn predefined() -> Self {
+        Self {
+            variables: SymbolTable::new().symbols,
+            ..Default::default()
+        }
+    }
+
+    /// Resolves a RAM address given as a number, `RAM[n]` or a symbol
+    pub fn ram_address(&self, target: &str) -> Result<u16, String> {
+        let number = target
+            .strip_prefix("RAM[")
+            .and_then(|target| target.strip_suffix(']'))
+            .unwrap_or(target);
+
+        match number.parse::<u16>() {
+            Ok(address) => Ok(address),
+            Err(_) => self
+                .variables
+                .get(target)
+                .copied()
+                .ok_or_else(|| format!("Unknown symbol: {}", target)),
+        }
+    }
+
+    /// Describes a ROM address with its source line when it is known,
+    /// e.g. `PC 12 (line 20)`
+    pub fn location(&self, address: u16) -> String {
+        match self.lines.get(&address) {
+            Some(line) => format!("PC {} (line {})", address, line),
+            None => format!("PC {}", address),
+        }
+    }
+}
+
+/// Assembles a Hack assembly program (.asm) into binary instructions
+/// using the asm2hack assembler
+///
+/// # Arguments
+///
+/// * `input` - The content of the `.asm` file
+/// * `config` - The configuration of the assembler (e.g. the extended instruction set)
+pub fn assemble(input: &str, config: &CodeConfiguration) -> Result<Vec<u16>, String> {
+    Ok(assemble_with_symbols(input, config)?.0)
+}
+
+/// Assembles a Hack assembly program like `assemble` and keeps the
+/// symbols resolved by the assembler (e.g. for the debugger)
+///
+/// # Arguments
+///
+/// * `input` - The content of the `.asm` file
+/// * `config` - The configuration of the assembler (e.g. the extended instruction set)
+pub fn assemble_with_symbols(
+    input: &str,
+    config: &CodeConfiguration,
+) -> Result<(Vec<u16>, ProgramSymbols), String> {
+    let mut parser = Parser::new(input, false);
+    parser.parse();
+
+    // labels are resolved alone first to tell them apart from variables
+    let mut fields = parser.get_fields().clone();
+    let mut label_table = SymbolTable::new();
+    resolve_labels(&fields, &mut label_table)?;
+
+    let predefined = SymbolTable::new();
+    let labels: HashMap<String, u16> = label_table
+        .symbols
+        .into_iter()
+        .filter(|(symbol, _)| !predefined.contains(symbol))
+        .collect();
+
+    // resolve labels and variables into addresses
+    let mut symbol_table = SymbolTable::new();
+    resolve_symbols(&mut fields, &mut symbol_table)?;
+
+    let variables: HashMap<String, u16> = symbol_table
+        .symbols
+        .into_iter()
+        .filter(|(symbol, _)| !labels.contains_key(symbol))
+        .collect();
+
+    let instructions: Vec<BinaryInstruction> = process_fields_with_config(&fields, config)?
+        .into_iter()
+        .filter(|instruction| !instruction.binary.is_empty())
+        .collect();
+
+    let references: HashMap<u16, String> = (0..)
+        .zip(instructions.iter())
+        .filter_map(|(address, instruction)| {
+            instruction
+                .instruction
+                .instruction_symbol
+                .clone()
+                .filter(|_| {
+                    instruction.instruction.instruction_type == ParserInstructionType::AInstruction
+                })
+                .map(|symbol| (address, symbol))
+        })
+        .collect();
+
+    let lines: HashMap<u16, usize> = (0..)
+        .zip(instructions.iter())
+        .map(|(address, instruction)| (address, instruction.instruction.line_number))
+        .collect();
+
+    let binary = instructions
+        .iter()
+        .map(|instruction| u16::from_str_radix(&instruction.binary, 2).unwrap())
+        .collect();
+
+    Ok((
+        binary,
+        ProgramSymbols {
+            labels,
+            variables,
+            references,
+            lines,
+        },
+    ))
+}
+
+/// Loads a program from a `.hack` file or assembles it from a `.asm` file
+///
+/// # Arguments
+///
+/// * `input` - The path of the program to load
+/// * `config` - The configuration of the assembler for `.asm` files
+pub fn load_program(input: &Path, config: &CodeConfiguration) -> Result<Vec<u16>, String> {
+    Ok(load_program_with_symbols(input, config)?.0)
+}
+
+/// Loads a program like `load_program` and returns its symbols, binary
+/// programs only know the predefined symbols of the platform
+///
+/// # Arguments
+///
+/// * `input` - The path of the program to load
+/// * `config` - The configuration of the assembler for `.asm` files
+pub fn load_program_with_symbols(
+    input: &Path,
+    config: &CodeConfiguration,
+) -> Result<(Vec<u16>, ProgramSymbols), String> {
+    let content = std::fs::read_to_string(input)
+        .map_err(|_| format!("failed to read program: {}", input.display()))?;
+
+    match input.extension().and_then(|extension| extension.to_str()) {
+        Some("hack") => Ok((parse_hack(&content), ProgramSymbols::predefined())),
+        Some("asm") => assemble_with_symbols(&content, config),
+        _ => Err(format!("Program format not supported: {}", input.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assemble_program_with_symbols() {
+        let instructions =
+            assemble("@i\nM=1\n(END)\n@END\n0;JMP", &CodeConfiguration::default()).unwrap();
+
+        assert_eq!(
+            instructions,
+            vec![
+                0b0000000000010000,
+                0b1110111111001000,
+                0b0000000000000010,
+                0b1110101010000111,
+            ]
+        );
+    }
+
+    #[test]
+    fn assemble_program_keeping_symbols() {
+        let (_, symbols) =
+            assemble_with_symbols("@i\nM=1\n(END)\n@END\n0;JMP", &CodeConfiguration::default())
+                .unwrap();
+
+        assert_eq!(symbols.labels.get("END"), Some(&2));
+        assert_eq!(symbols.labels.get("i"), None);
+        assert_eq!(symbols.variables.get("i"), Some(&16));
+        assert_eq!(symbols.variables.get("SP"), Some(&0));
+        assert_eq!(symbols.references.get(&0), Some(&String::from("i")));
+        assert_eq!(symbols.references.get(&2), Some(&String::from("END")));
+        assert_eq!(symbols.references.get(&1), None);
+        assert_eq!(symbols.lines.get(&2), Some(&4));
+    }
+
+    #[test]
+    fn assemble_extended_instructions_with_the_extended_set() {
+        let extended = CodeConfiguration { extended: true };
+
+        assert_eq!(assemble("D=D<<", &extended), Ok(vec![0b1010110000010000]));
+        assert_eq!(
+            assemble("D=D<<", &CodeConfiguration::default()),
+            Err(String::from(
+                "Extended instruction requires the extended instruction set at line 1: D=D<<"
+            ))
+        );
+        assert_eq!(
+            assemble("(LOOP)\n(LOOP)", &extended),
+            Err(String::from("Duplicated label at line 2: LOOP"))
+        );
+        assert_eq!(
+            load_program(Path::new("program.bin"), &extended),
+            Err(String::from("failed to read program: program.bin"))
+        );
+    }
+
+    #[test]
+    fn resolve_ram_addresses() {
+        let (_, symbols) = assemble_with_symbols("@i\nM=1", &CodeConfiguration::default()).unwrap();
+
+        assert_eq!(symbols.ram_address("i"), Ok(16));
+        assert_eq!(symbols.ram_address("THIS"), Ok(3));
+        assert_eq!(symbols.ram_address("RAM[300]"), Ok(300));
+        assert_eq!(symbols.ram_address("256"), Ok(256));
+        assert_eq!(
+            symbols.ram_address("j"),
+            Err(String::from("Unknown symbol: j"))
+        );
+        assert_eq!(symbols.location(1), "PC 1 (line 2)");
+        assert_eq!(symbols.location(5), "PC 5");
+    }
+}
@@ -0,0 +1,407 @@
+use asm2hack::code::CodeConfiguration;
+use clap::Parser as ClapParser;
+use hackcpu::compare::log_failure;
+use hackcpu::computer::{Computer, StopReason};
+use hackcpu::debugger::{self, Debugger, DebuggerCommand};
+use hackcpu::logs::{log_error, log_info, log_success, log_warn};
+use hackcpu::monitor::{Invariant, Monitor, WatchAction, WatchKind};
+use hackcpu::script::ScriptRunner;
+use hackcpu::{
+    engine, gif, keyboard, loader, profiler, screen, serial, snapshot, trace, tui, utils,
+};
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+/// number of instructions reported as hot spots by the profiler
+const HOT_SPOTS: usize = 20;
+
+/// interface to execute Hack programs (.hack or .asm) and test scripts (.tst)
+/// in an emulated Hack hardware platform (CPU, ROM32K, RAM, screen and keyboard)
+#[derive(ClapParser, Debug)]
+#[command(author = "txxnano", version, about)]
+pub struct Args {
+    #[arg(short, long)]
+    /// input file to use (.hack, .asm or .tst)
+    input: String,
+
+    #[arg(short, long)]
+    /// assemble .asm programs with the shift operations of the extended instruction set
+    extended: bool,
+
+    #[arg(short, long)]
+    /// maximum number of cycles to execute (default: until the program halts),
+    /// in scripts the cycles of each repeat without a count (default: 10000000)
+    cycles: Option<u64>,
+
+    #[arg(short, long, value_delimiter = ',')]
+    /// RAM addresses to print once the program stops (e.g. 0,1,2)
+    ram: Vec<u16>,
+
+    #[arg(short, long)]
+    /// keyboard script with the keys to press while running (cycle or frame and key)
+    keyboard: Option<String>,
+
+    #[arg(short, long)]
+    /// start an interactive debugger (step, breakpoints, registers, disassembly)
+    debug: bool,
+
+    #[arg(short, long)]
+    /// count the instructions executed per ROM address and per routine (label)
+    profile: bool,
+
+    #[arg(short, long)]
+    /// save the executed instructions to a file (cycle, PC, A, D and memory writes)
+    trace: Option<String>,
+
+    #[arg(long)]
+    /// start the run from a snapshot of the same program (registers, memory and cycles)
+    snapshot: Option<String>,
+
+    #[arg(long)]
+    /// save a snapshot once the program stops (use --cycles to choose the cycle)
+    save_snapshot: Option<String>,
+
+    #[arg(long)]
+    /// map a serial console printing the words written to its output register
+    /// and reading its input register from the standard input
+    serial: bool,
+
+    #[arg(long, requires = "serial", default_value_t = serial::SERIAL_ADDRESS)]
+    /// address of the serial output register, the input register is the next word
+    serial_address: u16,
+
+    #[arg(short, long)]
+    /// stop when a word of RAM changes (address, RAM[n] or symbol, can be repeated)
+    watch: Vec<String>,
+
+    #[arg(long)]
+    /// report the changes of a word of RAM without stopping (can be repeated)
+    watch_log: Vec<String>,
+
+    #[arg(long)]
+    /// stop when a write breaks an invariant (e.g. "SP in 256..2047", "THIS not in SCREEN..24575")
+    invariant: Vec<String>,
+
+    #[arg(long)]
+    /// run the program in the terminal, drawing the screen and reading the keyboard
+    tui: bool,
+
+    #[arg(long, requires = "tui")]
+    /// draw the screen with half blocks instead of braille characters
+    blocks: bool,
+
+    #[arg(long, requires = "tui", default_value_t = 1)]
+    /// number of pixels merged in each pixel drawn in the terminal (1, 2, 4...)
+    scale: usize,
+
+    #[arg(long, default_value_t = 3_000_000)]
+    /// number of cycles executed per second in the terminal and in GIF recordings
+    speed: u64,
+
+    #[arg(long)]
+    /// save the screen as an image once the program stops (.pbm or .png)
+    screen: Option<String>,
+
+    #[arg(long, requires = "screen", value_parser = clap::value_parser!(u64).range(1..))]
+    /// save the screen every N cycles as numbered images (e.g. screen-0001.png)
+    screen_every: Option<u64>,
+
+    #[arg(long)]
+    /// record the screen as an animated GIF once the program stops
+    gif: Option<String>,
+
+    #[arg(long, requires = "gif", default_value_t = 100_000, value_parser = clap::value_parser!(u64).range(1..))]
+    /// capture the screen every N cycles, frames without changes are skipped
+    gif_every: u64,
+}
+
+pub fn main() {
+    let args = Args::parse();
+
+    // extract parameters from command line
+    let app_name = "hackcpu emulator";
+    let version = env!("CARGO_PKG_VERSION");
+    let input = args.input;
+    let cycles = args.cycles;
+    let config = CodeConfiguration {
+        extended: args.extended,
+    };
+
+    // print headers of the program
+    utils::header_info(app_name, version, &input, cycles);
+
+    // test scripts load their own program and define their own output
+    if input.ends_with(".tst") {
+        run_script(&input, &config, cycles);
+        return;
+    }
+
+    // load the program into the ROM, keeping its symbols for the debugger
+    let (instructions, symbols) =
+        match loader::load_program_with_symbols(Path::new(&input), &config) {
+            Ok(program) => program,
+            Err(error) => {
+                log_error(&error);
+                std::process::exit(1);
+            }
+        };
+    let mut computer = Computer::new();
+    computer.load_rom(&instructions);
+
+    log_info(format!("program size: {} instructions", computer.program_size).as_str());
+
+    // drive the keyboard register from a script
+    if let Some(keyboard) = &args.keyboard {
+        let script = keyboard::KeyboardScript::parse(&utils::read_file(keyboard));
+        log_info(format!("keyboard events: {}", script.events.len()).as_str());
+
+        computer.keyboard = Some(script);
+    }
+
+    if args.serial {
+        let address = args.serial_address;
+        if let Err(error) = computer
+            .devices
+            .map(address, 2, serial::SerialConsole::stdio(address))
+        {
+            log_error(&error);
+            std::process::exit(1);
+        }
+    }
+
+    // resume a long run from the state saved in a snapshot
+    if let Some(snapshot) = &args.snapshot {
+        snapshot::Snapshot::parse(&utils::read_file(snapshot))
+            .and_then(|snapshot| snapshot.restore(&mut computer))
+            .unwrap_or_else(|error| panic!("{}", error));
+
+        log_info(format!("snapshot restored at cycle {}", computer.cycles).as_str());
+    }
+
+    if args.profile {
+        computer.profile = Some(profiler::Profile::new());
+    }
+
+    if args.trace.is_some() {
+        computer.trace = Some(trace::Trace::new());
+    }
+
+    // check the watchpoints and the invariants on every write
+    if !args.watch.is_empty() || !args.watch_log.is_empty() || !args.invariant.is_empty() {
+        computer.monitor = Some(
+            monitor(&args.watch, &args.watch_log, &args.invariant, &symbols)
+                .unwrap_or_else(|error| panic!("{}", error)),
+        );
+    }
+
+    if args.debug {
+        debug(Debugger::new(computer, symbols));
+        return;
+    }
+
+    // execute the program, capturing the screen every N cycles if requested
+    let reason = match (&args.screen, args.screen_every) {
+        _ if args.tui => {
+            let options = tui::TuiOptions {
+                renderer: if args.blocks {
+                    tui::Renderer::HalfBlocks
+                } else {
+                    tui::Renderer::Braille
+                },
+                scale: args.scale.max(1),
+                speed: args.speed,
+                max_cycles: cycles,
+            };
+
+            tui::run_tui(&mut computer, &options)
+                .unwrap_or_else(|error| panic!("Terminal error: {}", error))
+        }
+        _ if args.gif.is_some() => {
+            let mut recorder = gif::GifRecorder::new();
+            recorder.capture(computer.cycles, &computer.screen());
+
+            let reason = computer.run_with_frames(cycles, args.gif_every, |computer| {
+                recorder.capture(computer.cycles, &computer.screen());
+            });
+            recorder.capture(computer.cycles, &computer.screen());
+
+            let output = args.gif.as_ref().unwrap();
+            utils::save_file(output, &recorder.to_gif(computer.cycles, args.speed));
+            log_success(
+                format!("GIF saved: {} ({} frames)", output, recorder.frames.len()).as_str(),
+            );
+
+            reason
+        }
+        (Some(screen), Some(every)) => {
+            let mut frame = 0;
+
+            computer.run_with_frames(cycles, every, |computer| {
+                frame += 1;
+                save_screen(computer, &screen::frame_file(screen, frame));
+            })
+        }
+        _ => engine::Engine::new(&computer).run(&mut computer, cycles),
+    };
+
+    match reason {
+        StopReason::Halted => log_success(format!("halted at {}", computer.pc).as_str()),
+        StopReason::EndOfProgram => log_success("reached the end of the program"),
+        StopReason::CycleLimit => log_warn("reached the maximum number of cycles"),
+        StopReason::Interrupted => log_warn("stopped by the user"),
+        StopReason::Watchpoint => log_warn("stopped by a watchpoint"),
+    }
+
+    // report the changes and the broken invariants with their location
+    let mut violated = false;
+
+    if let Some(monitor) = &computer.monitor {
+        for event in monitor.events.iter() {
+            match event.kind {
+                WatchKind::Watchpoint(_) => log_info(&event.describe(&symbols)),
+                WatchKind::Violation => {
+                    violated = true;
+                    log_error(&event.describe(&symbols));
+                }
+            }
+        }
+    }
+
+    // print the state of the computer
+    log_info(format!("cycles: {}", computer.cycles).as_str());
+    log_info(format!("A: {}", computer.a).as_str());
+    log_info(format!("D: {}", computer.d).as_str());
+    log_info(format!("PC: {}", computer.pc).as_str());
+
+    for address in args.ram.iter() {
+        log_info(format!("RAM[{}]: {}", address, computer.peek_memory(*address)).as_str());
+    }
+
+    if let Some(screen) = &args.screen {
+        save_screen(&computer, screen);
+    }
+
+    if let Some(output) = &args.save_snapshot {
+        let snapshot = snapshot::Snapshot::capture(&computer);
+        utils::save_file(output, snapshot.to_text().as_bytes());
+        log_success(format!("snapshot saved: {}", output).as_str());
+    }
+
+    if let (Some(output), Some(trace)) = (&args.trace, &computer.trace) {
+        utils::save_file(output, trace.to_text().as_bytes());
+        log_success(format!("trace saved: {}", output).as_str());
+    }
+
+    if let Some(profile) = &computer.profile {
+        println!("{}", profile.report(&computer.rom, &symbols, HOT_SPOTS));
+    }
+
+    if violated {
+        std::process::exit(1);
+    }
+}
+
+/// Builds the watchpoints and the invariants given on the command line
+fn monitor(
+    watch: &[String],
+    watch_log: &[String],
+    invariants: &[String],
+    symbols: &loader::ProgramSymbols,
+) -> Result<Monitor, String> {
+    let mut monitor = Monitor::new();
+
+    for target in watch.iter() {
+        monitor.watch(target, WatchAction::Break, symbols)?;
+    }
+
+    for target in watch_log.iter() {
+        monitor.watch(target, WatchAction::Log, symbols)?;
+    }
+
+    for invariant in invariants.iter() {
+        monitor
+            .invariants
+            .push(Invariant::parse(invariant, symbols)?);
+    }
+
+    Ok(monitor)
+}
+
+/// Saves the memory-mapped screen as an image
+fn save_screen(computer: &Computer, output: &str) {
+    utils::save_file(output, &screen::to_image(&computer.screen(), output));
+    log_success(format!("screen saved: {}", output).as_str());
+}
+
+/// Reads debugger commands from the standard input until `quit`, an
+/// empty line repeats the previous command
+fn debug(mut debugger: Debugger) {
+    log_info("debugger started, type `help` to list the commands");
+    println!("{}", debugger.instruction_line(debugger.computer.pc));
+
+    let stdin = std::io::stdin();
+    let mut previous = String::new();
+
+    loop {
+        print!("(hackcpu) ");
+        std::io::stdout().flush().unwrap();
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+            break;
+        }
+
+        if line.trim().is_empty() {
+            line = previous.clone();
+        }
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match debugger::parse_command(&line) {
+            Ok(DebuggerCommand::Quit) => break,
+            Ok(command) => match debugger.execute(&command) {
+                Ok(output) => println!("{}", output),
+                Err(error) => log_error(&error),
+            },
+            Err(error) => log_error(&error),
+        }
+
+        previous = line;
+    }
+}
+
+/// Runs a test script, saves its output file and reports the comparison
+fn run_script(input: &str, config: &CodeConfiguration, cycles: Option<u64>) {
+    let mut runner = ScriptRunner::from_file(Path::new(input));
+    runner.config = config.clone();
+
+    if let Some(cycles) = cycles {
+        runner.repeat_cycles = cycles;
+    }
+
+    let result = match runner.run() {
+        Ok(result) => result,
+        Err(error) => {
+            log_error(&error);
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(output_file) = &result.output_file {
+        utils::save_file(&output_file.to_string_lossy(), result.output.as_bytes());
+        log_info(format!("output saved: {}", output_file.display()).as_str());
+    }
+
+    match (&result.compare_file, &result.failure) {
+        (_, Some(failure)) => {
+            log_failure(failure);
+            std::process::exit(1);
+        }
+        (Some(compare_file), None) => log_success(
+            format!("comparison ended successfully: {}", compare_file.display()).as_str(),
+        ),
+        (None, None) => log_success("script ended successfully"),
+    }
+}
@@ -0,0 +1,279 @@
+use crate::loader::ProgramSymbols;
+use crate::trace::MemoryWrite;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum WatchAction {
+    /// stop the execution when the word changes
+    Break,
+    /// only record the change
+    Log,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Watchpoint {
+    /// name used to declare the watchpoint (symbol or address)
+    pub name: String,
+    pub address: u16,
+    pub action: WatchAction,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Invariant {
+    /// text used to declare the invariant, e.g. `SP in 256..2047`
+    pub text: String,
+    pub address: u16,
+    /// inclusive range of values
+    pub min: i16,
+    pub max: i16,
+    /// the value must stay outside of the range instead of inside
+    pub outside: bool,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum WatchKind {
+    Watchpoint(WatchAction),
+    Violation,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct WatchEvent {
+    pub kind: WatchKind,
+    /// name of the watchpoint or text of the invariant
+    pub name: String,
+    /// number of cycles executed before the instruction
+    pub cycle: u64,
+    /// address of the instruction that wrote the word
+    pub pc: u16,
+    pub write: MemoryWrite,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Monitor {
+    pub watchpoints: Vec<Watchpoint>,
+    pub invariants: Vec<Invariant>,
+    /// changes and violations found while running, the oldest first
+    pub events: Vec<WatchEvent>,
+    /// an event asked to stop the execution
+    stop: bool,
+}
+
+impl Invariant {
+    /// Parses an invariant on a word of RAM, the range is inclusive and
+    /// its bounds can be numbers or symbols:
+    ///
+    /// ```text
+    /// SP in 256..2047
+    /// THIS not in SCREEN..24575
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The invariant to parse
+    /// * `symbols` - The symbols used to resolve the names
+    pub fn parse(text: &str, symbols: &ProgramSymbols) -> Result<Self, String> {
+        let invalid = || format!("Invalid invariant: {}", text.trim());
+        let parts: Vec<&str> = text.split_whitespace().collect();
+
+        let (target, outside, range) = match parts.as_slice() {
+            [target, "in", range] => (target, false, range),
+            [target, "not", "in", range] => (target, true, range),
+            _ => return Err(invalid()),
+        };
+
+        let (min, max) = range.split_once("..").ok_or_else(invalid)?;
+        let bound = |bound: &str| -> Result<i16, String> {
+            match bound.parse::<i16>() {
+                Ok(value) => Ok(value),
+                Err(_) => symbols.ram_address(bound).map(|address| address as i16),
+            }
+        };
+
+        let (min, max) = (bound(min)?, bound(max)?);
+
+        if max < min {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            text: parts.join(" "),
+            address: symbols.ram_address(target)?,
+            min,
+            max,
+            outside,
+        })
+    }
+
+    /// Checks if a value written in the word respects the invariant
+    pub fn holds(&self, value: i16) -> bool {
+        (self.min..=self.max).contains(&value) != self.outside
+    }
+}
+
+impl Monitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares a watchpoint on a word of RAM
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The word to watch (address, `RAM[n]` or symbol)
+    /// * `action` - What to do when the word changes
+    /// * `symbols` - The symbols used to resolve the names
+    pub fn watch(
+        &mut self,
+        target: &str,
+        action: WatchAction,
+        symbols: &ProgramSymbols,
+    ) -> Result<(), String> {
+        let address = symbols.ram_address(target)?;
+
+        self.watchpoints.push(Watchpoint {
+            name: target.to_string(),
+            address,
+            action,
+        });
+
+        Ok(())
+    }
+
+    /// Checks the watchpoints and the invariants of a word written by an
+    /// instruction, invariants are only checked when their word is written
+    ///
+    /// # Arguments
+    ///
+    /// * `cycle` - The number of cycles executed before the instruction
+    /// * `pc` - The address of the instruction
+    /// * `write` - The word written by the instruction
+    pub fn check(&mut self, cycle: u64, pc: u16, write: MemoryWrite) {
+        let event = |kind: WatchKind, name: &str| WatchEvent {
+            kind,
+            name: name.to_string(),
+            cycle,
+            pc,
+            write,
+        };
+
+        for watchpoint in self.watchpoints.iter() {
+            if watchpoint.address == write.address && write.previous != write.value {
+                self.events.push(event(
+                    WatchKind::Watchpoint(watchpoint.action),
+                    &watchpoint.name,
+                ));
+                self.stop |= watchpoint.action == WatchAction::Break;
+            }
+        }
+
+        for invariant in self.invariants.iter() {
+            if invariant.address == write.address && !invariant.holds(write.value) {
+                self.events
+                    .push(event(WatchKind::Violation, &invariant.text));
+                self.stop = true;
+            }
+        }
+    }
+
+    /// Returns true once after an event asked to stop the execution
+    pub fn take_stop(&mut self) -> bool {
+        std::mem::take(&mut self.stop)
+    }
+}
+
+impl WatchEvent {
+    /// Describes the event with the location of the instruction, e.g.
+    /// `invariant violated (SP in 256..2047): RAM[0] 256 -> 5 at PC 12 (line 20)`
+    pub fn describe(&self, symbols: &ProgramSymbols) -> String {
+        let what = match self.kind {
+            WatchKind::Watchpoint(_) => format!("{} changed", self.name),
+            WatchKind::Violation => format!("invariant violated ({})", self.name),
+        };
+
+        format!(
+            "{}: RAM[{}] {} -> {} at {}, cycle {}",
+            what,
+            self.write.address,
+            self.write.previous,
+            self.write.value,
+            symbols.location(self.pc),
+            self.cycle
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(address: u16, previous: i16, value: i16) -> MemoryWrite {
+        MemoryWrite {
+            address,
+            previous,
+            value,
+        }
+    }
+
+    #[test]
+    fn parse_invariants() {
+        let symbols = ProgramSymbols::predefined();
+
+        let invariant = Invariant::parse("SP in 256..2047", &symbols).unwrap();
+        assert_eq!(
+            (invariant.address, invariant.min, invariant.max),
+            (0, 256, 2047)
+        );
+        assert!(invariant.holds(256) && invariant.holds(2047) && !invariant.holds(2048));
+
+        let invariant = Invariant::parse("THIS  not in SCREEN..24575", &symbols).unwrap();
+        assert_eq!(invariant.text, "THIS not in SCREEN..24575");
+        assert_eq!((invariant.address, invariant.min), (3, 16384));
+        assert!(invariant.holds(3000) && !invariant.holds(16384));
+
+        assert_eq!(
+            Invariant::parse("SP in 2047..256", &symbols),
+            Err(String::from("Invalid invariant: SP in 2047..256"))
+        );
+        assert_eq!(
+            Invariant::parse("FOO in 0..1", &symbols),
+            Err(String::from("Unknown symbol: FOO"))
+        );
+    }
+
+    #[test]
+    fn check_watchpoints_on_changes() {
+        let symbols = ProgramSymbols::predefined();
+        let mut monitor = Monitor::new();
+
+        monitor.watch("SP", WatchAction::Log, &symbols).unwrap();
+        monitor.watch("LCL", WatchAction::Break, &symbols).unwrap();
+
+        monitor.check(0, 10, write(0, 0, 256));
+        monitor.check(1, 11, write(0, 256, 256));
+        assert_eq!(monitor.events.len(), 1);
+        assert!(!monitor.take_stop());
+
+        monitor.check(2, 12, write(1, 0, 300));
+        assert_eq!(monitor.events.len(), 2);
+        assert!(monitor.take_stop());
+        assert!(!monitor.take_stop());
+    }
+
+    #[test]
+    fn check_invariants_on_writes() {
+        let symbols = ProgramSymbols::predefined();
+        let mut monitor = Monitor::new();
+        monitor
+            .invariants
+            .push(Invariant::parse("SP in 256..2047", &symbols).unwrap());
+
+        monitor.check(0, 10, write(0, 0, 256));
+        assert!(monitor.events.is_empty());
+
+        monitor.check(1, 12, write(0, 256, 5));
+        assert!(monitor.take_stop());
+        assert_eq!(
+            monitor.events[0].describe(&symbols),
+            "invariant violated (SP in 256..2047): RAM[0] 256 -> 5 at PC 12, cycle 1"
+        );
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+
+use asm2hack::code::disassemble;
+
+use crate::computer::ROM_SIZE;
+use crate::loader::ProgramSymbols;
+
+/// name of the routine of the instructions that come before any label
+const START_ROUTINE: &str = "(start)";
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Profile {
+    /// number of times the instruction at each ROM address was executed
+    pub counts: Vec<u64>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct RoutineProfile {
+    pub name: String,
+    /// lowest ROM address of the routine
+    pub start: u16,
+    /// number of instructions executed inside the routine
+    pub cycles: u64,
+    /// number of different instructions executed inside the routine
+    pub instructions: usize,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Checks if a label is the entry point of a VM function (`Class.function`),
+/// local labels and return addresses use the `Class.function$label` form
+pub fn is_function_label(label: &str) -> bool {
+    label.contains('.') && !label.contains('$')
+}
+
+/// Returns the percentage of the total cycles, 0 when nothing was executed
+fn percentage(cycles: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        cycles as f64 * 100.0 / total as f64
+    }
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Self {
+            counts: vec![0; ROM_SIZE],
+        }
+    }
+
+    /// Counts one execution of the instruction at a ROM address
+    pub fn record(&mut self, address: u16) {
+        self.counts[address as usize % ROM_SIZE] += 1;
+    }
+
+    /// Returns the number of instructions executed while profiling
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Returns the most executed ROM addresses and their count, the
+    /// lowest address first when two instructions have the same count
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The maximum number of addresses to return
+    pub fn hot_spots(&self, limit: usize) -> Vec<(u16, u64)> {
+        let mut hot_spots: Vec<(u16, u64)> = (0..)
+            .zip(self.counts.iter().copied())
+            .filter(|(_, count)| *count > 0)
+            .collect();
+
+        hot_spots.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        hot_spots.truncate(limit);
+        hot_spots
+    }
+
+    /// Aggregates the counts per routine, each routine owns the instructions
+    /// up to the next one and the most expensive routine comes first.
+    /// Programs translated from VM code use their functions as routines,
+    /// any other program uses each of its labels
+    ///
+    /// # Arguments
+    ///
+    /// * `symbols` - The symbols of the program
+    pub fn routines(&self, symbols: &ProgramSymbols) -> Vec<RoutineProfile> {
+        let functions = symbols.labels.keys().any(|label| is_function_label(label));
+
+        let mut labels: Vec<(u16, &str)> = symbols
+            .labels
+            .iter()
+            .filter(|(label, _)| !functions || is_function_label(label))
+            .map(|(label, address)| (*address, label.as_str()))
+            .collect();
+        labels.sort();
+        labels.dedup_by_key(|(address, _)| *address);
+
+        let mut routines: HashMap<&str, RoutineProfile> = HashMap::new();
+
+        for (address, count) in (0..).zip(self.counts.iter().copied()) {
+            if count == 0 {
+                continue;
+            }
+
+            // last label placed at or before the instruction
+            let name = match labels.partition_point(|(label_address, _)| *label_address <= address)
+            {
+                0 => START_ROUTINE,
+                index => labels[index - 1].1,
+            };
+
+            let routine = routines.entry(name).or_insert(RoutineProfile {
+                name: name.to_string(),
+                start: address,
+                cycles: 0,
+                instructions: 0,
+            });
+
+            routine.start = routine.start.min(address);
+            routine.cycles += count;
+            routine.instructions += 1;
+        }
+
+        let mut routines: Vec<RoutineProfile> = routines.into_values().collect();
+        routines.sort_by(|a, b| b.cycles.cmp(&a.cycles).then(a.start.cmp(&b.start)));
+        routines
+    }
+
+    /// Builds a text report with the total cycles, the hot spots of the
+    /// program (with their disassembly) and the cost of each routine
+    ///
+    /// # Arguments
+    ///
+    /// * `rom` - The instruction memory used to disassemble the hot spots
+    /// * `symbols` - The symbols of the program
+    /// * `limit` - The number of hot spots to report
+    pub fn report(&self, rom: &[u16], symbols: &ProgramSymbols, limit: usize) -> String {
+        let total = self.total();
+        let mut lines: Vec<String> = vec![format!("total cycles: {}", total)];
+
+        lines.push(String::new());
+        lines.push(String::from("hot spots:"));
+        lines.push(format!(
+            "{:>7}  {:>12}  {:>7}  {}",
+            "address", "count", "%", "instruction"
+        ));
+
+        for (address, count) in self.hot_spots(limit) {
+            let instruction = rom[address as usize];
+            let assembly = disassemble(instruction).unwrap_or(format!("{:016b}", instruction));
+
+            let assembly = match symbols.references.get(&address) {
+                Some(symbol) => format!("{} ({})", assembly, symbol),
+                None => assembly,
+            };
+
+            lines.push(format!(
+                "{:>7}  {:>12}  {:>6.2}%  {}",
+                address,
+                count,
+                percentage(count, total),
+                assembly
+            ));
+        }
+
+        lines.push(String::new());
+        lines.push(String::from("routines:"));
+        lines.push(format!(
+            "{:>7}  {:>12}  {:>7}  {:>12}  {}",
+            "start", "cycles", "%", "instructions", "routine"
+        ));
+
+        for routine in self.routines(symbols) {
+            lines.push(format!(
+                "{:>7}  {:>12}  {:>6.2}%  {:>12}  {}",
+                routine.start,
+                routine.cycles,
+                percentage(routine.cycles, total),
+                routine.instructions,
+                routine.name
+            ));
+        }
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::Computer;
+    use crate::loader::assemble_with_symbols;
+    use asm2hack::code::CodeConfiguration;
+
+    const PROGRAM: &str = "@3\nD=A\n(Main.loop)\nD=D-1\n@Main.loop$end\nD;JEQ\n@Main.loop\n0;JMP\n(Main.loop$end)\n@END\n0;JMP\n(END)\n@END\n0;JMP";
+
+    fn profile() -> (Computer, ProgramSymbols) {
+        let (instructions, symbols) =
+            assemble_with_symbols(PROGRAM, &CodeConfiguration::default()).unwrap();
+
+        let mut computer = Computer::new();
+        computer.load_rom(&instructions);
+        computer.profile = Some(Profile::new());
+        computer.run(None);
+
+        (computer, symbols)
+    }
+
+    #[test]
+    fn check_function_labels() {
+        assert!(is_function_label("Main.loop"));
+        assert!(!is_function_label("Main.loop$end"));
+        assert!(!is_function_label("Main.loop$ret.1"));
+        assert!(!is_function_label("END"));
+    }
+
+    #[test]
+    fn count_executed_instructions() {
+        let (computer, _) = profile();
+        let profile = computer.profile.unwrap();
+
+        assert_eq!(profile.total(), computer.cycles);
+        assert_eq!(profile.counts[0], 1);
+        assert_eq!(profile.counts[2], 3);
+        assert_eq!(profile.counts[6], 2);
+        assert_eq!(profile.hot_spots(2), vec![(2, 3), (3, 3)]);
+    }
+
+    #[test]
+    fn aggregate_counts_per_routine() {
+        let (computer, symbols) = profile();
+        let routines = computer.profile.unwrap().routines(&symbols);
+
+        assert_eq!(
+            routines,
+            vec![
+                RoutineProfile {
+                    name: String::from("Main.loop"),
+                    start: 2,
+                    cycles: 15,
+                    instructions: 7,
+                },
+                RoutineProfile {
+                    name: String::from("(start)"),
+                    start: 0,
+                    cycles: 2,
+                    instructions: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn report_profile() {
+        let (computer, symbols) = profile();
+        let report = computer
+            .profile
+            .as_ref()
+            .unwrap()
+            .report(&computer.rom, &symbols, 1);
+
+        assert_eq!(
+            report,
+            [
+                "total cycles: 17",
+                "",
+                "hot spots:",
+                "address         count        %  instruction",
+                "      2             3   17.65%  D=D-1",
+                "",
+                "routines:",
+                "  start        cycles        %  instructions  routine",
+                "      2            15   88.24%             7  Main.loop",
+                "      0             2   11.76%             2  (start)",
+            ]
+            .join("\n")
+        );
+    }
+}
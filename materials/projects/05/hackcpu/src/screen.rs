@@ -0,0 +1,238 @@
+use std::path::Path;
+
+use crate::computer::SCREEN_SIZE;
+
+/// width of the screen in pixels
+pub const SCREEN_WIDTH: usize = 512;
+
+/// height of the screen in pixels
+pub const SCREEN_HEIGHT: usize = 256;
+
+/// number of words used by each row of the screen
+const WORDS_PER_ROW: usize = SCREEN_WIDTH / 16;
+
+/// Checks if a pixel of the screen is black, the least significant bit
+/// of each word is the leftmost pixel of its 16 pixels
+///
+/// # Arguments
+///
+/// * `screen` - The words of the memory-mapped screen
+/// * `x` - The column of the pixel (0..512)
+/// * `y` - The row of the pixel (0..256)
+pub fn is_black(screen: &[i16], x: usize, y: usize) -> bool {
+    let word = screen[y * WORDS_PER_ROW + x / 16] as u16;
+    (word >> (x % 16)) & 1 == 1
+}
+
+/// Packs each row of the screen in bytes with 8 pixels per byte, the
+/// leftmost pixel is the most significant bit
+fn pack_rows(screen: &[i16], black: bool) -> Vec<Vec<u8>> {
+    if screen.len() != SCREEN_SIZE {
+        panic!("Invalid screen size: {} words", screen.len());
+    }
+
+    (0..SCREEN_HEIGHT)
+        .map(|y| {
+            (0..SCREEN_WIDTH / 8)
+                .map(|byte| {
+                    (0..8).fold(0u8, |packed, bit| {
+                        let pixel = is_black(screen, byte * 8 + bit, y) == black;
+                        packed | ((pixel as u8) << (7 - bit))
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Encodes the screen as a binary PBM image (P4), black pixels are 1
+pub fn to_pbm(screen: &[i16]) -> Vec<u8> {
+    let mut bytes = format!("P4\n{} {}\n", SCREEN_WIDTH, SCREEN_HEIGHT).into_bytes();
+
+    for row in pack_rows(screen, true) {
+        bytes.extend_from_slice(&row);
+    }
+
+    bytes
+}
+
+/// Computes the CRC-32 used by the chunks of PNG files
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc: u32 = 0xFFFFFFFF;
+
+    for byte in bytes.iter() {
+        crc ^= *byte as u32;
+
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+/// Computes the Adler-32 checksum used by zlib streams
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b): (u32, u32) = (1, 0);
+
+    for byte in bytes.iter() {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+
+    (b << 16) | a
+}
+
+/// Wraps the data in a zlib stream made of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut bytes: Vec<u8> = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = data.chunks(65535).collect();
+
+    for (index, block) in blocks.iter().enumerate() {
+        let length = block.len() as u16;
+
+        // the first bit marks the last block, the type 00 means stored
+        bytes.push((index == blocks.len() - 1) as u8);
+        bytes.extend_from_slice(&length.to_le_bytes());
+        bytes.extend_from_slice(&(!length).to_le_bytes());
+        bytes.extend_from_slice(block);
+    }
+
+    bytes.extend_from_slice(&adler32(data).to_be_bytes());
+    bytes
+}
+
+fn push_chunk(bytes: &mut Vec<u8>, kind: &[u8], data: &[u8]) {
+    bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let start = bytes.len();
+    bytes.extend_from_slice(kind);
+    bytes.extend_from_slice(data);
+
+    let crc = crc32(&bytes[start..]);
+    bytes.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Encodes the screen as a 1-bit grayscale PNG image, the image data is
+/// stored without compression to avoid external dependencies
+pub fn to_png(screen: &[i16]) -> Vec<u8> {
+    let mut bytes: Vec<u8> = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+    // width, height, bit depth 1, grayscale, deflate, no filter, no interlace
+    let mut header: Vec<u8> = Vec::new();
+    header.extend_from_slice(&(SCREEN_WIDTH as u32).to_be_bytes());
+    header.extend_from_slice(&(SCREEN_HEIGHT as u32).to_be_bytes());
+    header.extend_from_slice(&[1, 0, 0, 0, 0]);
+    push_chunk(&mut bytes, b"IHDR", &header);
+
+    // grayscale uses 1 for white, each row starts with its filter type
+    let mut data: Vec<u8> = Vec::new();
+    for row in pack_rows(screen, false) {
+        data.push(0);
+        data.extend_from_slice(&row);
+    }
+    push_chunk(&mut bytes, b"IDAT", &zlib_stored(&data));
+
+    push_chunk(&mut bytes, b"IEND", &[]);
+    bytes
+}
+
+/// Encodes the screen based on the extension of the output file
+///
+/// # Arguments
+///
+/// * `screen` - The words of the memory-mapped screen
+/// * `output` - The output file (.pbm or .png)
+pub fn to_image(screen: &[i16], output: &str) -> Vec<u8> {
+    if output.ends_with(".png") {
+        to_png(screen)
+    } else if output.ends_with(".pbm") {
+        to_pbm(screen)
+    } else {
+        panic!("Image format not supported: {}", output);
+    }
+}
+
+/// Adds a frame number to an output file, `screen.png` becomes `screen-0003.png`
+pub fn frame_file(output: &str, frame: usize) -> String {
+    let path = Path::new(output);
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => {
+            let name = path.with_extension("");
+            format!("{}-{:04}.{}", name.display(), frame, extension)
+        }
+        None => format!("{}-{:04}", output, frame),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen_with_pixels(pixels: &[(usize, usize)]) -> Vec<i16> {
+        let mut screen = vec![0i16; SCREEN_SIZE];
+
+        for (x, y) in pixels.iter() {
+            screen[y * WORDS_PER_ROW + x / 16] |= (1u16 << (x % 16)) as i16;
+        }
+
+        screen
+    }
+
+    #[test]
+    fn read_screen_pixels() {
+        let screen = screen_with_pixels(&[(0, 0), (17, 1), (511, 255)]);
+
+        assert!(is_black(&screen, 0, 0));
+        assert!(!is_black(&screen, 1, 0));
+        assert!(is_black(&screen, 17, 1));
+        assert!(is_black(&screen, 511, 255));
+    }
+
+    #[test]
+    fn encode_screen_as_pbm() {
+        let screen = screen_with_pixels(&[(0, 0), (9, 0), (511, 255)]);
+        let pbm = to_pbm(&screen);
+
+        let header = b"P4\n512 256\n";
+        assert_eq!(&pbm[..header.len()], header);
+        assert_eq!(pbm.len(), header.len() + 64 * 256);
+
+        // leftmost pixel is the most significant bit
+        assert_eq!(pbm[header.len()], 0b10000000);
+        assert_eq!(pbm[header.len() + 1], 0b01000000);
+        assert_eq!(pbm[pbm.len() - 1], 0b00000001);
+    }
+
+    #[test]
+    fn encode_screen_as_png() {
+        let screen = screen_with_pixels(&[(0, 0)]);
+        let png = to_png(&screen);
+
+        assert_eq!(&png[..8], &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]);
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+
+        // filter byte, then the first pixel black (0) and the rest white
+        let data = &png[33 + 8..];
+        assert_eq!(&data[..2], &[0x78, 0x01]);
+        assert_eq!(&data[7..9], &[0, 0b01111111]);
+    }
+
+    #[test]
+    fn compute_checksums() {
+        assert_eq!(crc32(b"IEND"), 0xAE426082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E60398);
+    }
+
+    #[test]
+    fn add_frame_to_file_name() {
+        assert_eq!(frame_file("screen.png", 3), "screen-0003.png");
+        assert_eq!(frame_file("./out/screen", 12), "./out/screen-0012");
+    }
+}
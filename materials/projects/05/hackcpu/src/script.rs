@@ -0,0 +1,779 @@
+use std::path::{Path, PathBuf};
+
+use asm2hack::code::CodeConfiguration;
+
+use crate::compare::{compare_line, ComparisonFailure};
+use crate::computer::{Computer, RAM_SIZE, ROM_SIZE};
+use crate::loader::load_program;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct OutputColumn {
+    /// variable to print (RAM[n], A, D, PC, time)
+    pub name: String,
+    /// format of the value: D (decimal), B (binary), X (hexadecimal), S (string)
+    pub format: char,
+    /// spaces before the value
+    pub left: usize,
+    /// width of the value
+    pub length: usize,
+    /// spaces after the value
+    pub right: usize,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Condition {
+    pub variable: String,
+    pub operator: String,
+    pub value: i64,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ScriptCommand {
+    Load(Option<String>),
+    OutputFile(String),
+    CompareTo(String),
+    OutputList(Vec<OutputColumn>),
+    Set(String, i64),
+    /// repeats the commands N times, without a count the official tools
+    /// repeat them forever while this runner (as an extension) stops once
+    /// the program halts in a tight loop or after a number of cycles,
+    /// interactive scripts like Fill.tst never halt and run until the cap
+    Repeat(Option<u64>, Vec<ScriptCommand>),
+    While(Condition, Vec<ScriptCommand>),
+    TickTock,
+    Tick,
+    Tock,
+    Output,
+    Echo(String),
+    ClearEcho,
+}
+
+/// cycles after which a repeat without a count stops by default
+pub const REPEAT_CYCLES: u64 = 10_000_000;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ScriptResult {
+    /// content of the output file
+    pub output: String,
+    pub output_file: Option<PathBuf>,
+    pub compare_file: Option<PathBuf>,
+    /// first line of the output that does not match the compare file
+    pub failure: Option<ComparisonFailure>,
+}
+
+/// Parses a value of the script, decimal by default or using the
+/// %D (decimal), %X (hexadecimal) and %B (binary) prefixes
+fn parse_value(value: &str) -> Option<i64> {
+    let parsed = match value.strip_prefix('%') {
+        Some(value) if value.len() > 1 => {
+            let (format, digits) = value.split_at(1);
+
+            match format {
+                "D" => digits.parse::<i64>().ok(),
+                "X" => i64::from_str_radix(digits, 16).ok(),
+                "B" => i64::from_str_radix(digits, 2).ok(),
+                _ => None,
+            }
+        }
+        Some(_) => None,
+        None => value.parse::<i64>().ok(),
+    };
+
+    parsed.filter(|value| (-32768..=65535).contains(value))
+}
+
+impl OutputColumn {
+    /// Parses a column of the output list such as `RAM[0]%D2.6.2`, the
+    /// format `%D1.6.1` is used when the column does not define one
+    pub fn parse(column: &str) -> Option<Self> {
+        let (name, format) = column.split_once('%').unwrap_or((column, "D1.6.1"));
+
+        let mut chars = format.chars();
+        let format = chars.next()?;
+
+        if !"DBXS".contains(format) || name.is_empty() {
+            return None;
+        }
+
+        let sizes: Vec<usize> = chars
+            .as_str()
+            .split('.')
+            .map(|size| size.parse::<usize>().ok())
+            .collect::<Option<Vec<usize>>>()?;
+
+        match sizes.as_slice() {
+            [left, length, right] => Some(Self {
+                name: name.to_string(),
+                format,
+                left: *left,
+                length: *length,
+                right: *right,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.left + self.length + self.right
+    }
+
+    /// Returns the name of the column centered in its width (truncated if
+    /// it does not fit, like `RAM[3006` for `RAM[3006]%D1.6.1`)
+    pub fn header(&self) -> String {
+        let width = self.width();
+        let name: String = self.name.chars().take(width).collect();
+        let padding = width - name.len();
+
+        format!(
+            "{}{}{}",
+            " ".repeat(padding / 2),
+            name,
+            " ".repeat(padding - padding / 2)
+        )
+    }
+
+    /// Formats a value using the format of the column
+    pub fn format(&self, value: i64) -> String {
+        let length = self.length;
+
+        let value = match self.format {
+            'B' => format!("{:0>length$}", format!("{:016b}", value as u16)),
+            'X' => format!("{:0>length$}", format!("{:04X}", value as u16)),
+            'S' => format!("{:<length$}", value),
+            _ => format!("{:>length$}", value),
+        };
+
+        // binary and hexadecimal values keep their least significant digits
+        let value = if value.len() > length {
+            match self.format {
+                'B' | 'X' => value[value.len() - length..].to_string(),
+                _ => "*".repeat(length),
+            }
+        } else {
+            value
+        };
+
+        format!(
+            "{}{}{}",
+            " ".repeat(self.left),
+            value,
+            " ".repeat(self.right)
+        )
+    }
+}
+
+/// Splits the script into tokens with their line number, comments are
+/// removed and `{`, `}`, `,` and `;` are returned as single tokens
+fn tokenize(input: &str) -> Vec<(String, usize)> {
+    let mut tokens: Vec<(String, usize)> = Vec::new();
+    let mut current = String::new();
+    let mut line = 1;
+    let mut chars = input.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        // line comments
+        if char == '/' && chars.peek() == Some(&'/') {
+            while chars.peek().is_some_and(|next| *next != '\n') {
+                chars.next();
+            }
+            continue;
+        }
+
+        // block comments
+        if char == '/' && chars.peek() == Some(&'*') {
+            chars.next();
+            let mut previous = ' ';
+
+            for next in chars.by_ref() {
+                if next == '\n' {
+                    line += 1;
+                }
+                if previous == '*' && next == '/' {
+                    break;
+                }
+                previous = next;
+            }
+            continue;
+        }
+
+        // quoted strings are kept as a single token including the quotes
+        if char == '"' {
+            current.push(char);
+
+            for next in chars.by_ref() {
+                current.push(next);
+                if next == '"' {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        if char.is_whitespace() || "{},;".contains(char) {
+            if !current.is_empty() {
+                tokens.push((current.clone(), line));
+                current.clear();
+            }
+
+            if !char.is_whitespace() {
+                tokens.push((char.to_string(), line));
+            }
+
+            if char == '\n' {
+                line += 1;
+            }
+            continue;
+        }
+
+        current.push(char);
+    }
+
+    if !current.is_empty() {
+        tokens.push((current, line));
+    }
+
+    tokens
+}
+
+/// Parses a single command of the script (everything between separators)
+fn parse_command(words: &[String], line: usize) -> ScriptCommand {
+    let arguments = &words[1..];
+
+    let command = match (words[0].as_str(), arguments) {
+        ("load", []) => Some(ScriptCommand::Load(None)),
+        ("load", [file]) => Some(ScriptCommand::Load(Some(file.clone()))),
+        ("output-file", [file]) => Some(ScriptCommand::OutputFile(file.clone())),
+        ("compare-to", [file]) => Some(ScriptCommand::CompareTo(file.clone())),
+        ("output-list", columns) => columns
+            .iter()
+            .map(|column| OutputColumn::parse(column))
+            .collect::<Option<Vec<OutputColumn>>>()
+            .map(ScriptCommand::OutputList),
+        ("set", [variable, value]) => {
+            parse_value(value).map(|value| ScriptCommand::Set(variable.clone(), value))
+        }
+        ("ticktock", []) => Some(ScriptCommand::TickTock),
+        ("tick", []) => Some(ScriptCommand::Tick),
+        ("tock", []) => Some(ScriptCommand::Tock),
+        ("output", []) => Some(ScriptCommand::Output),
+        ("echo", [text]) => Some(ScriptCommand::Echo(text.trim_matches('"').to_string())),
+        ("clear-echo", []) => Some(ScriptCommand::ClearEcho),
+        _ => None,
+    };
+
+    match command {
+        Some(command) => command,
+        None => panic!(
+            "Invalid script command at line {}: {}",
+            line,
+            words.join(" ")
+        ),
+    }
+}
+
+/// Parses the commands until the end of the script or the end of the block
+fn parse_block(
+    tokens: &[(String, usize)],
+    position: &mut usize,
+    nested: bool,
+) -> Vec<ScriptCommand> {
+    let mut commands: Vec<ScriptCommand> = Vec::new();
+
+    while *position < tokens.len() {
+        let (token, line) = &tokens[*position];
+
+        match token.as_str() {
+            "," | ";" => {
+                *position += 1;
+            }
+            "}" if nested => {
+                *position += 1;
+                return commands;
+            }
+            "}" => panic!("Unexpected closing brace at line {}", line),
+            "repeat" | "while" => {
+                let mut words: Vec<String> = Vec::new();
+                *position += 1;
+
+                while *position < tokens.len() && tokens[*position].0 != "{" {
+                    words.push(tokens[*position].0.clone());
+                    *position += 1;
+                }
+
+                if *position >= tokens.len() {
+                    panic!("Missing opening brace at line {}", line);
+                }
+
+                *position += 1;
+                let body = parse_block(tokens, position, true);
+
+                let command = match (token.as_str(), words.as_slice()) {
+                    ("repeat", []) => Some(ScriptCommand::Repeat(None, body)),
+                    ("repeat", [count]) => count
+                        .parse::<u64>()
+                        .ok()
+                        .map(|count| ScriptCommand::Repeat(Some(count), body)),
+                    ("while", [variable, operator, value])
+                        if ["=", "<>", "<", ">", "<=", ">="].contains(&operator.as_str()) =>
+                    {
+                        parse_value(value).map(|value| {
+                            ScriptCommand::While(
+                                Condition {
+                                    variable: variable.clone(),
+                                    operator: operator.clone(),
+                                    value,
+                                },
+                                body,
+                            )
+                        })
+                    }
+                    _ => None,
+                };
+
+                match command {
+                    Some(command) => commands.push(command),
+                    None => panic!(
+                        "Invalid script command at line {}: {} {}",
+                        line,
+                        token,
+                        words.join(" ")
+                    ),
+                }
+            }
+            _ => {
+                let mut words: Vec<String> = Vec::new();
+
+                while *position < tokens.len() && !"{},;".contains(tokens[*position].0.as_str()) {
+                    words.push(tokens[*position].0.clone());
+                    *position += 1;
+                }
+
+                commands.push(parse_command(&words, *line));
+            }
+        }
+    }
+
+    if nested {
+        panic!("Missing closing brace at the end of the script");
+    }
+
+    commands
+}
+
+/// Parses a test script written in the nand2tetris test-script language
+///
+/// # Arguments
+///
+/// * `input` - The content of the `.tst` file
+pub fn parse_script(input: &str) -> Vec<ScriptCommand> {
+    let tokens = tokenize(input);
+    let mut position = 0;
+
+    parse_block(&tokens, &mut position, false)
+}
+
+pub struct ScriptRunner {
+    pub computer: Computer,
+    /// configuration of the assembler for the `.asm` programs loaded
+    pub config: CodeConfiguration,
+    /// cycles after which a repeat without a count stops when the
+    /// program does not halt
+    pub repeat_cycles: u64,
+    /// directory used to resolve the files referenced by the script
+    directory: PathBuf,
+    commands: Vec<ScriptCommand>,
+    columns: Vec<OutputColumn>,
+    output: Vec<String>,
+    output_file: Option<PathBuf>,
+    compare_file: Option<PathBuf>,
+    compare: Option<Vec<String>>,
+    failure: Option<ComparisonFailure>,
+}
+
+impl ScriptRunner {
+    /// Creates a runner for a script, the files of the script are relative
+    /// to the given directory
+    pub fn new(script: &str, directory: &Path) -> Self {
+        Self {
+            computer: Computer::new(),
+            config: CodeConfiguration::default(),
+            repeat_cycles: REPEAT_CYCLES,
+            directory: directory.to_path_buf(),
+            commands: parse_script(script),
+            columns: Vec::new(),
+            output: Vec::new(),
+            output_file: None,
+            compare_file: None,
+            compare: None,
+            failure: None,
+        }
+    }
+
+    /// Creates a runner for a `.tst` file
+    pub fn from_file(input: &Path) -> Self {
+        let script = std::fs::read_to_string(input)
+            .unwrap_or_else(|_| panic!("failed to read script: {}", input.display()));
+
+        Self::new(&script, input.parent().unwrap_or(Path::new(".")))
+    }
+
+    fn read_variable(&self, variable: &str) -> Result<i64, String> {
+        Ok(match variable {
+            "A" => self.computer.a as i64,
+            "D" => self.computer.d as i64,
+            "PC" => self.computer.pc as i64,
+            "time" => self.computer.cycles as i64,
+            _ => match parse_index(variable)? {
+                ("RAM", index) => self.computer.peek_memory(index) as i64,
+                (_, index) => self.computer.rom[index as usize] as i64,
+            },
+        })
+    }
+
+    fn write_variable(&mut self, variable: &str, value: i64) -> Result<(), String> {
+        match variable {
+            "A" => self.computer.a = value as i16,
+            "D" => self.computer.d = value as i16,
+            "PC" => self.computer.pc = value as u16,
+            _ => match parse_index(variable)? {
+                ("RAM", index) => self.computer.write_memory(index, value as i16),
+                (_, index) => self.computer.rom[index as usize] = value as u16,
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Adds a line to the output and compares it with the compare file
+    fn write_line(&mut self, line: String) {
+        self.output.push(line);
+
+        let number = self.output.len();
+        let actual = self.output[number - 1].trim_end();
+
+        if let Some(compare) = &self.compare {
+            let expected = compare
+                .get(number - 1)
+                .map(|line| line.trim_end())
+                .unwrap_or("");
+
+            // rows are compared cell by cell ignoring whitespace
+            if let Some(cell) = compare_line(expected, actual) {
+                self.failure = Some(ComparisonFailure {
+                    line: number,
+                    column: self.columns.get(cell).map(|column| column.name.clone()),
+                    expected: expected.to_string(),
+                    actual: actual.to_string(),
+                });
+            }
+        }
+    }
+
+    fn execute(&mut self, command: &ScriptCommand) -> Result<(), String> {
+        match command {
+            ScriptCommand::Load(file) => {
+                if let Some(file) = file {
+                    let program = load_program(&self.directory.join(file), &self.config)?;
+                    self.computer.load_rom(&program);
+                }
+                self.computer.reset();
+            }
+            ScriptCommand::OutputFile(file) => {
+                self.output_file = Some(self.directory.join(file));
+            }
+            ScriptCommand::CompareTo(file) => {
+                let compare_file = self.directory.join(file);
+                let content = std::fs::read_to_string(&compare_file)
+                    .map_err(|_| format!("failed to read file: {}", compare_file.display()))?;
+
+                self.compare = Some(content.lines().map(|line| line.to_string()).collect());
+                self.compare_file = Some(compare_file);
+            }
+            ScriptCommand::OutputList(columns) => {
+                self.columns = columns.clone();
+
+                let header: Vec<String> = columns.iter().map(|column| column.header()).collect();
+                self.write_line(format!("|{}|", header.join("|")));
+            }
+            ScriptCommand::Set(variable, value) => self.write_variable(variable, *value)?,
+            ScriptCommand::Repeat(count, commands) => {
+                let mut iteration: u64 = 0;
+                let start = self.computer.cycles;
+
+                // without a count the commands are repeated until the program
+                // halts or runs the cycle cap, instead of forever (extension
+                // of the nand2tetris tools)
+                while count.map_or(
+                    !self.computer.is_halted() && self.computer.cycles - start < self.repeat_cycles,
+                    |count| iteration < count,
+                ) {
+                    let cycles = self.computer.cycles;
+
+                    self.execute_all(commands)?;
+                    iteration += 1;
+
+                    if self.failure.is_some() {
+                        return Ok(());
+                    }
+
+                    // a body without cycles would never reach the cap
+                    if count.is_none() && self.computer.cycles == cycles {
+                        return Ok(());
+                    }
+                }
+            }
+            ScriptCommand::While(condition, commands) => {
+                while self.evaluate(condition)? && self.failure.is_none() {
+                    self.execute_all(commands)?;
+                }
+            }
+            ScriptCommand::TickTock | ScriptCommand::Tock => self.computer.step(),
+            ScriptCommand::Tick => {}
+            ScriptCommand::Output => {
+                let values: Vec<String> = self
+                    .columns
+                    .iter()
+                    .map(|column| Ok(column.format(self.read_variable(&column.name)?)))
+                    .collect::<Result<Vec<String>, String>>()?;
+
+                self.write_line(format!("|{}|", values.join("|")));
+            }
+            ScriptCommand::Echo(text) => println!("{}", text),
+            ScriptCommand::ClearEcho => {}
+        }
+
+        Ok(())
+    }
+
+    fn execute_all(&mut self, commands: &[ScriptCommand]) -> Result<(), String> {
+        for command in commands.iter() {
+            if self.failure.is_some() {
+                return Ok(());
+            }
+
+            self.execute(command)?;
+        }
+
+        Ok(())
+    }
+
+    fn evaluate(&self, condition: &Condition) -> Result<bool, String> {
+        let value = self.read_variable(&condition.variable)?;
+
+        Ok(match condition.operator.as_str() {
+            "=" => value == condition.value,
+            "<>" => value != condition.value,
+            "<" => value < condition.value,
+            ">" => value > condition.value,
+            "<=" => value <= condition.value,
+            _ => value >= condition.value,
+        })
+    }
+
+    /// Executes the script, the execution stops at the first line of the
+    /// output that does not match the compare file
+    ///
+    /// # Returns
+    ///
+    /// * The result of the script, or an error when a command cannot be
+    ///   executed (e.g. a program that does not load or an unknown variable)
+    pub fn run(&mut self) -> Result<ScriptResult, String> {
+        let commands = std::mem::take(&mut self.commands);
+        let executed = self.execute_all(&commands);
+        self.commands = commands;
+        executed?;
+
+        let mut output = self.output.join("\n");
+        if !output.is_empty() {
+            output.push('\n');
+        }
+
+        Ok(ScriptResult {
+            output,
+            output_file: self.output_file.clone(),
+            compare_file: self.compare_file.clone(),
+            failure: self.failure.clone(),
+        })
+    }
+}
+
+/// Parses an indexed variable such as `RAM[256]` into its memory (RAM or
+/// ROM) and an index inside of it
+fn parse_index(variable: &str) -> Result<(&str, u16), String> {
+    let (name, index) = variable
+        .strip_suffix(']')
+        .and_then(|variable| variable.split_once('['))
+        .filter(|(name, _)| ["RAM", "ROM"].contains(name))
+        .ok_or(format!("Unknown script variable: {}", variable))?;
+
+    let size = if name == "RAM" { RAM_SIZE } else { ROM_SIZE };
+
+    match index.parse::<u16>() {
+        Ok(index) if (index as usize) < size => Ok((name, index)),
+        _ => Err(format!("Index out of range for {}: {}", name, index)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_output_column() {
+        let column = OutputColumn::parse("RAM[0]%D2.6.2").unwrap();
+
+        assert_eq!(column.name, "RAM[0]");
+        assert_eq!(column.format, 'D');
+        assert_eq!((column.left, column.length, column.right), (2, 6, 2));
+
+        assert_eq!(OutputColumn::parse("RAM[0]%Q1.6.1"), None);
+        assert_eq!(OutputColumn::parse("RAM[0]%D1.6"), None);
+    }
+
+    #[test]
+    fn format_output_column() {
+        let column = OutputColumn::parse("RAM[0]%D2.6.2").unwrap();
+        assert_eq!(column.header(), "  RAM[0]  ");
+        assert_eq!(column.format(257), "     257  ");
+
+        let column = OutputColumn::parse("RAM[3006]%D1.6.1").unwrap();
+        assert_eq!(column.header(), "RAM[3006");
+        assert_eq!(column.format(-91), "    -91 ");
+
+        let column = OutputColumn::parse("RAM[11]%D1.6.1").unwrap();
+        assert_eq!(column.header(), "RAM[11] ");
+
+        let column = OutputColumn::parse("A%B1.16.1").unwrap();
+        assert_eq!(column.format(-1), " 1111111111111111 ");
+
+        let column = OutputColumn::parse("A%X0.2.0").unwrap();
+        assert_eq!(column.format(0x1F3), "F3");
+    }
+
+    #[test]
+    fn parse_script_commands() {
+        let commands = parse_script(
+            "// header\nload Add.asm,\noutput-list RAM[0]%D2.6.2 A;\n/* block\ncomment */\nset RAM[0] %X10,\nrepeat 2 {\n  ticktock;\n}\nwhile PC < 3 { tick, tock; }\noutput;",
+        );
+
+        assert_eq!(
+            commands,
+            vec![
+                ScriptCommand::Load(Some("Add.asm".to_string())),
+                ScriptCommand::OutputList(vec![
+                    OutputColumn::parse("RAM[0]%D2.6.2").unwrap(),
+                    OutputColumn::parse("A%D1.6.1").unwrap(),
+                ]),
+                ScriptCommand::Set("RAM[0]".to_string(), 16),
+                ScriptCommand::Repeat(Some(2), vec![ScriptCommand::TickTock]),
+                ScriptCommand::While(
+                    Condition {
+                        variable: "PC".to_string(),
+                        operator: "<".to_string(),
+                        value: 3,
+                    },
+                    vec![ScriptCommand::Tick, ScriptCommand::Tock]
+                ),
+                ScriptCommand::Output,
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid script command at line 2: set RAM[0]")]
+    fn parse_script_with_invalid_command() {
+        parse_script("output;\nset RAM[0];");
+    }
+
+    #[test]
+    #[should_panic(expected = "Missing closing brace at the end of the script")]
+    fn parse_script_without_closing_brace() {
+        parse_script("repeat 10 { ticktock;");
+    }
+
+    #[test]
+    fn run_script_with_loaded_program() {
+        let mut runner = ScriptRunner::new(
+            "output-list RAM[0]%D1.6.1 D%D1.6.1;\nset RAM[0] 3,\nrepeat 4 { ticktock; }\noutput;",
+            Path::new("."),
+        );
+
+        // @0, D=M, @0, M=D+1
+        runner
+            .computer
+            .load_rom(&[0, 0b1111110000010000, 0, 0b1110011111001000]);
+
+        let result = runner.run().unwrap();
+
+        assert_eq!(result.output, "| RAM[0] |   D    |\n|      4 |      3 |\n");
+        assert_eq!(result.failure, None);
+        assert_eq!(runner.computer.cycles, 4);
+    }
+
+    #[test]
+    fn run_script_until_condition() {
+        let mut runner = ScriptRunner::new("while RAM[0] <> 10 { ticktock; }", Path::new("."));
+
+        // @0, M=M+1, @0, 0;JMP
+        runner
+            .computer
+            .load_rom(&[0, 0b1111110111001000, 0, 0b1110101010000111]);
+
+        runner.run().unwrap();
+
+        assert_eq!(runner.computer.read_memory(0), 10);
+    }
+
+    #[test]
+    fn run_script_repeat_without_count_until_halt() {
+        let mut runner = ScriptRunner::new("repeat { ticktock; }", Path::new("."));
+
+        // @0, M=M+1, @2, 0;JMP (halts jumping to itself)
+        runner
+            .computer
+            .load_rom(&[0, 0b1111110111001000, 2, 0b1110101010000111]);
+
+        runner.run().unwrap();
+
+        assert!(runner.computer.is_halted());
+        assert_eq!(runner.computer.read_memory(0), 1);
+    }
+
+    #[test]
+    fn run_script_repeat_without_count_until_cycle_cap() {
+        let mut runner = ScriptRunner::new("repeat { ticktock; }", Path::new("."));
+        runner.repeat_cycles = 1000;
+
+        // @0, M=M+1, @0, 0;JMP (loops forever)
+        runner
+            .computer
+            .load_rom(&[0, 0b1111110111001000, 0, 0b1110101010000111]);
+
+        runner.run().unwrap();
+
+        assert_eq!(runner.computer.cycles, 1000);
+        assert_eq!(runner.computer.read_memory(0), 250);
+    }
+
+    #[test]
+    fn run_script_with_variables_out_of_memory() {
+        let mut runner = ScriptRunner::new("set ROM[32768] 1;", Path::new("."));
+        assert_eq!(
+            runner.run(),
+            Err(String::from("Index out of range for ROM: 32768"))
+        );
+
+        let mut runner =
+            ScriptRunner::new("output-list RAM[40000]%D1.6.1;\noutput;", Path::new("."));
+        assert_eq!(
+            runner.run(),
+            Err(String::from("Index out of range for RAM: 40000"))
+        );
+
+        let mut runner = ScriptRunner::new("while X = 0 { ticktock; }", Path::new("."));
+        assert_eq!(
+            runner.run(),
+            Err(String::from("Unknown script variable: X"))
+        );
+    }
+}
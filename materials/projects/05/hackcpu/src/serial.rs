@@ -0,0 +1,151 @@
+use std::io::{Read, Write};
+
+use crate::computer::KBD_ADDRESS;
+use crate::device::Device;
+
+/// default address of the output register, the input register follows it
+pub const SERIAL_ADDRESS: u16 = KBD_ADDRESS + 1;
+
+/// value of the input register once the input is exhausted
+pub const END_OF_INPUT: i16 = -1;
+
+/// Serial console with two registers: each word written to the output
+/// register is emitted as a character, the input register holds the next
+/// character of the input until the program writes to it to consume it
+pub struct SerialConsole {
+    /// address of the output register, the input register is the next word
+    pub address: u16,
+    input: Box<dyn Read>,
+    /// character waiting in the input register
+    current: Option<i16>,
+    /// characters written by the program
+    pub output: Vec<u8>,
+    /// print the characters on the standard output as they are written
+    pub echo: bool,
+}
+
+impl SerialConsole {
+    /// Creates a console reading its input from a reader
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address of the output register
+    /// * `input` - The characters given to the input register
+    /// * `echo` - Print the output on the standard output
+    pub fn new(address: u16, input: Box<dyn Read>, echo: bool) -> Self {
+        Self {
+            address,
+            input,
+            current: None,
+            output: Vec::new(),
+            echo,
+        }
+    }
+
+    /// Creates a console connected to the standard input and output
+    pub fn stdio(address: u16) -> Self {
+        Self::new(address, Box::new(std::io::stdin()), true)
+    }
+
+    /// Returns the output written by the program as text
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.output).to_string()
+    }
+}
+
+impl Device for SerialConsole {
+    /// The output register always reads 0 (ready), the input register
+    /// reads the next character, waiting for it if needed
+    fn read(&mut self, address: u16) -> i16 {
+        if address == self.address {
+            return 0;
+        }
+
+        *self.current.get_or_insert_with(|| {
+            let mut byte = [0u8; 1];
+
+            match self.input.read(&mut byte) {
+                Ok(1) => byte[0] as i16,
+                _ => END_OF_INPUT,
+            }
+        })
+    }
+
+    /// The input register is only known once a read waited for it
+    fn peek(&self, address: u16) -> Option<i16> {
+        if address == self.address {
+            Some(0)
+        } else {
+            self.current
+        }
+    }
+
+    fn write(&mut self, address: u16, value: i16) {
+        if address != self.address {
+            // writing the input register consumes its character
+            if self.current != Some(END_OF_INPUT) {
+                self.current = None;
+            }
+
+            return;
+        }
+
+        let byte = value as u8;
+        self.output.push(byte);
+
+        if self.echo {
+            let mut stdout = std::io::stdout();
+            let _ = stdout.write_all(&[byte]);
+            let _ = stdout.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn console(input: &str) -> SerialConsole {
+        SerialConsole::new(
+            SERIAL_ADDRESS,
+            Box::new(std::io::Cursor::new(input.as_bytes().to_vec())),
+            false,
+        )
+    }
+
+    #[test]
+    fn write_characters_to_output() {
+        let mut console = console("");
+
+        for char in "ok\n".chars() {
+            console.write(SERIAL_ADDRESS, char as i16);
+        }
+
+        assert_eq!(console.text(), "ok\n");
+        assert_eq!(console.read(SERIAL_ADDRESS), 0);
+    }
+
+    #[test]
+    fn read_characters_from_input() {
+        let mut console = console("ab");
+        let input = SERIAL_ADDRESS + 1;
+
+        // peeking does not wait for the input
+        assert_eq!(console.peek(input), None);
+
+        // the character stays until the program consumes it
+        assert_eq!(console.read(input), 97);
+        assert_eq!(console.peek(input), Some(97));
+        assert_eq!(console.read(input), 97);
+
+        console.write(input, 0);
+        assert_eq!(console.read(input), 98);
+
+        console.write(input, 0);
+        assert_eq!(console.read(input), END_OF_INPUT);
+
+        console.write(input, 0);
+        assert_eq!(console.read(input), END_OF_INPUT);
+        assert!(console.output.is_empty());
+    }
+}
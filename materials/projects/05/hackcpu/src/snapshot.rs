@@ -0,0 +1,212 @@
+use crate::computer::{Computer, RAM_SIZE};
+
+/// first line of a snapshot file
+const HEADER: &str = "// hackcpu snapshot";
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Snapshot {
+    /// hash of the loaded program, a snapshot only restores on the same program
+    pub rom_hash: u64,
+    pub ram: Vec<i16>,
+    pub a: i16,
+    pub d: i16,
+    pub pc: u16,
+    pub cycles: u64,
+}
+
+/// Hashes the instructions of a program (64-bit FNV-1a over each word)
+///
+/// # Arguments
+///
+/// * `program` - The instructions loaded in the ROM
+pub fn rom_hash(program: &[u16]) -> u64 {
+    program.iter().fold(0xcbf29ce484222325, |hash, word| {
+        word.to_le_bytes().iter().fold(hash, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        })
+    })
+}
+
+impl Snapshot {
+    /// Captures the registers and the memory of a computer
+    pub fn capture(computer: &Computer) -> Self {
+        Self {
+            rom_hash: rom_hash(&computer.rom[..computer.program_size]),
+            ram: computer.memory(),
+            a: computer.a,
+            d: computer.d,
+            pc: computer.pc,
+            cycles: computer.cycles,
+        }
+    }
+
+    /// Restores the registers and the memory of a computer running the
+    /// program of the snapshot, the keyboard script continues from the
+    /// cycle of the snapshot and the trace starts over
+    pub fn restore(&self, computer: &mut Computer) -> Result<(), String> {
+        let rom_hash = rom_hash(&computer.rom[..computer.program_size]);
+
+        if rom_hash != self.rom_hash {
+            return Err(format!(
+                "Snapshot of another program: ROM hash {:016x}, expected {:016x}",
+                self.rom_hash, rom_hash
+            ));
+        }
+
+        computer.restore_memory(&self.ram);
+        computer.a = self.a;
+        computer.d = self.d;
+        computer.pc = self.pc;
+        computer.cycles = self.cycles;
+
+        if let Some(keyboard) = &mut computer.keyboard {
+            keyboard.seek(self.cycles);
+        }
+
+        if let Some(trace) = &mut computer.trace {
+            trace.entries.clear();
+        }
+
+        Ok(())
+    }
+
+    /// Formats the snapshot as text, the registers first and then the
+    /// words of RAM that are not zero, e.g. `RAM[0]=256`
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "{}\nrom {:016x}\ncycles {}\npc {}\na {}\nd {}\n",
+            HEADER, self.rom_hash, self.cycles, self.pc, self.a, self.d
+        );
+
+        for (address, value) in self.ram.iter().enumerate() {
+            if *value != 0 {
+                text.push_str(&format!("RAM[{}]={}\n", address, value));
+            }
+        }
+
+        text
+    }
+
+    /// Parses a snapshot saved with `to_text`
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The content of the snapshot file
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut lines = input.lines().enumerate();
+
+        if lines.next().map(|(_, line)| line.trim()) != Some(HEADER) {
+            return Err(String::from("Invalid snapshot: missing header"));
+        }
+
+        let mut snapshot = Self {
+            rom_hash: 0,
+            ram: vec![0; RAM_SIZE],
+            a: 0,
+            d: 0,
+            pc: 0,
+            cycles: 0,
+        };
+        let mut rom_hash: Option<u64> = None;
+
+        for (index, line) in lines {
+            let line = line.trim();
+            let invalid = || format!("Invalid snapshot at line {}: {}", index + 1, line);
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(word) = line.strip_prefix("RAM[") {
+                let (address, value) = word.split_once("]=").ok_or_else(invalid)?;
+                let address = address
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|address| *address < RAM_SIZE)
+                    .ok_or_else(invalid)?;
+
+                snapshot.ram[address] = value.parse::<i16>().map_err(|_| invalid())?;
+                continue;
+            }
+
+            match line.split_once(' ').ok_or_else(invalid)? {
+                ("rom", hash) => {
+                    rom_hash = Some(u64::from_str_radix(hash, 16).map_err(|_| invalid())?)
+                }
+                ("cycles", cycles) => snapshot.cycles = cycles.parse().map_err(|_| invalid())?,
+                ("pc", pc) => snapshot.pc = pc.parse().map_err(|_| invalid())?,
+                ("a", a) => snapshot.a = a.parse().map_err(|_| invalid())?,
+                ("d", d) => snapshot.d = d.parse().map_err(|_| invalid())?,
+                _ => return Err(invalid()),
+            }
+        }
+
+        snapshot.rom_hash = rom_hash.ok_or("Invalid snapshot: missing ROM hash")?;
+
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn computer(program: &[u16]) -> Computer {
+        let mut computer = Computer::new();
+        computer.load_rom(program);
+        computer
+    }
+
+    #[test]
+    fn hash_programs() {
+        assert_eq!(rom_hash(&[]), 0xcbf29ce484222325);
+        assert_ne!(rom_hash(&[1, 2]), rom_hash(&[2, 1]));
+    }
+
+    #[test]
+    fn save_and_parse_snapshot() {
+        let mut computer = computer(&[7, 0xEC10]);
+        computer.step();
+        computer.write_memory(0, 256);
+        computer.write_memory(24576, -1);
+
+        let snapshot = Snapshot::capture(&computer);
+        let text = snapshot.to_text();
+
+        assert!(text.starts_with("// hackcpu snapshot\nrom "));
+        assert!(text.ends_with("cycles 1\npc 1\na 7\nd 0\nRAM[0]=256\nRAM[24576]=-1\n"));
+        assert_eq!(Snapshot::parse(&text), Ok(snapshot));
+    }
+
+    #[test]
+    fn restore_snapshot_on_same_program() {
+        let mut original = computer(&[7, 0xEC10, 0]);
+        original.run(Some(2));
+
+        let snapshot = Snapshot::capture(&original);
+
+        let mut computer = computer(&[7, 0xEC10, 0]);
+        assert_eq!(snapshot.restore(&mut computer), Ok(()));
+        assert_eq!(computer, original);
+
+        let mut other = self::computer(&[8, 0xEC10, 0]);
+        assert!(snapshot.restore(&mut other).is_err());
+        assert_eq!(other.cycles, 0);
+    }
+
+    #[test]
+    fn reject_invalid_snapshots() {
+        assert_eq!(
+            Snapshot::parse("pc 1"),
+            Err(String::from("Invalid snapshot: missing header"))
+        );
+        assert_eq!(
+            Snapshot::parse("// hackcpu snapshot\npc 1\n"),
+            Err(String::from("Invalid snapshot: missing ROM hash"))
+        );
+        assert_eq!(
+            Snapshot::parse("// hackcpu snapshot\nRAM[40000]=1\n"),
+            Err(String::from("Invalid snapshot at line 2: RAM[40000]=1"))
+        );
+    }
+}
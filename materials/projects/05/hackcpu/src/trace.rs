@@ -0,0 +1,169 @@
+use std::collections::VecDeque;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct MemoryWrite {
+    pub address: u16,
+    /// value of the word before the write, used to step backwards
+    pub previous: i16,
+    pub value: i16,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct TraceEntry {
+    /// number of cycles executed before the instruction
+    pub cycle: u64,
+    /// address of the executed instruction
+    pub pc: u16,
+    /// registers before the instruction
+    pub a: i16,
+    pub d: i16,
+    /// key set by the keyboard script before the instruction
+    pub key: Option<MemoryWrite>,
+    /// word of RAM written by the instruction (`M=...`)
+    pub write: Option<MemoryWrite>,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Trace {
+    /// executed instructions, the oldest first
+    pub entries: VecDeque<TraceEntry>,
+    /// maximum number of entries kept, older entries are dropped
+    pub limit: Option<usize>,
+}
+
+impl Trace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a trace that only keeps the last executed instructions
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            limit: Some(limit),
+        }
+    }
+
+    pub fn record(&mut self, entry: TraceEntry) {
+        if self.limit.is_some_and(|limit| self.entries.len() >= limit) {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(entry);
+    }
+
+    /// Returns the last writes to a word of RAM, the most recent first
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The RAM address written
+    /// * `limit` - The maximum number of writes to return
+    pub fn writes_to(&self, address: u16, limit: usize) -> Vec<(&TraceEntry, MemoryWrite)> {
+        self.entries
+            .iter()
+            .rev()
+            .flat_map(|entry| {
+                [entry.write, entry.key]
+                    .into_iter()
+                    .flatten()
+                    .filter(|write| write.address == address)
+                    .map(move |write| (entry, write))
+            })
+            .take(limit)
+            .collect()
+    }
+
+    /// Formats the trace as text, one executed instruction per line with
+    /// the cycle, the PC, the registers before the instruction and the
+    /// words written, e.g. `12 5 256 -1 RAM[0]=257`
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("// cycle pc A D writes\n");
+
+        for entry in self.entries.iter() {
+            text.push_str(&format!(
+                "{} {} {} {}",
+                entry.cycle, entry.pc, entry.a, entry.d
+            ));
+
+            for write in [entry.key, entry.write].iter().flatten() {
+                text.push_str(&format!(" RAM[{}]={}", write.address, write.value));
+            }
+
+            text.push('\n');
+        }
+
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(cycle: u64, write: Option<MemoryWrite>) -> TraceEntry {
+        TraceEntry {
+            cycle,
+            pc: cycle as u16,
+            a: 0,
+            d: -1,
+            key: None,
+            write,
+        }
+    }
+
+    #[test]
+    fn keep_last_entries_with_limit() {
+        let mut trace = Trace::with_limit(2);
+
+        for cycle in 0..5 {
+            trace.record(entry(cycle, None));
+        }
+
+        let cycles: Vec<u64> = trace.entries.iter().map(|entry| entry.cycle).collect();
+        assert_eq!(cycles, vec![3, 4]);
+    }
+
+    #[test]
+    fn find_writes_to_address() {
+        let mut trace = Trace::new();
+        let write = |value| {
+            Some(MemoryWrite {
+                address: 0,
+                previous: value - 1,
+                value,
+            })
+        };
+
+        trace.record(entry(0, write(256)));
+        trace.record(entry(1, None));
+        trace.record(entry(2, write(257)));
+
+        let writes = trace.writes_to(0, 10);
+
+        assert_eq!(writes.len(), 2);
+        assert_eq!(writes[0].0.cycle, 2);
+        assert_eq!(writes[0].1.value, 257);
+        assert_eq!(writes[1].1.previous, 255);
+        assert!(trace.writes_to(1, 10).is_empty());
+    }
+
+    #[test]
+    fn format_trace_as_text() {
+        let mut trace = Trace::new();
+
+        trace.record(entry(0, None));
+        trace.record(entry(
+            1,
+            Some(MemoryWrite {
+                address: 256,
+                previous: 0,
+                value: 7,
+            }),
+        ));
+
+        assert_eq!(
+            trace.to_text(),
+            "// cycle pc A D writes\n0 0 0 -1\n1 1 0 -1 RAM[256]=7\n"
+        );
+    }
+}
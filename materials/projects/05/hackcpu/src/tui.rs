@@ -0,0 +1,359 @@
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{
+    self, Event, KeyCode, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::{cursor, execute, queue, style, terminal};
+
+use crate::computer::{Computer, StopReason};
+use crate::engine::Engine;
+use crate::screen::{is_black, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// number of frames drawn per second
+const FPS: u64 = 30;
+
+/// time a key stays pressed when the terminal does not report releases,
+/// long enough to cover the delay before the terminal repeats a key
+const KEY_HOLD: Duration = Duration::from_millis(500);
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Renderer {
+    /// 2x4 pixels per character (U+2800..U+28FF)
+    Braille,
+    /// 1x2 pixels per character (upper and lower half blocks)
+    HalfBlocks,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TuiOptions {
+    pub renderer: Renderer,
+    /// number of screen pixels merged in each rendered pixel (per axis)
+    pub scale: usize,
+    /// number of cycles executed per second
+    pub speed: u64,
+    /// maximum number of cycles to execute (None to run until the program stops)
+    pub max_cycles: Option<u64>,
+}
+
+/// Checks if any pixel of a block of the screen is black, so thin lines
+/// stay visible when the screen is scaled down
+fn is_block_black(screen: &[i16], x: usize, y: usize, scale: usize) -> bool {
+    (y * scale..(y + 1) * scale)
+        .any(|row| (x * scale..(x + 1) * scale).any(|column| is_black(screen, column, row)))
+}
+
+/// Renders the screen as lines of text, black pixels are drawn
+///
+/// # Arguments
+///
+/// * `screen` - The words of the memory-mapped screen
+/// * `renderer` - The characters to use for the pixels
+/// * `scale` - The number of pixels merged in each rendered pixel (1, 2, 4...)
+pub fn render(screen: &[i16], renderer: Renderer, scale: usize) -> Vec<String> {
+    let (width, height) = (SCREEN_WIDTH / scale, SCREEN_HEIGHT / scale);
+    let pixel = |x: usize, y: usize| x < width && y < height && is_block_black(screen, x, y, scale);
+
+    match renderer {
+        Renderer::Braille => {
+            // bit of each dot of a braille cell, by row then column
+            const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+            (0..height.div_ceil(4))
+                .map(|row| {
+                    (0..width.div_ceil(2))
+                        .map(|column| {
+                            let bits = (0..4)
+                                .flat_map(|dy| (0..2).map(move |dx| (dx, dy)))
+                                .filter(|(dx, dy)| pixel(column * 2 + dx, row * 4 + dy))
+                                .fold(0, |bits, (dx, dy)| bits | DOTS[dy][dx]);
+
+                            char::from_u32(0x2800 + bits).unwrap()
+                        })
+                        .collect()
+                })
+                .collect()
+        }
+        Renderer::HalfBlocks => (0..height.div_ceil(2))
+            .map(|row| {
+                (0..width)
+                    .map(|x| match (pixel(x, row * 2), pixel(x, row * 2 + 1)) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    })
+                    .collect()
+            })
+            .collect(),
+    }
+}
+
+/// Returns the Hack key code of a key pressed in the terminal
+pub fn hack_key(code: KeyCode) -> Option<i16> {
+    let key = match code {
+        KeyCode::Enter => 128,
+        KeyCode::Backspace => 129,
+        KeyCode::Left => 130,
+        KeyCode::Up => 131,
+        KeyCode::Right => 132,
+        KeyCode::Down => 133,
+        KeyCode::Home => 134,
+        KeyCode::End => 135,
+        KeyCode::PageUp => 136,
+        KeyCode::PageDown => 137,
+        KeyCode::Insert => 138,
+        KeyCode::Delete => 139,
+        KeyCode::Esc => 140,
+        KeyCode::F(number) if (1..=12).contains(&number) => 140 + number as i16,
+        KeyCode::Char(char) if (char as u32) < 128 => char as i16,
+        _ => return None,
+    };
+
+    Some(key)
+}
+
+/// Restores the terminal when the runner stops, even after a panic
+struct TerminalGuard {
+    enhanced: bool,
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let mut stdout = std::io::stdout();
+
+        if self.enhanced {
+            let _ = execute!(stdout, PopKeyboardEnhancementFlags);
+        }
+
+        let _ = execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Draws the rendered screen and a status line, lines are cut to the
+/// size of the terminal to avoid scrolling
+fn draw(lines: &[String], status: &str) -> std::io::Result<()> {
+    let (columns, rows) = terminal::size()?;
+    let mut stdout = std::io::stdout();
+
+    for (row, line) in lines
+        .iter()
+        .take(rows.saturating_sub(1) as usize)
+        .enumerate()
+    {
+        let line: String = line.chars().take(columns as usize).collect();
+        queue!(stdout, cursor::MoveTo(0, row as u16), style::Print(line))?;
+    }
+
+    let status: String = status.chars().take(columns as usize).collect();
+    queue!(
+        stdout,
+        cursor::MoveTo(0, rows.saturating_sub(1)),
+        terminal::Clear(terminal::ClearType::CurrentLine),
+        style::Print(status)
+    )?;
+
+    stdout.flush()
+}
+
+/// Runs a program in the terminal, drawing the screen on every frame and
+/// writing the keys pressed in the keyboard register (Ctrl-C to quit).
+/// Terminals that do not report key releases keep a key pressed for a
+/// while after each key press or repetition
+///
+/// # Arguments
+///
+/// * `computer` - The computer with the program loaded
+/// * `options` - The renderer, the speed and the cycle limit to use
+pub fn run_tui(computer: &mut Computer, options: &TuiOptions) -> std::io::Result<StopReason> {
+    terminal::enable_raw_mode()?;
+
+    let enhanced = terminal::supports_keyboard_enhancement().unwrap_or(false);
+    let _guard = TerminalGuard { enhanced };
+    let mut stdout = std::io::stdout();
+
+    execute!(
+        stdout,
+        terminal::EnterAlternateScreen,
+        terminal::Clear(terminal::ClearType::All),
+        cursor::Hide
+    )?;
+
+    if enhanced {
+        execute!(
+            stdout,
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+        )?;
+    }
+
+    let engine = Engine::new(computer);
+    let frame = Duration::from_millis(1000 / FPS);
+    let cycles_per_frame = (options.speed / FPS).max(1);
+
+    let mut pressed: Option<(i16, Instant)> = None;
+    let mut previous_screen: Vec<i16> = Vec::new();
+    let mut executed: u64 = 0;
+
+    let reason = loop {
+        let start = Instant::now();
+        let mut redraw = false;
+
+        while event::poll(Duration::ZERO)? {
+            match event::read()? {
+                Event::Key(key)
+                    if key.code == KeyCode::Char('c')
+                        && key.modifiers.contains(KeyModifiers::CONTROL) =>
+                {
+                    return Ok(StopReason::Interrupted);
+                }
+                Event::Key(key) => match (key.kind, hack_key(key.code)) {
+                    (KeyEventKind::Release, Some(code)) => {
+                        if pressed.is_some_and(|(pressed, _)| pressed == code) {
+                            pressed = None;
+                            computer.set_key(0);
+                        }
+                    }
+                    (_, Some(code)) => {
+                        pressed = Some((code, Instant::now()));
+                        computer.set_key(code);
+                    }
+                    (_, None) => {}
+                },
+                Event::Resize(_, _) => {
+                    execute!(stdout, terminal::Clear(terminal::ClearType::All))?;
+                    redraw = true;
+                }
+                _ => {}
+            }
+        }
+
+        // without release events a key is released after a while
+        if !enhanced && pressed.is_some_and(|(_, at)| at.elapsed() > KEY_HOLD) {
+            pressed = None;
+            computer.set_key(0);
+        }
+
+        let budget = match options.max_cycles {
+            Some(max_cycles) => cycles_per_frame.min(max_cycles - executed),
+            None => cycles_per_frame,
+        };
+
+        let cycles = computer.cycles;
+        let reason = engine.run(computer, Some(budget));
+        executed += computer.cycles - cycles;
+
+        if redraw || *computer.screen() != *previous_screen {
+            previous_screen = computer.screen().to_vec();
+
+            let status = format!(
+                "cycles: {}  key: {}  (Ctrl-C to quit)",
+                computer.cycles,
+                pressed.map_or(0, |(code, _)| code)
+            );
+            draw(
+                &render(&previous_screen, options.renderer, options.scale),
+                &status,
+            )?;
+        }
+
+        if reason != StopReason::CycleLimit
+            || options
+                .max_cycles
+                .is_some_and(|max_cycles| executed >= max_cycles)
+        {
+            break reason;
+        }
+
+        if let Some(remaining) = frame.checked_sub(start.elapsed()) {
+            std::thread::sleep(remaining);
+        }
+    };
+
+    // keep the last frame on the terminal until a key is pressed
+    let status = match reason {
+        StopReason::Halted => format!("halted at {}, press any key to quit", computer.pc),
+        StopReason::EndOfProgram => {
+            String::from("reached the end of the program, press any key to quit")
+        }
+        StopReason::CycleLimit => {
+            String::from("reached the maximum number of cycles, press any key to quit")
+        }
+        StopReason::Watchpoint => String::from("stopped by a watchpoint, press any key to quit"),
+        StopReason::Interrupted => String::new(),
+    };
+    draw(
+        &render(&computer.screen(), options.renderer, options.scale),
+        &status,
+    )?;
+
+    loop {
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Release {
+                break;
+            }
+        }
+    }
+
+    Ok(reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::SCREEN_SIZE;
+
+    fn screen_with_pixels(pixels: &[(usize, usize)]) -> Vec<i16> {
+        let mut screen = vec![0i16; SCREEN_SIZE];
+
+        for (x, y) in pixels.iter() {
+            screen[y * SCREEN_WIDTH / 16 + x / 16] |= (1u16 << (x % 16)) as i16;
+        }
+
+        screen
+    }
+
+    #[test]
+    fn render_screen_with_braille() {
+        let screen = screen_with_pixels(&[(0, 0), (1, 3), (511, 255)]);
+        let lines = render(&screen, Renderer::Braille, 1);
+
+        assert_eq!(lines.len(), 64);
+        assert_eq!(lines[0].chars().count(), 256);
+        assert_eq!(lines[0].chars().next(), Some('⢁'));
+        assert_eq!(lines[0].chars().nth(1), Some('⠀'));
+        assert_eq!(lines[63].chars().last(), Some('⢀'));
+    }
+
+    #[test]
+    fn render_screen_with_half_blocks() {
+        let screen = screen_with_pixels(&[(0, 0), (1, 1), (2, 0), (2, 1)]);
+        let lines = render(&screen, Renderer::HalfBlocks, 1);
+
+        assert_eq!(lines.len(), 128);
+        assert_eq!(lines[0].chars().count(), 512);
+        assert!(lines[0].starts_with("▀▄█ "));
+    }
+
+    #[test]
+    fn render_scaled_screen() {
+        let screen = screen_with_pixels(&[(3, 3)]);
+        let lines = render(&screen, Renderer::HalfBlocks, 4);
+
+        assert_eq!(lines.len(), 32);
+        assert_eq!(lines[0].chars().count(), 128);
+        assert!(lines[0].starts_with("▀ "));
+    }
+
+    #[test]
+    fn map_terminal_keys() {
+        assert_eq!(hack_key(KeyCode::Left), Some(130));
+        assert_eq!(hack_key(KeyCode::Enter), Some(128));
+        assert_eq!(hack_key(KeyCode::F(12)), Some(152));
+        assert_eq!(hack_key(KeyCode::Char('K')), Some(75));
+        assert_eq!(hack_key(KeyCode::Char(' ')), Some(32));
+        assert_eq!(hack_key(KeyCode::Char('é')), None);
+        assert_eq!(hack_key(KeyCode::Tab), None);
+    }
+}
@@ -0,0 +1,54 @@
+use crate::logs::log_info;
+
+/// Creates a divider line in the console output.
+///
+/// # Arguments
+///
+/// * `char` - The character to use for the divider.
+/// * `count` - The number of times to repeat the character.
+fn make_divider(char: char, count: Option<usize>) {
+    let count = count.unwrap_or(20);
+    log_info(char.to_string().repeat(count).as_str());
+}
+
+/// Prints header information for the application.
+///
+/// # Arguments
+///
+/// * `app_name` - The name of the application.
+/// * `version` - The version of the application.
+/// * `input` - The program to execute.
+/// * `cycles` - The maximum number of cycles to execute.
+pub fn header_info(app_name: &str, version: &str, input: &str, cycles: Option<u64>) {
+    log_info(format!("{} v{}", app_name, version).as_str());
+    log_info(format!("version: {}", version).as_str());
+    make_divider('=', None);
+    log_info(format!("input: {}", input).as_str());
+
+    match cycles {
+        Some(cycles) => log_info(format!("cycles: {}", cycles).as_str()),
+        None => log_info("cycles: until the program halts"),
+    }
+
+    make_divider('=', None);
+}
+
+/// Reads the contents of a file.
+///
+/// # Arguments
+///
+/// * `input` - The input file to read.
+pub fn read_file(input: &str) -> String {
+    log_info(format!("reading file: {}", input).as_str());
+    std::fs::read_to_string(input).expect("failed to read file")
+}
+
+/// Saves content to a file.
+///
+/// # Arguments
+///
+/// * `output` - The output file to write.
+/// * `content` - The content to write.
+pub fn save_file(output: &str, content: &[u8]) {
+    std::fs::write(output, content).expect("failed to write to file");
+}
@@ -0,0 +1,16 @@
+// copies the serial input to the serial output until the end of the input
+(LOOP)
+    @24578
+    D=M
+    @END
+    D;JLT
+    @24577
+    M=D
+    // consume the character
+    @24578
+    M=0
+    @LOOP
+    0;JMP
+(END)
+    @END
+    0;JMP
@@ -0,0 +1,468 @@
+use asm2hack::code::CodeConfiguration;
+use hackcpu::compare::compare;
+use hackcpu::computer::{Computer, StopReason};
+use hackcpu::debugger::{parse_command, Debugger};
+use hackcpu::engine::Engine;
+use hackcpu::gif::GifRecorder;
+use hackcpu::keyboard::KeyboardScript;
+use hackcpu::loader::{load_program, load_program_with_symbols};
+use hackcpu::monitor::{Invariant, Monitor, WatchKind};
+use hackcpu::profiler::Profile;
+use hackcpu::screen;
+use hackcpu::script::ScriptRunner;
+use hackcpu::serial::{SerialConsole, SERIAL_ADDRESS};
+use hackcpu::snapshot::Snapshot;
+use hackcpu::trace::Trace;
+use hackcpu::utils;
+use std::path::Path;
+
+#[test]
+fn run_add_program() {
+    // computes 2 + 3 and stores the result in RAM[0]
+    let input_content = utils::read_file("../Add.hack");
+
+    let mut computer = Computer::new();
+    computer.load_hack(&input_content);
+
+    assert_eq!(computer.program_size, 6);
+    assert_eq!(computer.run(Some(1000)), StopReason::EndOfProgram);
+    assert_eq!(computer.read_memory(0), 5);
+}
+
+#[test]
+fn run_max_program() {
+    // computes max(RAM[0], RAM[1]) and stores it in RAM[2]
+    let input_content = utils::read_file("../Max.hack");
+
+    let mut computer = Computer::new();
+    computer.load_hack(&input_content);
+
+    computer.write_memory(0, 3);
+    computer.write_memory(1, 5);
+
+    assert_eq!(computer.run(Some(1000)), StopReason::Halted);
+    assert_eq!(computer.read_memory(2), 5);
+
+    // second run after a reset, the memory is kept
+    computer.reset();
+    computer.write_memory(0, 23456);
+    computer.write_memory(1, 12345);
+
+    assert_eq!(computer.run(Some(1000)), StopReason::Halted);
+    assert_eq!(computer.read_memory(2), 23456);
+}
+
+#[test]
+fn run_rect_program() {
+    // draws a rectangle of 16 pixels wide and RAM[0] pixels high
+    let input_content = utils::read_file("../Rect.hack");
+
+    let mut computer = Computer::new();
+    computer.load_hack(&input_content);
+    computer.write_memory(0, 4);
+
+    assert_eq!(computer.run(Some(1000)), StopReason::Halted);
+
+    let screen = computer.screen();
+
+    // each row of the screen has 32 words
+    for row in 0..4 {
+        assert_eq!(screen[row * 32], -1);
+    }
+
+    assert_eq!(screen[4 * 32], 0);
+    assert_eq!(screen[1], 0);
+}
+
+#[test]
+fn run_simple_add_script() {
+    // the assembly file was translated by vm2asm
+    let mut runner = ScriptRunner::from_file(Path::new(
+        "../../07/StackArithmetic/SimpleAdd/SimpleAdd.tst",
+    ));
+
+    let result = runner.run().unwrap();
+
+    assert_eq!(result.failure, None);
+    assert_eq!(
+        result.output,
+        "|  RAM[0]  | RAM[256] |\n|     257  |      15  |\n"
+    );
+    assert!(result.compare_file.is_some());
+}
+
+#[test]
+fn run_stack_test_script() {
+    let mut runner = ScriptRunner::from_file(Path::new(
+        "../../07/StackArithmetic/StackTest/StackTest.tst",
+    ));
+
+    let result = runner.run().unwrap();
+
+    // the output list is changed in the middle of the script
+    assert_eq!(result.failure, None);
+    assert_eq!(result.output.lines().count(), 4);
+}
+
+#[test]
+fn run_fill_script_until_the_cycle_cap() {
+    // Fill.asm polls the keyboard forever, the repeat without a count of
+    // Fill.tst stops after the cycles of the cap
+    let mut runner = ScriptRunner::from_file(Path::new("../../04/fill/Fill.tst"));
+    runner.repeat_cycles = 100_000;
+
+    let result = runner.run().unwrap();
+
+    assert_eq!(result.output, "");
+    assert_eq!(runner.computer.cycles, 100_000);
+    assert!(!runner.computer.is_halted());
+}
+
+#[test]
+fn run_script_with_comparison_failure() {
+    let mut runner = ScriptRunner::new(
+        "load ../../07/StackArithmetic/SimpleAdd/SimpleAdd.asm,\ncompare-to ../../07/StackArithmetic/SimpleAdd/SimpleAdd.cmp,\noutput-list RAM[0]%D2.6.2 RAM[256]%D2.6.2;\nset RAM[0] 256,\nrepeat 10 { ticktock; }\noutput;\noutput;",
+        Path::new("."),
+    );
+
+    let result = runner.run().unwrap();
+    let failure = result.failure.unwrap();
+
+    // the program did not finish and the script stops at the first failure
+    assert_eq!(failure.line, 2);
+    assert_eq!(failure.column, Some("RAM[256]".to_string()));
+    assert_eq!(failure.expected, "|     257  |      15  |");
+    assert_eq!(result.output.lines().count(), 2);
+}
+
+#[test]
+fn compare_output_with_compare_file() {
+    let output = utils::read_file("../../07/StackArithmetic/StackTest/StackTest.out");
+    let expected = utils::read_file("../../07/StackArithmetic/StackTest/StackTest.cmp");
+
+    assert_eq!(compare(&output, &expected), None);
+
+    // a different value in the second table is reported with its column
+    let output = output.replace("-91", "-92");
+    let failure = compare(&output, &expected).unwrap();
+
+    assert_eq!(failure.line, 4);
+    assert_eq!(failure.column, Some("RAM[265]".to_string()));
+}
+
+#[test]
+fn render_rect_screen() {
+    // draws a rectangle of 16 pixels wide and 50 pixels high
+    let mut computer = Computer::new();
+    computer.load_rom(
+        &load_program(
+            Path::new("../../06/rect/Rect.asm"),
+            &CodeConfiguration::default(),
+        )
+        .unwrap(),
+    );
+    computer.write_memory(0, 50);
+
+    assert_eq!(computer.run(Some(100000)), StopReason::Halted);
+
+    let golden = std::fs::read("./tests/files/rect.pbm").unwrap();
+    assert_eq!(screen::to_pbm(&computer.screen()), golden);
+}
+
+#[test]
+fn render_pong_screen() {
+    // the ball and the bat are drawn once the game is initialised
+    let mut computer = Computer::new();
+    computer.load_rom(
+        &load_program(
+            Path::new("../../06/pong/Pong.asm"),
+            &CodeConfiguration::default(),
+        )
+        .unwrap(),
+    );
+
+    assert_eq!(computer.run(Some(5_000_000)), StopReason::CycleLimit);
+
+    let golden = std::fs::read("./tests/files/pong.pbm").unwrap();
+    assert_eq!(screen::to_pbm(&computer.screen()), golden);
+}
+
+#[test]
+fn run_fill_with_keyboard_script() {
+    // the screen is black while a key is pressed
+    let mut computer = Computer::new();
+    computer.load_rom(
+        &load_program(
+            Path::new("../../04/fill/Fill.asm"),
+            &CodeConfiguration::default(),
+        )
+        .unwrap(),
+    );
+    computer.keyboard = Some(KeyboardScript::parse(&utils::read_file(
+        "./tests/files/fill.keys",
+    )));
+
+    let mut frames: Vec<Vec<i16>> = Vec::new();
+    computer.run_with_frames(Some(600000), 200000, |computer| {
+        frames.push(computer.screen().to_vec());
+    });
+
+    assert_eq!(frames.len(), 3);
+    assert!(frames[0].iter().all(|word| *word != -1));
+    assert!(frames[1].iter().all(|word| *word == -1));
+    assert!(frames[2].iter().all(|word| *word != -1));
+}
+
+#[test]
+fn render_pong_screen_with_keyboard_script() {
+    // the bat is moved to the left with the arrow key
+    let mut computer = Computer::new();
+    computer.load_rom(
+        &load_program(
+            Path::new("../../06/pong/Pong.asm"),
+            &CodeConfiguration::default(),
+        )
+        .unwrap(),
+    );
+    computer.keyboard = Some(KeyboardScript::parse(&utils::read_file(
+        "./tests/files/pong.keys",
+    )));
+
+    computer.run(Some(8_000_000));
+
+    let golden = std::fs::read("./tests/files/pong-left.pbm").unwrap();
+    assert_eq!(screen::to_pbm(&computer.screen()), golden);
+}
+
+#[test]
+fn debug_max_program_with_labels() {
+    // stops before the result is stored, then changes the greatest number
+    let (instructions, symbols) = load_program_with_symbols(
+        Path::new("../../06/max/Max.asm"),
+        &CodeConfiguration::default(),
+    )
+    .unwrap();
+
+    let mut computer = Computer::new();
+    computer.load_rom(&instructions);
+    computer.write_memory(0, 3);
+    computer.write_memory(1, 5);
+
+    let mut debugger = Debugger::new(computer, symbols);
+
+    let mut run = |line: &str| debugger.execute(&parse_command(line).unwrap());
+
+    assert_eq!(run("break OUTPUT_D"), Ok("breakpoint at 12".to_string()));
+    assert_eq!(
+        run("continue"),
+        Ok("*>    12  @2                  // R2".to_string())
+    );
+    assert_eq!(run("set D 42"), Ok("D = 42".to_string()));
+    assert_eq!(run("continue"), Ok("program halted at 14".to_string()));
+    assert_eq!(run("print R2"), Ok("RAM[2] (R2): 42".to_string()));
+}
+
+#[test]
+fn profile_max_program_per_label() {
+    // the second number is the greatest, so OUTPUT_FIRST is never executed
+    let (instructions, symbols) = load_program_with_symbols(
+        Path::new("../../06/max/Max.asm"),
+        &CodeConfiguration::default(),
+    )
+    .unwrap();
+
+    let mut computer = Computer::new();
+    computer.load_rom(&instructions);
+    computer.write_memory(0, 3);
+    computer.write_memory(1, 5);
+    computer.profile = Some(Profile::new());
+
+    assert_eq!(computer.run(None), StopReason::Halted);
+
+    let profile = computer.profile.as_ref().unwrap();
+    let routines: Vec<(String, u64)> = profile
+        .routines(&symbols)
+        .into_iter()
+        .map(|routine| (routine.name, routine.cycles))
+        .collect();
+
+    assert_eq!(profile.total(), computer.cycles);
+    assert_eq!(
+        routines,
+        vec![("(start)".to_string(), 10), ("OUTPUT_D".to_string(), 2)]
+    );
+}
+
+#[test]
+fn record_add_program_trace() {
+    // the trace keeps the registers before each instruction and the writes
+    let input_content = utils::read_file("../Add.hack");
+
+    let mut computer = Computer::new();
+    computer.load_hack(&input_content);
+    computer.trace = Some(Trace::new());
+
+    assert_eq!(computer.run(Some(1000)), StopReason::EndOfProgram);
+    assert_eq!(
+        computer.trace.as_ref().unwrap().to_text(),
+        "// cycle pc A D writes\n0 0 0 0\n1 1 2 0\n2 2 2 2\n3 3 3 2\n4 4 3 5\n5 5 0 5 RAM[0]=5\n"
+    );
+
+    // stepping back undoes the write to RAM[0]
+    assert!(computer.step_back());
+    assert_eq!(computer.read_memory(0), 0);
+    assert_eq!(computer.pc, 5);
+}
+
+#[test]
+fn run_pong_with_engine_like_the_computer() {
+    // the decoded engine reaches the same state, keys included
+    let program = load_program(
+        Path::new("../../06/pong/Pong.asm"),
+        &CodeConfiguration::default(),
+    )
+    .unwrap();
+    let script = KeyboardScript::parse(&utils::read_file("./tests/files/pong.keys"));
+
+    let mut expected = Computer::new();
+    expected.load_rom(&program);
+    expected.keyboard = Some(script.clone());
+    expected.run(Some(6_000_000));
+
+    let mut computer = Computer::new();
+    computer.load_rom(&program);
+    computer.keyboard = Some(script);
+    Engine::new(&computer).run(&mut computer, Some(6_000_000));
+
+    assert_eq!(computer, expected);
+}
+
+#[test]
+fn check_pong_stack_invariants() {
+    // the stack of the VM stays in its segment and THIS never points to the screen
+    let (instructions, symbols) = load_program_with_symbols(
+        Path::new("../../06/pong/Pong.asm"),
+        &CodeConfiguration::default(),
+    )
+    .unwrap();
+
+    let mut monitor = Monitor::new();
+    for invariant in ["SP in 256..2047", "THIS not in SCREEN..KBD"] {
+        monitor
+            .invariants
+            .push(Invariant::parse(invariant, &symbols).unwrap());
+    }
+
+    let mut computer = Computer::new();
+    computer.load_rom(&instructions);
+    computer.monitor = Some(monitor);
+
+    let engine = Engine::new(&computer);
+    assert_eq!(
+        engine.run(&mut computer, Some(200_000)),
+        StopReason::CycleLimit
+    );
+    assert!(computer.monitor.as_ref().unwrap().events.is_empty());
+
+    let monitor = computer.monitor.as_mut().unwrap();
+    monitor
+        .invariants
+        .push(Invariant::parse("SP in 256..260", &symbols).unwrap());
+
+    assert_eq!(engine.run(&mut computer, None), StopReason::Watchpoint);
+
+    let event = &computer.monitor.as_ref().unwrap().events[0];
+    assert_eq!(event.kind, WatchKind::Violation);
+    assert_eq!(event.write.address, 0);
+    assert!(event.write.value > 260);
+    assert!(event
+        .describe(&symbols)
+        .contains(&format!("at PC {} (line ", event.pc)));
+}
+
+#[test]
+fn resume_pong_from_snapshot() {
+    // a run resumed from a snapshot ends like a single run, keys included
+    let program = load_program(
+        Path::new("../../06/pong/Pong.asm"),
+        &CodeConfiguration::default(),
+    )
+    .unwrap();
+    let script = KeyboardScript::parse(&utils::read_file("./tests/files/pong.keys"));
+
+    let mut expected = Computer::new();
+    expected.load_rom(&program);
+    expected.keyboard = Some(script.clone());
+    expected.run(Some(4_000_000));
+
+    let mut computer = Computer::new();
+    computer.load_rom(&program);
+    computer.keyboard = Some(script.clone());
+    computer.run(Some(2_000_000));
+
+    let text = Snapshot::capture(&computer).to_text();
+
+    let mut computer = Computer::new();
+    computer.load_rom(&program);
+    computer.keyboard = Some(script);
+    Snapshot::parse(&text)
+        .unwrap()
+        .restore(&mut computer)
+        .unwrap();
+    computer.run(Some(2_000_000));
+
+    assert_eq!(computer, expected);
+}
+
+#[test]
+fn record_pong_as_gif() {
+    // the paddle moves with the keys, so several different frames are kept
+    let program = load_program(
+        Path::new("../../06/pong/Pong.asm"),
+        &CodeConfiguration::default(),
+    )
+    .unwrap();
+
+    let mut computer = Computer::new();
+    computer.load_rom(&program);
+    computer.keyboard = Some(KeyboardScript::parse(&utils::read_file(
+        "./tests/files/pong.keys",
+    )));
+
+    let mut recorder = GifRecorder::new();
+    computer.run_with_frames(Some(6_000_000), 100_000, |computer| {
+        recorder.capture(computer.cycles, &computer.screen());
+    });
+
+    assert!(recorder.frames.len() > 10);
+    assert_eq!(recorder.frames.last().unwrap().1, *computer.screen());
+
+    let gif = recorder.to_gif(computer.cycles, 3_000_000);
+    assert!(gif.starts_with(b"GIF89a"));
+    assert_eq!(gif.last(), Some(&0x3B));
+}
+
+#[test]
+fn echo_serial_input_to_output() {
+    // the program prints what it reads until the end of the input
+    let program = load_program(
+        Path::new("./tests/files/echo.asm"),
+        &CodeConfiguration::default(),
+    )
+    .unwrap();
+    let input = std::io::Cursor::new(b"hack\n".to_vec());
+
+    let mut computer = Computer::new();
+    computer.load_rom(&program);
+    let console = computer
+        .devices
+        .map(
+            SERIAL_ADDRESS,
+            2,
+            SerialConsole::new(SERIAL_ADDRESS, Box::new(input), false),
+        )
+        .unwrap();
+
+    assert_eq!(computer.run(Some(1000)), StopReason::Halted);
+    assert_eq!(console.borrow().text(), "hack\n");
+}
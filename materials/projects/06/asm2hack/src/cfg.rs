@@ -0,0 +1,511 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use crate::parser::{ParserFields, ParserInstructionType};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum EdgeKind {
+    /// the target of a jump resolved from a constant `@LABEL`
+    Jump,
+    /// the next block in ROM (no jump or a conditional one)
+    FallThrough,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct BasicBlock {
+    pub id: usize,
+    /// ROM address of the first instruction of the block
+    pub start: u16,
+    /// ROM address of the last instruction of the block
+    pub end: u16,
+    /// labels pointing at the start of the block
+    pub labels: Vec<String>,
+    /// instructions of the block as written in the source
+    pub instructions: Vec<String>,
+    /// the block ends with a jump whose target is only known at runtime
+    pub computed_jump: bool,
+    /// the block can be reached from the entry point of the program
+    pub reachable: bool,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ControlFlowGraph {
+    pub blocks: Vec<BasicBlock>,
+    pub edges: Vec<Edge>,
+}
+
+fn is_jump(field: &ParserFields) -> bool {
+    field.instruction_type == ParserInstructionType::CInstruction
+        && matches!(
+            field.instruction_jump.as_deref(),
+            Some("JGT" | "JEQ" | "JGE" | "JLT" | "JNE" | "JLE" | "JMP")
+        )
+}
+
+fn is_unconditional_jump(field: &ParserFields) -> bool {
+    is_jump(field) && field.instruction_jump.as_deref() == Some("JMP")
+}
+
+fn writes_a_register(field: &ParserFields) -> bool {
+    field
+        .instruction_dest
+        .as_ref()
+        .is_some_and(|dest| dest.contains('A'))
+}
+
+fn instruction_text(field: &ParserFields) -> String {
+    match field.instruction_type {
+        ParserInstructionType::AInstruction => match &field.instruction_symbol {
+            Some(symbol) => format!("@{}", symbol),
+            None => format!("@{}", field.instruction_value.unwrap_or(0)),
+        },
+        _ => field.instruction_symbol.clone().unwrap_or_default(),
+    }
+}
+
+/// Escapes a string so it can be used inside double quotes (DOT and JSON)
+fn escape(value: &str) -> String {
+    let mut escaped = String::new();
+
+    for char in value.chars() {
+        match char {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(char),
+        }
+    }
+
+    escaped
+}
+
+/// Looks backwards from a jump for the value loaded into the A-register,
+/// the search stops at any label since the block may be entered from elsewhere
+fn resolve_jump_target(
+    instructions: &[&ParserFields],
+    labels: &HashMap<usize, Vec<String>>,
+    address: usize,
+) -> Option<u16> {
+    let mut current = address;
+
+    while current > 0 {
+        // an entry point between the load and the jump makes A unknown
+        if labels.contains_key(&current) {
+            return None;
+        }
+
+        current -= 1;
+        let field = instructions[current];
+
+        match field.instruction_type {
+            ParserInstructionType::AInstruction => return field.instruction_value,
+            _ if writes_a_register(field) || is_jump(field) => return None,
+            _ => continue,
+        }
+    }
+
+    None
+}
+
+impl ControlFlowGraph {
+    /// Splits the program in basic blocks at labels and jumps and links them
+    ///
+    /// # Arguments
+    ///
+    /// * `fields` - The fields of the program with its symbols already resolved
+    pub fn new(fields: &[ParserFields]) -> Self {
+        let mut instructions: Vec<&ParserFields> = Vec::new();
+        let mut labels: HashMap<usize, Vec<String>> = HashMap::new();
+
+        // 1. collect ROM instructions and the labels pointing at them
+        for field in fields.iter() {
+            match field.instruction_type {
+                ParserInstructionType::LInstruction => {
+                    labels
+                        .entry(instructions.len())
+                        .or_default()
+                        .push(field.instruction_symbol.clone().unwrap_or_default());
+                }
+                ParserInstructionType::AInstruction | ParserInstructionType::CInstruction => {
+                    instructions.push(field);
+                }
+                ParserInstructionType::Comment => {}
+            }
+        }
+
+        if instructions.is_empty() {
+            return Self {
+                blocks: Vec::new(),
+                edges: Vec::new(),
+            };
+        }
+
+        // 2. find the leaders: entry point, labels, jump targets and after jumps
+        let mut targets: HashMap<usize, Option<u16>> = HashMap::new();
+        let mut leaders: BTreeSet<usize> = BTreeSet::new();
+        leaders.insert(0);
+
+        for address in labels.keys() {
+            if *address < instructions.len() {
+                leaders.insert(*address);
+            }
+        }
+
+        for (address, field) in instructions.iter().enumerate() {
+            if !is_jump(field) {
+                continue;
+            }
+
+            let target = resolve_jump_target(&instructions, &labels, address);
+
+            if let Some(target) = target {
+                if (target as usize) < instructions.len() {
+                    leaders.insert(target as usize);
+                }
+            }
+
+            if address + 1 < instructions.len() {
+                leaders.insert(address + 1);
+            }
+
+            targets.insert(address, target);
+        }
+
+        // 3. create the blocks between leaders
+        let starts: Vec<usize> = leaders.into_iter().collect();
+        let mut block_at: HashMap<usize, usize> = HashMap::new();
+        let mut blocks: Vec<BasicBlock> = Vec::new();
+
+        for (id, start) in starts.iter().enumerate() {
+            let end = starts
+                .get(id + 1)
+                .map_or(instructions.len() - 1, |next| next - 1);
+
+            block_at.insert(*start, id);
+            blocks.push(BasicBlock {
+                id,
+                start: *start as u16,
+                end: end as u16,
+                labels: labels.get(start).cloned().unwrap_or_default(),
+                instructions: instructions[*start..=end]
+                    .iter()
+                    .map(|field| instruction_text(field))
+                    .collect(),
+                computed_jump: false,
+                reachable: false,
+            });
+        }
+
+        // 4. link the blocks based on the last instruction of each one
+        let mut edges: Vec<Edge> = Vec::new();
+
+        for block in blocks.iter_mut() {
+            let last = instructions[block.end as usize];
+
+            if is_jump(last) {
+                match targets.get(&(block.end as usize)).cloned().flatten() {
+                    Some(target) => {
+                        if let Some(to) = block_at.get(&(target as usize)) {
+                            edges.push(Edge {
+                                from: block.id,
+                                to: *to,
+                                kind: EdgeKind::Jump,
+                            });
+                        }
+                    }
+                    None => block.computed_jump = true,
+                }
+            }
+
+            if !is_unconditional_jump(last) && block.id + 1 < starts.len() {
+                edges.push(Edge {
+                    from: block.id,
+                    to: block.id + 1,
+                    kind: EdgeKind::FallThrough,
+                });
+            }
+        }
+
+        let mut graph = Self { blocks, edges };
+        graph.mark_reachable(&instructions);
+
+        graph
+    }
+
+    /// Marks the blocks reachable from the entry point, a computed jump may
+    /// land on any block whose label address is loaded somewhere in the code
+    fn mark_reachable(&mut self, instructions: &[&ParserFields]) {
+        let referenced: HashSet<&str> = instructions
+            .iter()
+            .filter(|field| field.instruction_type == ParserInstructionType::AInstruction)
+            .filter_map(|field| field.instruction_symbol.as_deref())
+            .collect();
+
+        let address_taken: Vec<usize> = self
+            .blocks
+            .iter()
+            .filter(|block| {
+                block
+                    .labels
+                    .iter()
+                    .any(|label| referenced.contains(label.as_str()))
+            })
+            .map(|block| block.id)
+            .collect();
+
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        let mut computed_visited = false;
+
+        if !self.blocks.is_empty() {
+            queue.push_back(0);
+        }
+
+        while let Some(id) = queue.pop_front() {
+            if self.blocks[id].reachable {
+                continue;
+            }
+
+            self.blocks[id].reachable = true;
+
+            for edge in self.edges.iter().filter(|edge| edge.from == id) {
+                queue.push_back(edge.to);
+            }
+
+            if self.blocks[id].computed_jump && !computed_visited {
+                computed_visited = true;
+                queue.extend(address_taken.iter());
+            }
+        }
+    }
+
+    /// Returns the blocks that can not be reached from the entry point
+    pub fn unreachable_blocks(&self) -> Vec<&BasicBlock> {
+        self.blocks
+            .iter()
+            .filter(|block| !block.reachable)
+            .collect()
+    }
+
+    /// Exports the graph in Graphviz DOT format
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+
+        dot.push_str("digraph cfg {\n");
+        dot.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+
+        for block in self.blocks.iter() {
+            let mut label = format!("b{} [{}..{}]", block.id, block.start, block.end);
+
+            if !block.labels.is_empty() {
+                label.push_str(format!(" {}", block.labels.join(", ")).as_str());
+            }
+
+            label.push_str("\\l");
+
+            for instruction in block.instructions.iter() {
+                label.push_str(format!("{}\\l", escape(instruction)).as_str());
+            }
+
+            let style = if block.reachable {
+                ""
+            } else {
+                ", style=dashed"
+            };
+
+            dot.push_str(format!("    b{} [label=\"{}\"{}];\n", block.id, label, style).as_str());
+        }
+
+        for edge in self.edges.iter() {
+            let attributes = match edge.kind {
+                EdgeKind::Jump => " [label=\"jump\"]",
+                EdgeKind::FallThrough => "",
+            };
+
+            dot.push_str(format!("    b{} -> b{}{};\n", edge.from, edge.to, attributes).as_str());
+        }
+
+        // computed jumps point to a single unknown destination
+        if self.blocks.iter().any(|block| block.computed_jump) {
+            dot.push_str("    computed [shape=diamond, label=\"computed\"];\n");
+
+            for block in self.blocks.iter().filter(|block| block.computed_jump) {
+                dot.push_str(format!("    b{} -> computed [style=dotted];\n", block.id).as_str());
+            }
+        }
+
+        dot.push_str("}\n");
+
+        dot
+    }
+
+    /// Exports the graph in JSON format
+    pub fn to_json(&self) -> String {
+        let blocks: Vec<String> = self
+            .blocks
+            .iter()
+            .map(|block| {
+                let labels: Vec<String> = block
+                    .labels
+                    .iter()
+                    .map(|label| format!("\"{}\"", escape(label)))
+                    .collect();
+                let instructions: Vec<String> = block
+                    .instructions
+                    .iter()
+                    .map(|instruction| format!("\"{}\"", escape(instruction)))
+                    .collect();
+
+                format!(
+                    "{{\"id\":{},\"start\":{},\"end\":{},\"labels\":[{}],\"instructions\":[{}],\"computed_jump\":{},\"reachable\":{}}}",
+                    block.id,
+                    block.start,
+                    block.end,
+                    labels.join(","),
+                    instructions.join(","),
+                    block.computed_jump,
+                    block.reachable
+                )
+            })
+            .collect();
+
+        let edges: Vec<String> = self
+            .edges
+            .iter()
+            .map(|edge| {
+                let kind = match edge.kind {
+                    EdgeKind::Jump => "jump",
+                    EdgeKind::FallThrough => "fallthrough",
+                };
+
+                format!(
+                    "{{\"from\":{},\"to\":{},\"kind\":\"{}\"}}",
+                    edge.from, edge.to, kind
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"blocks\":[{}],\"edges\":[{}]}}\n",
+            blocks.join(","),
+            edges.join(",")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::symbols::{resolve_symbols, SymbolTable};
+
+    fn build(input: &str) -> ControlFlowGraph {
+        let mut parser = Parser::new(input, false);
+        parser.parse();
+
+        let mut fields = parser.get_fields().clone();
+        resolve_symbols(&mut fields, &mut SymbolTable::new()).unwrap();
+
+        ControlFlowGraph::new(&fields)
+    }
+
+    #[test]
+    fn build_graph_without_jumps() {
+        let graph = build("@2\nD=A\n@3\nD=D+A");
+
+        assert_eq!(graph.blocks.len(), 1);
+        assert_eq!(graph.blocks[0].start, 0);
+        assert_eq!(graph.blocks[0].end, 3);
+        assert_eq!(graph.edges.len(), 0);
+        assert!(graph.blocks[0].reachable);
+    }
+
+    #[test]
+    fn build_graph_with_loop() {
+        let graph = build("@10\nD=A\n(LOOP)\nD=D-1\n@LOOP\nD;JGT\n(END)\n@END\n0;JMP");
+
+        assert_eq!(graph.blocks.len(), 3);
+        assert_eq!(graph.blocks[1].labels, vec!["LOOP".to_string()]);
+        assert_eq!(graph.blocks[1].start, 2);
+        assert_eq!(graph.blocks[1].end, 4);
+        assert_eq!(graph.blocks[2].labels, vec!["END".to_string()]);
+
+        assert_eq!(
+            graph.edges,
+            vec![
+                Edge {
+                    from: 0,
+                    to: 1,
+                    kind: EdgeKind::FallThrough
+                },
+                Edge {
+                    from: 1,
+                    to: 1,
+                    kind: EdgeKind::Jump
+                },
+                Edge {
+                    from: 1,
+                    to: 2,
+                    kind: EdgeKind::FallThrough
+                },
+                Edge {
+                    from: 2,
+                    to: 2,
+                    kind: EdgeKind::Jump
+                },
+            ]
+        );
+        assert!(graph.unreachable_blocks().is_empty());
+    }
+
+    #[test]
+    fn build_graph_with_unreachable_block() {
+        let graph = build("@END\n0;JMP\n@1\nD=A\n(END)\n@END\n0;JMP");
+
+        assert_eq!(graph.blocks.len(), 3);
+
+        let unreachable = graph.unreachable_blocks();
+        assert_eq!(unreachable.len(), 1);
+        assert_eq!(unreachable[0].start, 2);
+    }
+
+    #[test]
+    fn build_graph_with_computed_jump() {
+        // push a return address and jump through R14 like vm2asm does
+        let graph = build(
+            "@RET\nD=A\n@R14\nM=D\n@FUNC\n0;JMP\n(RET)\n@RET\n0;JMP\n(FUNC)\n@R14\nA=M\n0;JMP",
+        );
+
+        assert_eq!(graph.blocks.len(), 3);
+        assert!(graph.blocks[2].computed_jump);
+        assert!(!graph.blocks[0].computed_jump);
+
+        // the return address is reachable through the computed jump
+        assert!(graph.unreachable_blocks().is_empty());
+    }
+
+    #[test]
+    fn export_graph_as_dot() {
+        let graph = build("(LOOP)\n@LOOP\n0;JMP");
+
+        assert_eq!(
+            graph.to_dot(),
+            "digraph cfg {\n    node [shape=box, fontname=\"monospace\"];\n    b0 [label=\"b0 [0..1] LOOP\\l@LOOP\\l0;JMP\\l\"];\n    b0 -> b0 [label=\"jump\"];\n}\n"
+        );
+    }
+
+    #[test]
+    fn export_graph_as_json() {
+        let graph = build("(LOOP)\n@LOOP\n0;JMP");
+
+        assert_eq!(
+            graph.to_json(),
+            "{\"blocks\":[{\"id\":0,\"start\":0,\"end\":1,\"labels\":[\"LOOP\"],\"instructions\":[\"@LOOP\",\"0;JMP\"],\"computed_jump\":false,\"reachable\":true}],\"edges\":[{\"from\":0,\"to\":0,\"kind\":\"jump\"}]}\n"
+        );
+    }
+}
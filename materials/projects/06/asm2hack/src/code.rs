@@ -6,6 +6,12 @@ pub struct BinaryInstruction {
     pub binary: String,
 }
 
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct CodeConfiguration {
+    /// recognise the shift operations of the extended instruction set
+    pub extended: bool,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum BinaryInstructionStrategy {
     Dest,
@@ -65,10 +71,32 @@ fn get_comb_form(comb: &str) -> String {
         "M-D" => String::from("1000111"),
         "D&M" => String::from("1000000"),
         "D|M" => String::from("1010101"),
+        // commutative forms accepted by the nand2tetris assembler
+        "A+D" => String::from("0000010"),
+        "A&D" => String::from("0000000"),
+        "A|D" => String::from("0010101"),
+        "M+D" => String::from("1000010"),
+        "M&D" => String::from("1000000"),
+        "M|D" => String::from("1010101"),
         _ => String::from("0000000"),
     }
 }
 
+/// Returns the comp bits (a-bit included) of the shift operations
+/// available in the extended instruction set, those are encoded
+/// with the `101` prefix instead of the usual `111`
+fn get_extended_comb_form(comb: &str) -> Option<String> {
+    match comb {
+        "A<<" => Some(String::from("0100000")),
+        "D<<" => Some(String::from("0110000")),
+        "M<<" => Some(String::from("1100000")),
+        "A>>" => Some(String::from("0000000")),
+        "D>>" => Some(String::from("0010000")),
+        "M>>" => Some(String::from("1000000")),
+        _ => None,
+    }
+}
+
 fn get_jump_form(jump: &str) -> String {
     match jump {
         "JGT" => String::from("001"),
@@ -82,6 +110,65 @@ fn get_jump_form(jump: &str) -> String {
     }
 }
 
+/// Mnemonics of the comp field tried by the disassembler, the canonical
+/// forms come before the commutative ones sharing the same encoding
+const COMPS: [&str; 34] = [
+    "0", "1", "-1", "D", "A", "!D", "!A", "-D", "-A", "D+1", "A+1", "D-1", "A-1", "D+A", "D-A",
+    "A-D", "D&A", "D|A", "M", "!M", "-M", "M+1", "M-1", "D+M", "D-M", "M-D", "D&M", "D|M", "A+D",
+    "A&D", "A|D", "M+D", "M&D", "M|D",
+];
+
+const EXTENDED_COMPS: [&str; 6] = ["A<<", "D<<", "M<<", "A>>", "D>>", "M>>"];
+
+const DESTS: [&str; 7] = ["M", "D", "MD", "A", "AM", "AD", "AMD"];
+
+const JUMPS: [&str; 7] = ["JGT", "JEQ", "JGE", "JLT", "JNE", "JLE", "JMP"];
+
+/// Converts a binary instruction back into Hack assembly, using the same
+/// encodings as the assembler (symbols are lost, A-instructions are numeric)
+///
+/// # Arguments
+///
+/// * `instruction` - The binary instruction
+///
+/// # Returns
+///
+/// * The assembly instruction, or None if the bits do not encode a valid instruction
+pub fn disassemble(instruction: u16) -> Option<String> {
+    // a-instructions keep the value in the lower 15 bits
+    if instruction & 0x8000 == 0 {
+        return Some(format!("@{}", instruction));
+    }
+
+    let binary = get_binary_form(instruction);
+    let (prefix, comp_bits) = (&binary[0..3], &binary[3..10]);
+    let (dest_bits, jump_bits) = (&binary[10..13], &binary[13..16]);
+
+    let comp = match prefix {
+        "111" => COMPS.iter().find(|comp| get_comb_form(comp) == comp_bits)?,
+        "101" => EXTENDED_COMPS
+            .iter()
+            .find(|comp| get_extended_comb_form(comp).as_deref() == Some(comp_bits))?,
+        _ => return None,
+    };
+
+    let mut assembly = String::new();
+
+    if let Some(dest) = DESTS.iter().find(|dest| get_dest_form(dest) == dest_bits) {
+        assembly.push_str(dest);
+        assembly.push('=');
+    }
+
+    assembly.push_str(comp);
+
+    if let Some(jump) = JUMPS.iter().find(|jump| get_jump_form(jump) == jump_bits) {
+        assembly.push(';');
+        assembly.push_str(jump);
+    }
+
+    Some(assembly)
+}
+
 fn apply_strategy(strategy: BinaryInstructionStrategy, source: &str, destination: &mut String) {
     match strategy {
         BinaryInstructionStrategy::Dest => {
@@ -96,16 +183,22 @@ fn apply_strategy(strategy: BinaryInstructionStrategy, source: &str, destination
     }
 }
 
-/// Process the fields and generate the binary instructions
+/// Process the fields and generate the binary instructions using
+/// a specific configuration (e.g. the extended instruction set)
 ///
 /// # Arguments
 ///
 /// * `fields` - A vector of ParserFields
+/// * `config` - The configuration to use while generating the binary
 ///
 /// # Returns
 ///
-/// * A vector of BinaryInstruction
-pub fn process_fields(fields: &Vec<ParserFields>) -> Vec<BinaryInstruction> {
+/// * A vector of BinaryInstruction, or an error with the line of the
+///   first extended instruction found without the extended set
+pub fn process_fields_with_config(
+    fields: &[ParserFields],
+    config: &CodeConfiguration,
+) -> Result<Vec<BinaryInstruction>, String> {
     let mut binary_instructions: Vec<BinaryInstruction> = Vec::new();
 
     // iterate over each field and generate a binary_instruction
@@ -128,14 +221,44 @@ pub fn process_fields(fields: &Vec<ParserFields>) -> Vec<BinaryInstruction> {
                 // knowing it's a C instruction we need to get the binary form
                 // of each part of it as: dest=comp;jump
 
+                // shift operations are only available on the extended set
+                let extended_comp = field
+                    .instruction_comp
+                    .as_deref()
+                    .and_then(get_extended_comb_form);
+
+                if extended_comp.is_some() && !config.extended {
+                    // rebuild the instruction as written: dest=comp;jump
+                    let mut source = String::new();
+                    if let Some(dest) = &field.instruction_dest {
+                        source.push_str(&format!("{}=", dest));
+                    }
+                    source.push_str(field.instruction_comp.as_deref().unwrap_or_default());
+                    if let Some(jump) = &field.instruction_jump {
+                        source.push_str(&format!(";{}", jump));
+                    }
+
+                    return Err(format!(
+                        "Extended instruction requires the extended instruction set at line {}: {}",
+                        field.line_number, source
+                    ));
+                }
+
                 // all the c-instructions start with a fixed-set of bits
-                let fixed_binary_form = String::from("111");
+                // (extended instructions use 101 instead of 111)
+                let fixed_binary_form = if extended_comp.is_some() {
+                    String::from("101")
+                } else {
+                    String::from("111")
+                };
 
                 // get the final binary form
-                let mut final_binary = String::from(fixed_binary_form);
+                let mut final_binary = fixed_binary_form;
 
                 // check comparison
-                if field.instruction_comp.is_some() {
+                if let Some(extended_value) = extended_comp {
+                    final_binary.push_str(&extended_value);
+                } else if field.instruction_comp.is_some() {
                     // get comp binary form
                     let comp_value = field.instruction_comp.clone().unwrap();
                     apply_strategy(
@@ -193,7 +316,7 @@ pub fn process_fields(fields: &Vec<ParserFields>) -> Vec<BinaryInstruction> {
         binary_instructions.push(binary_instruction);
     }
 
-    binary_instructions
+    Ok(binary_instructions)
 }
 
 /// Converts a list of binary instructions into a byte array
@@ -205,7 +328,7 @@ pub fn process_fields(fields: &Vec<ParserFields>) -> Vec<BinaryInstruction> {
 /// # Returns
 ///
 /// * `Vec<u8>` - A byte array
-pub fn binary_instructions_to_bytes(binary_instructions: &Vec<BinaryInstruction>) -> Vec<u8> {
+pub fn binary_instructions_to_bytes(binary_instructions: &[BinaryInstruction]) -> Vec<u8> {
     // prepare bytes array
     let mut bytes: Vec<u8> = Vec::new();
 
@@ -234,7 +357,8 @@ mod tests {
     fn process_array_of_empty_fields() {
         let fields = vec![];
 
-        let binary_instructions = process_fields(&fields);
+        let binary_instructions =
+            process_fields_with_config(&fields, &CodeConfiguration::default()).unwrap();
 
         assert_eq!(binary_instructions.len(), 0);
     }
@@ -253,7 +377,8 @@ mod tests {
 
         let fields = vec![unique_field];
 
-        let binary_instructions = process_fields(&fields);
+        let binary_instructions =
+            process_fields_with_config(&fields, &CodeConfiguration::default()).unwrap();
 
         assert_eq!(binary_instructions.len(), 1);
         assert_eq!(binary_instructions[0].instruction.line_number, 0);
@@ -285,7 +410,8 @@ mod tests {
 
         let fields = vec![unique_field];
 
-        let binary_instructions = process_fields(&fields);
+        let binary_instructions =
+            process_fields_with_config(&fields, &CodeConfiguration::default()).unwrap();
 
         assert_eq!(binary_instructions.len(), 1);
         assert_eq!(binary_instructions[0].instruction.line_number, 0);
@@ -314,7 +440,8 @@ mod tests {
 
         let fields = vec![unique_field];
 
-        let binary_instructions = process_fields(&fields);
+        let binary_instructions =
+            process_fields_with_config(&fields, &CodeConfiguration::default()).unwrap();
 
         assert_eq!(binary_instructions.len(), 1);
         assert_eq!(binary_instructions[0].instruction.line_number, 0);
@@ -344,7 +471,8 @@ mod tests {
 
         let fields = vec![unique_field];
 
-        let binary_instructions = process_fields(&fields);
+        let binary_instructions =
+            process_fields_with_config(&fields, &CodeConfiguration::default()).unwrap();
 
         assert_eq!(binary_instructions.len(), 1);
         assert_eq!(binary_instructions[0].instruction.line_number, 0);
@@ -374,7 +502,8 @@ mod tests {
 
         let fields = vec![unique_field];
 
-        let binary_instructions = process_fields(&fields);
+        let binary_instructions =
+            process_fields_with_config(&fields, &CodeConfiguration::default()).unwrap();
 
         assert_eq!(binary_instructions.len(), 1);
         assert_eq!(binary_instructions[0].instruction.line_number, 0);
@@ -390,4 +519,144 @@ mod tests {
             String::from("1111000010001001")
         );
     }
+
+    #[test]
+    fn process_field_c_instruction_extended_shift() {
+        let unique_field: ParserFields = ParserFields {
+            line_number: 0,
+            instruction_type: ParserInstructionType::CInstruction,
+            instruction_symbol: None,
+            instruction_value: None,
+            instruction_dest: Some(String::from("D")),
+            instruction_comp: Some(String::from("D<<")),
+            instruction_jump: None,
+        };
+
+        let fields = vec![unique_field];
+
+        let binary_instructions =
+            process_fields_with_config(&fields, &CodeConfiguration { extended: true }).unwrap();
+
+        assert_eq!(binary_instructions.len(), 1);
+        // extended instructions use 101 as the fixed prefix
+        assert_eq!(
+            binary_instructions[0].binary,
+            String::from("1010110000010000")
+        );
+    }
+
+    #[test]
+    fn process_field_c_instruction_extended_shift_memory() {
+        let unique_field: ParserFields = ParserFields {
+            line_number: 0,
+            instruction_type: ParserInstructionType::CInstruction,
+            instruction_symbol: None,
+            instruction_value: None,
+            instruction_dest: Some(String::from("M")),
+            instruction_comp: Some(String::from("M>>")),
+            instruction_jump: Some(String::from("JGT")),
+        };
+
+        let fields = vec![unique_field];
+
+        let binary_instructions =
+            process_fields_with_config(&fields, &CodeConfiguration { extended: true }).unwrap();
+
+        assert_eq!(binary_instructions.len(), 1);
+        assert_eq!(
+            binary_instructions[0].binary,
+            String::from("1011000000001001")
+        );
+    }
+
+    #[test]
+    fn process_field_c_instruction_extended_keeps_regular_comp() {
+        let unique_field: ParserFields = ParserFields {
+            line_number: 0,
+            instruction_type: ParserInstructionType::CInstruction,
+            instruction_symbol: None,
+            instruction_value: None,
+            instruction_dest: Some(String::from("M")),
+            instruction_comp: Some(String::from("D+M")),
+            instruction_jump: Some(String::from("JGT")),
+        };
+
+        let fields = vec![unique_field];
+
+        let binary_instructions =
+            process_fields_with_config(&fields, &CodeConfiguration { extended: true }).unwrap();
+
+        // regular instructions are not affected by the extended set
+        assert_eq!(
+            binary_instructions[0].binary,
+            String::from("1111000010001001")
+        );
+    }
+
+    #[test]
+    fn process_field_c_instruction_extended_without_config() {
+        let unique_field: ParserFields = ParserFields {
+            line_number: 3,
+            instruction_type: ParserInstructionType::CInstruction,
+            instruction_symbol: None,
+            instruction_value: None,
+            instruction_dest: Some(String::from("A")),
+            instruction_comp: Some(String::from("A<<")),
+            instruction_jump: None,
+        };
+
+        let fields = vec![unique_field];
+
+        let result = process_fields_with_config(&fields, &CodeConfiguration::default());
+
+        assert_eq!(
+            result.unwrap_err(),
+            "Extended instruction requires the extended instruction set at line 3: A=A<<"
+        );
+    }
+
+    #[test]
+    fn process_field_c_instruction_commutative_comp() {
+        let unique_field: ParserFields = ParserFields {
+            line_number: 0,
+            instruction_type: ParserInstructionType::CInstruction,
+            instruction_symbol: None,
+            instruction_value: None,
+            instruction_dest: Some(String::from("D")),
+            instruction_comp: Some(String::from("A+D")),
+            instruction_jump: None,
+        };
+
+        let fields = vec![unique_field];
+
+        let binary_instructions =
+            process_fields_with_config(&fields, &CodeConfiguration::default()).unwrap();
+
+        // A+D is encoded as D+A
+        assert_eq!(
+            binary_instructions[0].binary,
+            String::from("1110000010010000")
+        );
+    }
+
+    #[test]
+    fn disassemble_instructions() {
+        assert_eq!(disassemble(0b0000000000010000), Some(String::from("@16")));
+        assert_eq!(disassemble(0b1110111111001000), Some(String::from("M=1")));
+        assert_eq!(disassemble(0b1110101010000111), Some(String::from("0;JMP")));
+        assert_eq!(disassemble(0b1110000010010000), Some(String::from("D=D+A")));
+        assert_eq!(
+            disassemble(0b1111000010111101),
+            Some(String::from("AMD=D+M;JNE"))
+        );
+        assert_eq!(disassemble(0b1011100000001000), Some(String::from("M=M<<")));
+        assert_eq!(disassemble(0b1010010000010000), Some(String::from("D=D>>")));
+    }
+
+    #[test]
+    fn disassemble_invalid_instructions() {
+        assert_eq!(disassemble(0b1000000000000000), None);
+        assert_eq!(disassemble(0b1110111110000000), None);
+        assert_eq!(disassemble(0b1011111110000000), None);
+    }
 }
@@ -2,3 +2,6 @@ pub mod parser;
 pub mod logs;
 pub mod utils;
 pub mod code;
+pub mod symbols;
+pub mod cfg;
+pub mod memory_map;
\ No newline at end of file
@@ -1,12 +1,8 @@
+use asm2hack::code::{binary_instructions_to_bytes, process_fields_with_config, CodeConfiguration};
+use asm2hack::logs::{log_command, log_error, log_info, log_success, log_warn};
+use asm2hack::symbols::{self, SymbolTable};
+use asm2hack::{cfg, memory_map, parser, utils};
 use clap::Parser as ClapParser;
-use code::{binary_instructions_to_bytes, process_fields};
-use logs::log_command;
-
-// module definitions
-mod code;
-mod logs;
-mod parser;
-mod utils;
 
 /// interface to assemble Hack assembly language programs into binary code
 /// for execution in the Hack hardware platform
@@ -14,8 +10,8 @@ mod utils;
 #[command(author = "txxnano", version, about)]
 pub struct Args {
     #[arg(short, long, default_value_t = false)]
-    /// use symbolic links instead of absolute reference memory addresses (default: false)
-    symbolic: bool,
+    /// recognise the shift operations of the extended instruction set (default: false)
+    extended: bool,
 
     #[arg(short, long)]
     /// input file to use (.asm)
@@ -24,6 +20,18 @@ pub struct Args {
     /// output file to use (.hack)
     #[arg(short, long, default_value = "default")]
     output: String,
+
+    /// memory map with extra devices to predefine as symbols (TOML or NAME=address)
+    #[arg(short, long)]
+    memory_map: Option<String>,
+
+    /// export the control-flow graph of the program as Graphviz DOT
+    #[arg(long)]
+    cfg_dot: Option<String>,
+
+    /// export the control-flow graph of the program as JSON
+    #[arg(long)]
+    cfg_json: Option<String>,
 }
 
 pub fn main() {
@@ -33,24 +41,93 @@ pub fn main() {
     let app_name = "asm2hack assembler";
     let version = env!("CARGO_PKG_VERSION");
     let input = args.input;
-    let symbolic = args.symbolic;
+    let extended = args.extended;
     let output = args.output;
 
     // print headers of the program
-    utils::header_info(app_name, version, &input, symbolic);
+    utils::header_info(app_name, version, &input, extended);
 
     // read the contents of the input file
     let input_content = utils::read_file(&input);
 
     // create a new parser
-    let mut parser = parser::Parser::new(&input_content, symbolic);
+    let mut parser = parser::Parser::new(&input_content, false);
 
     // run the parser against the content
     parser.parse();
 
+    // resolve labels and variables into addresses
+    let mut fields = parser.get_fields().clone();
+    let mut symbol_table = SymbolTable::new();
+
+    // declare the devices of the memory map before resolving variables
+    if let Some(memory_map) = &args.memory_map {
+        let memory_map = match memory_map::MemoryMap::parse(&utils::read_file(memory_map)) {
+            Ok(memory_map) => memory_map,
+            Err(error) => {
+                log_error(&error);
+                std::process::exit(1);
+            }
+        };
+
+        for device in memory_map.devices.iter() {
+            log_info(
+                format!(
+                    "device {} at [{}..{}]",
+                    device.name, device.start, device.end
+                )
+                .as_str(),
+            );
+        }
+
+        if let Err(error) = memory_map.apply(&mut symbol_table) {
+            log_error(&error);
+            std::process::exit(1);
+        }
+    }
+
+    if let Err(error) = symbols::resolve_symbols(&mut fields, &mut symbol_table) {
+        log_error(&error);
+        std::process::exit(1);
+    }
+
+    // export the control-flow graph if requested
+    if args.cfg_dot.is_some() || args.cfg_json.is_some() {
+        let graph = cfg::ControlFlowGraph::new(&fields);
+
+        for block in graph.unreachable_blocks() {
+            log_warn(
+                format!(
+                    "unreachable block b{} at [{}..{}] {}",
+                    block.id,
+                    block.start,
+                    block.end,
+                    block.labels.join(", ")
+                )
+                .as_str(),
+            );
+        }
+
+        if let Some(cfg_dot) = &args.cfg_dot {
+            utils::save_file(cfg_dot, graph.to_dot().as_bytes());
+            log_success(format!("control-flow graph saved: {}", cfg_dot).as_str());
+        }
+
+        if let Some(cfg_json) = &args.cfg_json {
+            utils::save_file(cfg_json, graph.to_json().as_bytes());
+            log_success(format!("control-flow graph saved: {}", cfg_json).as_str());
+        }
+    }
+
     // get the fields as binary instructions
-    let fields = parser.get_fields();
-    let binary_instructions = process_fields(fields);
+    let binary_instructions =
+        match process_fields_with_config(&fields, &CodeConfiguration { extended }) {
+            Ok(binary_instructions) => binary_instructions,
+            Err(error) => {
+                log_error(&error);
+                std::process::exit(1);
+            }
+        };
 
     // print the binary instructions
     for binary_instruction in binary_instructions.iter() {
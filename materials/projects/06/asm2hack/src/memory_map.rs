@@ -0,0 +1,266 @@
+use crate::symbols::{DeviceRange, SymbolTable, MAX_ADDRESS};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MemoryMap {
+    pub devices: Vec<DeviceRange>,
+}
+
+/// Parses a decimal (24577) or hexadecimal (0x6001) address, quoted
+/// or not, that fits in an A-instruction
+fn parse_address(value: &str) -> Option<u16> {
+    let value = value
+        .trim()
+        .trim_matches('"')
+        .trim_matches('\'')
+        .replace('_', "");
+
+    let address = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => value.parse::<u16>().ok(),
+    };
+
+    address.filter(|address| *address <= MAX_ADDRESS)
+}
+
+/// Removes the comment of a line (TOML uses # but we keep // as in .asm
+/// files), the comment markers inside quoted values are kept
+fn strip_comment(line: &str) -> &str {
+    let mut quote: Option<char> = None;
+
+    for (index, char) in line.char_indices() {
+        match (quote, char) {
+            (Some(open), _) if char == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(char),
+            (None, '#') => return &line[..index],
+            (None, '/') if line[index..].starts_with("//") => return &line[..index],
+            _ => {}
+        }
+    }
+
+    line
+}
+
+impl MemoryMap {
+    /// Parses a memory map definition, each line declares a device either
+    /// as `NAME=address` or with TOML syntax, ranges use an inclusive array.
+    /// With TOML tables only the entries of `[devices]` are read:
+    ///
+    /// ```text
+    /// # extra peripherals
+    /// [devices]
+    /// TIMER = 0x6001
+    /// LEDS = [24578, "0x6005"]
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The content of the memory map file
+    ///
+    /// # Returns
+    ///
+    /// * The memory map, or an error with the line of the first invalid entry
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut devices: Vec<DeviceRange> = Vec::new();
+
+        // entries before any table header are devices too
+        let mut in_devices = true;
+
+        for (index, line) in input.lines().enumerate() {
+            let line_number = index + 1;
+
+            let line = strip_comment(line).trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            // TOML table headers, only [devices] declares devices
+            if line.starts_with('[') && line.ends_with(']') {
+                in_devices = line[1..line.len() - 1].trim() == "devices";
+                continue;
+            }
+
+            if !in_devices {
+                continue;
+            }
+
+            let (name, value) = match line.split_once('=') {
+                Some((name, value)) => (name.trim().trim_matches('"'), value.trim()),
+                None => {
+                    return Err(format!(
+                        "Invalid memory map entry at line {}: {}",
+                        line_number, line
+                    ))
+                }
+            };
+
+            if name.is_empty() || name.chars().next().unwrap().is_numeric() {
+                return Err(format!(
+                    "Invalid memory map symbol at line {}: {}",
+                    line_number, line
+                ));
+            }
+
+            // a single address or an inclusive [start, end] range
+            let range = match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                Some(range) => {
+                    let bounds: Vec<Option<u16>> = range.split(',').map(parse_address).collect();
+
+                    match bounds.as_slice() {
+                        [Some(start), Some(end)] if start <= end => Some((*start, *end)),
+                        _ => None,
+                    }
+                }
+                None => parse_address(value).map(|address| (address, address)),
+            };
+
+            match range {
+                Some((start, end)) => devices.push(DeviceRange {
+                    name: name.to_string(),
+                    start,
+                    end,
+                }),
+                None => {
+                    return Err(format!(
+                        "Invalid memory map address at line {}: {}",
+                        line_number, line
+                    ))
+                }
+            }
+        }
+
+        Ok(Self { devices })
+    }
+
+    /// Declares every device of the memory map in the symbol table
+    pub fn apply(&self, table: &mut SymbolTable) -> Result<(), String> {
+        for device in self.devices.iter() {
+            table.add_device(&device.name, device.start, device.end)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_simple_memory_map() {
+        let memory_map = MemoryMap::parse("TIMER=24577\nSERIAL=24578\n").unwrap();
+
+        assert_eq!(
+            memory_map.devices,
+            vec![
+                DeviceRange {
+                    name: "TIMER".to_string(),
+                    start: 24577,
+                    end: 24577
+                },
+                DeviceRange {
+                    name: "SERIAL".to_string(),
+                    start: 24578,
+                    end: 24578
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_toml_memory_map() {
+        let input =
+            "# extra peripherals\n[devices]\nTIMER = 0x6001 # timer\n\nLEDS = [24578, 0x6005]\n";
+        let memory_map = MemoryMap::parse(input).unwrap();
+
+        assert_eq!(
+            memory_map.devices,
+            vec![
+                DeviceRange {
+                    name: "TIMER".to_string(),
+                    start: 24577,
+                    end: 24577
+                },
+                DeviceRange {
+                    name: "LEDS".to_string(),
+                    start: 24578,
+                    end: 24581
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_memory_map_to_symbol_table() {
+        let memory_map = MemoryMap::parse("TIMER=24577").unwrap();
+        let mut table = SymbolTable::new();
+
+        memory_map.apply(&mut table).unwrap();
+
+        assert_eq!(table.get_address("TIMER"), Some(24577));
+        assert_eq!(table.get_address("KBD"), Some(24576));
+        assert_eq!(table.devices.len(), 1);
+    }
+
+    #[test]
+    fn parse_toml_memory_map_with_quoted_values_and_other_tables() {
+        let input = "[board]\nNAME = \"hack\"\n\n[devices]\nTIMER = \"0x6001\"\nLEDS = [\"24578\", 0x6005]\n\n[screen]\nWIDTH = 512\n";
+        let memory_map = MemoryMap::parse(input).unwrap();
+
+        assert_eq!(
+            memory_map.devices,
+            vec![
+                DeviceRange {
+                    name: "TIMER".to_string(),
+                    start: 24577,
+                    end: 24577
+                },
+                DeviceRange {
+                    name: "LEDS".to_string(),
+                    start: 24578,
+                    end: 24581
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_memory_map_with_comment_markers_in_quotes() {
+        let input =
+            "[board]\nNAME = \"hack #2 // rev b\" # board\n[devices]\nTIMER = \"0x6001\" # timer\n";
+        let memory_map = MemoryMap::parse(input).unwrap();
+
+        assert_eq!(memory_map.devices.len(), 1);
+        assert_eq!(memory_map.devices[0].start, 24577);
+
+        // the quoted value is not cut at the #
+        assert_eq!(
+            MemoryMap::parse("TIMER = \"24577 # timer\""),
+            Err("Invalid memory map address at line 1: TIMER = \"24577 # timer\"".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_memory_map_without_assignment() {
+        assert_eq!(
+            MemoryMap::parse("SERIAL=24578\nTIMER 24577"),
+            Err("Invalid memory map entry at line 2: TIMER 24577".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_memory_map_with_invalid_address() {
+        assert_eq!(
+            MemoryMap::parse("TIMER=70000"),
+            Err("Invalid memory map address at line 1: TIMER=70000".to_string())
+        );
+        assert_eq!(
+            MemoryMap::parse("[devices]\nLEDS = [24581, 24578]"),
+            Err("Invalid memory map address at line 2: LEDS = [24581, 24578]".to_string())
+        );
+    }
+}
@@ -52,6 +52,11 @@ impl Parser {
             line = line.trim();
             let line_number: usize = index + 1;
 
+            // remove trailing comments from instructions (e.g. D=M // comment)
+            if let Some(position) = line.find("//").filter(|position| *position > 0) {
+                line = line[..position].trim_end();
+            }
+
             // revisit the first character of the line
             match line.chars().nth(0) {
                 Some('/') if line.starts_with("//") => {
@@ -80,7 +85,7 @@ impl Parser {
                 }
                 Some('@') => {
                     // extract from line the value or the symbolic reference
-                    let is_value = line.chars().skip(1).next().unwrap().is_numeric();
+                    let is_value = line.chars().nth(1).unwrap().is_numeric();
 
                     if is_value {
                         // check if line is an A instruction
@@ -113,14 +118,22 @@ impl Parser {
                     // split and collect the parts
                     let parts: Vec<&str> = line.split('=').collect();
 
+                    // without destination the instruction needs at least comp;jump
+                    let is_jump_only =
+                        parts.len() == 1 && line.contains(';') && !line.starts_with(';');
+
                     // check if parts is higher than 2 at least
-                    if parts.len() == 1 {
+                    if parts.len() == 1 && !is_jump_only {
                         panic!("Invalid instruction schema: {}.", line)
                     }
 
                     // get dest, comp and jump
-                    let dest = parts.get(0).map(|s| s.to_string());
-                    let (comp, jump) = parts.get(1).map_or((None, None), |s| {
+                    let (dest, computation) = if is_jump_only {
+                        (None, parts.first())
+                    } else {
+                        (parts.first().map(|s| s.to_string()), parts.get(1))
+                    };
+                    let (comp, jump) = computation.map_or((None, None), |s| {
                         // divide second part based on ;
                         let mut split = s.split(';').map(|s| s.to_string());
 
@@ -173,7 +186,7 @@ impl Parser {
             return self._parse_complex();
         }
 
-        return self._parse_simple();
+        self._parse_simple();
     }
 }
 
@@ -186,7 +199,7 @@ mod tests {
         let input = "random string";
         let parser = Parser::new(input, false);
 
-        assert_eq!(parser.is_symbolic, false);
+        assert!(!parser.is_symbolic);
         assert_eq!(parser.input, input);
         assert_eq!(parser.fields.len(), 0);
     }
@@ -196,7 +209,7 @@ mod tests {
         let input = "random string";
         let mut parser = Parser::new(input, true);
 
-        assert_eq!(parser.is_symbolic, true);
+        assert!(parser.is_symbolic);
         assert_eq!(parser.input, input);
         assert_eq!(parser.fields.len(), 0);
 
@@ -209,7 +222,7 @@ mod tests {
         let input = "random string";
         let parser = Parser::new(input, false);
 
-        assert_eq!(parser.is_symbolic, false);
+        assert!(!parser.is_symbolic);
         assert_eq!(parser.input, input);
         assert_eq!(parser.fields.len(), 0);
     }
@@ -220,7 +233,7 @@ mod tests {
         let input = "";
         let mut parser = Parser::new(input, false);
 
-        assert_eq!(parser.is_symbolic, false);
+        assert!(!parser.is_symbolic);
         assert_eq!(parser.input, input);
         assert_eq!(parser.fields.len(), 0);
 
@@ -235,7 +248,7 @@ mod tests {
 
         let mut parser = Parser::new(input_asm, false);
 
-        assert_eq!(parser.is_symbolic, false);
+        assert!(!parser.is_symbolic);
         assert_eq!(parser.input, input_asm);
         assert_eq!(parser.fields.len(), 0);
 
@@ -256,7 +269,7 @@ mod tests {
 
         let mut parser = Parser::new(input_asm, false);
 
-        assert_eq!(parser.is_symbolic, false);
+        assert!(!parser.is_symbolic);
         assert_eq!(parser.input, input_asm);
         assert_eq!(parser.fields.len(), 0);
 
@@ -281,7 +294,7 @@ mod tests {
 
         let mut parser = Parser::new(input_asm, false);
 
-        assert_eq!(parser.is_symbolic, false);
+        assert!(!parser.is_symbolic);
         assert_eq!(parser.input, input_asm);
         assert_eq!(parser.fields.len(), 0);
 
@@ -313,7 +326,7 @@ mod tests {
 
         let mut parser = Parser::new(input_asm, false);
 
-        assert_eq!(parser.is_symbolic, false);
+        assert!(!parser.is_symbolic);
         assert_eq!(parser.input, input_asm);
         assert_eq!(parser.fields.len(), 0);
 
@@ -339,7 +352,7 @@ mod tests {
 
         let mut parser = Parser::new(input_asm, false);
 
-        assert_eq!(parser.is_symbolic, false);
+        assert!(!parser.is_symbolic);
         assert_eq!(parser.input, input_asm);
         assert_eq!(parser.fields.len(), 0);
 
@@ -355,7 +368,7 @@ mod tests {
 
         let mut parser = Parser::new(input_asm, false);
 
-        assert_eq!(parser.is_symbolic, false);
+        assert!(!parser.is_symbolic);
         assert_eq!(parser.input, input_asm);
         assert_eq!(parser.fields.len(), 0);
 
@@ -385,7 +398,7 @@ mod tests {
 
         let mut parser = Parser::new(input_asm, false);
 
-        assert_eq!(parser.is_symbolic, false);
+        assert!(!parser.is_symbolic);
         assert_eq!(parser.input, input_asm);
         assert_eq!(parser.fields.len(), 0);
 
@@ -419,7 +432,7 @@ mod tests {
 
         let mut parser = Parser::new(input_asm, false);
 
-        assert_eq!(parser.is_symbolic, false);
+        assert!(!parser.is_symbolic);
         assert_eq!(parser.input, input_asm);
         assert_eq!(parser.fields.len(), 0);
 
@@ -433,7 +446,7 @@ mod tests {
 
         let mut parser = Parser::new(input_asm, false);
 
-        assert_eq!(parser.is_symbolic, false);
+        assert!(!parser.is_symbolic);
         assert_eq!(parser.input, input_asm);
         assert_eq!(parser.fields.len(), 0);
 
@@ -447,7 +460,7 @@ mod tests {
 
         let mut parser = Parser::new(input_asm, false);
 
-        assert_eq!(parser.is_symbolic, false);
+        assert!(!parser.is_symbolic);
         assert_eq!(parser.input, input_asm);
         assert_eq!(parser.fields.len(), 0);
 
@@ -461,10 +474,72 @@ mod tests {
 
         let mut parser = Parser::new(input_asm, false);
 
-        assert_eq!(parser.is_symbolic, false);
+        assert!(!parser.is_symbolic);
         assert_eq!(parser.input, input_asm);
         assert_eq!(parser.fields.len(), 0);
 
         parser.parse();
     }
+
+    #[test]
+    fn fn_get_comp_jump_instruction() {
+        let input_asm = "0;JMP"; // unconditional jump without destination
+
+        let mut parser = Parser::new(input_asm, false);
+
+        parser.parse();
+
+        assert_eq!(parser.get_fields().len(), 1);
+
+        let c_instrument: &ParserFields = &parser.fields[0];
+
+        assert_eq!(
+            c_instrument.instruction_type,
+            ParserInstructionType::CInstruction
+        );
+        assert_eq!(c_instrument.instruction_symbol, Some("0;JMP".to_string()));
+        assert_eq!(c_instrument.instruction_dest, None);
+        assert_eq!(c_instrument.instruction_comp, Some("0".to_string()));
+        assert_eq!(c_instrument.instruction_jump, Some("JMP".to_string()));
+    }
+
+    #[test]
+    fn fn_get_conditional_jump_with_trailing_comment() {
+        let input_asm = "D;JGT // goto OUTPUT_FIRST if D > 0";
+
+        let mut parser = Parser::new(input_asm, false);
+
+        parser.parse();
+
+        assert_eq!(parser.get_fields().len(), 1);
+        assert_eq!(
+            parser.fields[0].instruction_symbol,
+            Some("D;JGT".to_string())
+        );
+        assert_eq!(parser.fields[0].instruction_dest, None);
+        assert_eq!(parser.fields[0].instruction_comp, Some("D".to_string()));
+        assert_eq!(parser.fields[0].instruction_jump, Some("JGT".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid instruction schema: ;JMP.")]
+    fn fn_fail_on_jump_without_comp() {
+        let mut parser = Parser::new(";JMP", false);
+
+        parser.parse();
+    }
+
+    #[test]
+    fn fn_parse_instruction_with_trailing_comment() {
+        let input_asm = "D=M              // D = first number\n@R1 // second";
+
+        let mut parser = Parser::new(input_asm, false);
+
+        parser.parse();
+
+        assert_eq!(parser.get_fields().len(), 2);
+        assert_eq!(parser.fields[0].instruction_symbol, Some("D=M".to_string()));
+        assert_eq!(parser.fields[0].instruction_comp, Some("M".to_string()));
+        assert_eq!(parser.fields[1].instruction_symbol, Some("R1".to_string()));
+    }
 }
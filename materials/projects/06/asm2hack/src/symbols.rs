@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+
+use crate::parser::{ParserFields, ParserInstructionType};
+
+/// first RAM address used to allocate user variables
+pub const VARIABLE_BASE_ADDRESS: u16 = 16;
+
+/// base address of the memory-mapped screen
+pub const SCREEN_ADDRESS: u16 = 16384;
+
+/// address of the memory-mapped keyboard
+pub const KBD_ADDRESS: u16 = 24576;
+
+/// largest address an A-instruction can load
+pub const MAX_ADDRESS: u16 = 32767;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct DeviceRange {
+    pub name: String,
+    /// first RAM address used by the device
+    pub start: u16,
+    /// last RAM address used by the device (inclusive)
+    pub end: u16,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SymbolTable {
+    /// symbol name to address (RAM for variables, ROM for labels)
+    pub symbols: HashMap<String, u16>,
+    /// next RAM address available for a new variable
+    pub next_variable: u16,
+    /// RAM ranges declared by memory-mapped devices
+    pub devices: Vec<DeviceRange>,
+}
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SymbolTable {
+    /// Creates a symbol table with the predefined symbols of the Hack platform
+    pub fn new() -> Self {
+        let mut symbols: HashMap<String, u16> = HashMap::new();
+
+        // virtual registers R0..R15
+        for register in 0..16 {
+            symbols.insert(format!("R{}", register), register);
+        }
+
+        // pointers used by the virtual machine
+        symbols.insert(String::from("SP"), 0);
+        symbols.insert(String::from("LCL"), 1);
+        symbols.insert(String::from("ARG"), 2);
+        symbols.insert(String::from("THIS"), 3);
+        symbols.insert(String::from("THAT"), 4);
+
+        // memory-mapped I/O
+        symbols.insert(String::from("SCREEN"), SCREEN_ADDRESS);
+        symbols.insert(String::from("KBD"), KBD_ADDRESS);
+
+        Self {
+            symbols,
+            next_variable: VARIABLE_BASE_ADDRESS,
+            devices: Vec::new(),
+        }
+    }
+
+    pub fn add_entry(&mut self, symbol: &str, address: u16) {
+        self.symbols.insert(symbol.to_string(), address);
+    }
+
+    pub fn contains(&self, symbol: &str) -> bool {
+        self.symbols.contains_key(symbol)
+    }
+
+    pub fn get_address(&self, symbol: &str) -> Option<u16> {
+        self.symbols.get(symbol).copied()
+    }
+
+    /// Declares a memory-mapped device, its name points to the first address
+    pub fn add_device(&mut self, symbol: &str, start: u16, end: u16) -> Result<(), String> {
+        if self.contains(symbol) {
+            return Err(format!("Device symbol already defined: {}", symbol));
+        }
+
+        if end < start || end > MAX_ADDRESS {
+            return Err(format!(
+                "Invalid device range for {}: [{}..{}]",
+                symbol, start, end
+            ));
+        }
+
+        // the screen and the keyboard are devices of the platform too
+        if start <= KBD_ADDRESS && end >= SCREEN_ADDRESS {
+            return Err(format!(
+                "Device range for {} overlaps the screen or the keyboard: [{}..{}]",
+                symbol, start, end
+            ));
+        }
+
+        if let Some(device) = self
+            .devices
+            .iter()
+            .find(|device| start <= device.end && end >= device.start)
+        {
+            return Err(format!(
+                "Device range for {} overlaps {}: [{}..{}]",
+                symbol, device.name, start, end
+            ));
+        }
+
+        self.add_entry(symbol, start);
+        self.devices.push(DeviceRange {
+            name: symbol.to_string(),
+            start,
+            end,
+        });
+
+        Ok(())
+    }
+
+    /// Allocates a new variable in the next free RAM address, skipping
+    /// the ranges of the declared devices
+    pub fn allocate_variable(&mut self, symbol: &str) -> Result<u16, String> {
+        let mut address = Some(self.next_variable);
+
+        // variables should never overlap with a declared device
+        while let Some(device) = self.devices.iter().find(|device| {
+            address.is_some_and(|address| device.start <= address && address <= device.end)
+        }) {
+            address = device.end.checked_add(1);
+        }
+
+        let address = address
+            .filter(|address| *address <= MAX_ADDRESS)
+            .ok_or(format!("No RAM address left for variable: {}", symbol))?;
+
+        self.add_entry(symbol, address);
+        self.next_variable = address + 1;
+
+        Ok(address)
+    }
+}
+
+/// Registers every label of the program with the ROM address of the
+/// instruction that follows it (labels do not take space in ROM)
+///
+/// # Arguments
+///
+/// * `fields` - The fields produced by the parser
+/// * `table` - The symbol table to fill
+///
+/// # Returns
+///
+/// * An error with the line of the first label already defined
+pub fn resolve_labels(fields: &[ParserFields], table: &mut SymbolTable) -> Result<(), String> {
+    let mut rom_address: u16 = 0;
+
+    for field in fields.iter() {
+        match field.instruction_type {
+            ParserInstructionType::LInstruction => {
+                let label = field.instruction_symbol.as_ref().unwrap();
+
+                if table.contains(label) {
+                    return Err(format!(
+                        "Duplicated label at line {}: {}",
+                        field.line_number, label
+                    ));
+                }
+
+                table.add_entry(label, rom_address);
+            }
+            ParserInstructionType::AInstruction | ParserInstructionType::CInstruction => {
+                rom_address += 1;
+            }
+            ParserInstructionType::Comment => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves the symbolic A-instructions of the program, labels are
+/// resolved first and any other unknown symbol is allocated as a variable
+///
+/// # Arguments
+///
+/// * `fields` - The fields produced by the parser
+/// * `table` - The symbol table to use (predefined symbols included)
+///
+/// # Returns
+///
+/// * An error with the line of the first label already defined, or
+///   with the first variable that does not fit in RAM
+pub fn resolve_symbols(fields: &mut [ParserFields], table: &mut SymbolTable) -> Result<(), String> {
+    resolve_labels(fields, table)?;
+
+    for field in fields.iter_mut() {
+        if field.instruction_type != ParserInstructionType::AInstruction
+            || field.instruction_value.is_some()
+        {
+            continue;
+        }
+
+        let symbol = field.instruction_symbol.clone().unwrap();
+
+        let address = match table.get_address(&symbol) {
+            Some(address) => address,
+            None => table.allocate_variable(&symbol)?,
+        };
+
+        field.instruction_value = Some(address);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn parse(input: &str) -> Vec<ParserFields> {
+        let mut parser = Parser::new(input, false);
+        parser.parse();
+        parser.get_fields().clone()
+    }
+
+    #[test]
+    fn init_symbol_table_with_predefined_symbols() {
+        let table = SymbolTable::new();
+
+        assert_eq!(table.get_address("R0"), Some(0));
+        assert_eq!(table.get_address("R15"), Some(15));
+        assert_eq!(table.get_address("SP"), Some(0));
+        assert_eq!(table.get_address("THAT"), Some(4));
+        assert_eq!(table.get_address("SCREEN"), Some(16384));
+        assert_eq!(table.get_address("KBD"), Some(24576));
+        assert_eq!(table.next_variable, 16);
+    }
+
+    #[test]
+    fn resolve_labels_to_rom_addresses() {
+        let fields = parse("// comment\n(START)\n@1\nD=A\n(LOOP)\n@LOOP\n0;JMP");
+        let mut table = SymbolTable::new();
+
+        resolve_labels(&fields, &mut table).unwrap();
+
+        assert_eq!(table.get_address("START"), Some(0));
+        assert_eq!(table.get_address("LOOP"), Some(2));
+    }
+
+    #[test]
+    fn resolve_symbols_allocates_variables() {
+        let mut fields = parse("@i\nM=1\n@sum\nM=0\n@i\nD=M\n@SCREEN\n(END)\n@END");
+        let mut table = SymbolTable::new();
+
+        resolve_symbols(&mut fields, &mut table).unwrap();
+
+        assert_eq!(fields[0].instruction_value, Some(16));
+        assert_eq!(fields[2].instruction_value, Some(17));
+        assert_eq!(fields[4].instruction_value, Some(16));
+        assert_eq!(fields[6].instruction_value, Some(16384));
+        assert_eq!(fields[8].instruction_value, Some(7));
+
+        // the symbol is kept for later reference
+        assert_eq!(fields[0].instruction_symbol, Some("i".to_string()));
+    }
+
+    #[test]
+    fn resolve_duplicated_labels() {
+        let fields = parse("(LOOP)\n@1\n(LOOP)");
+        let mut table = SymbolTable::new();
+
+        assert_eq!(
+            resolve_labels(&fields, &mut table),
+            Err("Duplicated label at line 3: LOOP".to_string())
+        );
+    }
+
+    #[test]
+    fn resolve_labels_with_predefined_names() {
+        let mut fields = parse("@1\n(SCREEN)\n@SCREEN\n0;JMP");
+        let mut table = SymbolTable::new();
+
+        assert_eq!(
+            resolve_symbols(&mut fields, &mut table),
+            Err("Duplicated label at line 2: SCREEN".to_string())
+        );
+    }
+
+    #[test]
+    fn add_device_as_predefined_symbol() {
+        let mut fields = parse("@TIMER\nD=M\n@i\nM=D");
+        let mut table = SymbolTable::new();
+
+        table.add_device("TIMER", 24577, 24578).unwrap();
+        resolve_symbols(&mut fields, &mut table).unwrap();
+
+        assert_eq!(fields[0].instruction_value, Some(24577));
+        assert_eq!(fields[2].instruction_value, Some(16));
+    }
+
+    #[test]
+    fn allocate_variable_after_device_range() {
+        let mut fields = parse("@i\nM=1\n@j\nM=1\n@k\nM=1");
+        let mut table = SymbolTable::new();
+
+        table.add_device("LEDS", 17, 20).unwrap();
+        resolve_symbols(&mut fields, &mut table).unwrap();
+
+        assert_eq!(fields[0].instruction_value, Some(16));
+        assert_eq!(fields[2].instruction_value, Some(21));
+        assert_eq!(fields[4].instruction_value, Some(22));
+    }
+
+    #[test]
+    fn add_overlapping_devices() {
+        let mut table = SymbolTable::new();
+
+        table.add_device("LEDS", 24578, 24581).unwrap();
+
+        assert_eq!(
+            table.add_device("TIMER", 24580, 24582),
+            Err("Device range for TIMER overlaps LEDS: [24580..24582]".to_string())
+        );
+        assert_eq!(
+            table.add_device("PAD", 24000, 24577),
+            Err(
+                "Device range for PAD overlaps the screen or the keyboard: [24000..24577]"
+                    .to_string()
+            )
+        );
+        assert_eq!(table.devices.len(), 1);
+    }
+
+    #[test]
+    fn allocate_variable_after_device_at_the_end_of_ram() {
+        let mut table = SymbolTable::new();
+
+        table.add_device("TOP", 24577, MAX_ADDRESS).unwrap();
+        table.next_variable = 24577;
+
+        assert_eq!(
+            table.allocate_variable("i"),
+            Err("No RAM address left for variable: i".to_string())
+        );
+    }
+
+    #[test]
+    fn add_device_with_predefined_name() {
+        let mut table = SymbolTable::new();
+
+        assert_eq!(
+            table.add_device("SCREEN", 16384, 24575),
+            Err("Device symbol already defined: SCREEN".to_string())
+        );
+    }
+}
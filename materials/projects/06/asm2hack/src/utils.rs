@@ -20,12 +20,12 @@ fn make_divider(char: char, count: Option<usize>) {
 /// * `app_name` - The name of the application.
 /// * `version` - The version of the application.
 /// * `args` - The command line arguments.
-pub fn header_info(app_name: &str, version: &str, input: &str, symbolic: bool) {
+pub fn header_info(app_name: &str, version: &str, input: &str, extended: bool) {
     log_info(format!("{} v{}", app_name, version).as_str());
     log_info(format!("version: {}", version).as_str());
     make_divider('=', None);
     log_info(format!("input: {}", input).as_str());
-    log_info(format!("symbolic: {}", symbolic).as_str());
+    log_info(format!("extended: {}", extended).as_str());
     make_divider('=', None);
     log_success(format!("output: {}", input).as_str());
 }
@@ -37,8 +37,7 @@ pub fn header_info(app_name: &str, version: &str, input: &str, symbolic: bool) {
 /// * `input` - The input file to read.
 pub fn read_file(input: &str) -> String {
     log_info(format!("reading file: {}", input).as_str());
-    let input_content = std::fs::read_to_string(input).expect("failed to read file");
-    input_content
+    std::fs::read_to_string(input).expect("failed to read file")
 }
 
 /// Saves content to a file.
@@ -47,7 +46,7 @@ pub fn read_file(input: &str) -> String {
 ///
 /// * `input` - The input file to read.
 ///
-pub fn save_file(output: &str, content: &Vec<u8>) {
+pub fn save_file(output: &str, content: &[u8]) {
     let mut file = std::fs::File::create(output).expect("failed to create file");
 
     for byte in content.iter() {
@@ -0,0 +1,7 @@
+// copies the timer into the first led
+@TIMER
+D=M
+@LEDS
+M=D
+@counter
+M=D
@@ -0,0 +1,10 @@
+// Computes R1 = R0 * 4 using the extended shift operations
+
+@0
+D=M
+D=D<<
+D=D<<
+@1
+M=D
+@1
+M=M>>
@@ -11,7 +11,7 @@ fn create_parser_use_fields() {
     let mut parser = asm2hack::parser::Parser::new(&input_content, false);
 
     // assess the parser fields
-    assert_eq!(parser.is_symbolic, false);
+    assert!(!parser.is_symbolic);
     assert_eq!(parser.input, input_content);
 
     // use fields and convert those to binary
@@ -57,18 +57,19 @@ fn create_parser_use_fields() {
     );
 
     // process those fields
-    let mut binary_instructions = asm2hack::code::process_fields(&fields);
+    let mut binary_instructions = asm2hack::code::process_fields_with_config(
+        fields,
+        &asm2hack::code::CodeConfiguration::default(),
+    )
+    .unwrap();
 
     // check total binary instructions
     assert_eq!(binary_instructions.len(), total_lines);
 
     // filter comment type lines
-    binary_instructions = binary_instructions
-        .into_iter()
-        .filter(|line| {
-            line.instruction.instruction_type != asm2hack::parser::ParserInstructionType::Comment
-        })
-        .collect();
+    binary_instructions.retain(|line| {
+        line.instruction.instruction_type != asm2hack::parser::ParserInstructionType::Comment
+    });
 
     // check A-instruction line @2
     assert_eq!(
@@ -106,3 +107,140 @@ fn create_parser_use_fields() {
         String::from("1110001100001000")
     );
 }
+
+#[test]
+fn create_parser_use_extended_fields() {
+    // read from file
+    let input = "./tests/files/extended.asm";
+
+    let input_content = utils::read_file(input);
+
+    // create a new parser based on extended.asm
+    let mut parser = asm2hack::parser::Parser::new(&input_content, false);
+    parser.parse();
+
+    // process those fields with the extended instruction set
+    let binary_instructions = asm2hack::code::process_fields_with_config(
+        parser.get_fields(),
+        &asm2hack::code::CodeConfiguration { extended: true },
+    )
+    .unwrap();
+
+    // keep only the lines that produce a binary
+    let binaries: Vec<String> = binary_instructions
+        .into_iter()
+        .filter(|line| !line.binary.is_empty())
+        .map(|line| line.binary)
+        .collect();
+
+    assert_eq!(
+        binaries,
+        vec![
+            String::from("0000000000000000"), // @0
+            String::from("1111110000010000"), // D=M
+            String::from("1010110000010000"), // D=D<<
+            String::from("1010110000010000"), // D=D<<
+            String::from("0000000000000001"), // @1
+            String::from("1110001100001000"), // M=D
+            String::from("0000000000000001"), // @1
+            String::from("1011000000001000"), // M=M>>
+        ]
+    );
+}
+
+#[test]
+fn create_control_flow_graph_from_file() {
+    // read the max program from the course materials
+    let input_content = utils::read_file("../max/Max.asm");
+
+    let mut parser = asm2hack::parser::Parser::new(&input_content, false);
+    parser.parse();
+
+    // resolve the labels before building the graph
+    let mut fields = parser.get_fields().clone();
+    let mut symbol_table = asm2hack::symbols::SymbolTable::new();
+    asm2hack::symbols::resolve_symbols(&mut fields, &mut symbol_table).unwrap();
+
+    let graph = asm2hack::cfg::ControlFlowGraph::new(&fields);
+
+    // entry, second number, OUTPUT_FIRST, OUTPUT_D and INFINITE_LOOP
+    assert_eq!(graph.blocks.len(), 5);
+    assert_eq!(graph.blocks[2].labels, vec!["OUTPUT_FIRST".to_string()]);
+    assert_eq!(graph.blocks[3].labels, vec!["OUTPUT_D".to_string()]);
+    assert_eq!(graph.blocks[4].labels, vec!["INFINITE_LOOP".to_string()]);
+
+    // every block is reachable and no jump is computed
+    assert!(graph.unreachable_blocks().is_empty());
+    assert!(graph.blocks.iter().all(|block| !block.computed_jump));
+}
+
+#[test]
+fn create_symbol_table_with_memory_map() {
+    let memory_map =
+        asm2hack::memory_map::MemoryMap::parse(&utils::read_file("./tests/files/devices.toml"))
+            .unwrap();
+
+    let input_content = utils::read_file("./tests/files/devices.asm");
+
+    let mut parser = asm2hack::parser::Parser::new(&input_content, false);
+    parser.parse();
+
+    // declare the devices before resolving the symbols
+    let mut fields = parser.get_fields().clone();
+    let mut symbol_table = asm2hack::symbols::SymbolTable::new();
+    memory_map.apply(&mut symbol_table).unwrap();
+    asm2hack::symbols::resolve_symbols(&mut fields, &mut symbol_table).unwrap();
+
+    assert_eq!(fields[1].instruction_value, Some(24577));
+    assert_eq!(fields[3].instruction_value, Some(24578));
+    assert_eq!(fields[5].instruction_value, Some(16));
+}
+
+/// Assembles a file the way the command line does: labels and variables
+/// are always resolved before generating the binary
+fn assemble_file(input: &str) -> Vec<u8> {
+    let input_content = utils::read_file(input);
+
+    let mut parser = asm2hack::parser::Parser::new(&input_content, false);
+    parser.parse();
+
+    let mut fields = parser.get_fields().clone();
+    let mut symbol_table = asm2hack::symbols::SymbolTable::new();
+    asm2hack::symbols::resolve_symbols(&mut fields, &mut symbol_table).unwrap();
+
+    let binary_instructions = asm2hack::code::process_fields_with_config(
+        &fields,
+        &asm2hack::code::CodeConfiguration::default(),
+    )
+    .unwrap();
+
+    asm2hack::code::binary_instructions_to_bytes(&binary_instructions)
+}
+
+#[test]
+fn assemble_course_program_add() {
+    // the course ships the expected binary of Add.asm
+    let expected = utils::read_file("../add/Add.hack");
+
+    assert_eq!(
+        String::from_utf8(assemble_file("../add/Add.asm")).unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn assemble_course_programs_with_symbols() {
+    // the symbolic programs must match their symbol-less (L) versions
+    for (symbolic, plain) in [
+        ("../max/Max.asm", "../max/MaxL.asm"),
+        ("../rect/Rect.asm", "../rect/RectL.asm"),
+        ("../pong/Pong.asm", "../pong/PongL.asm"),
+    ] {
+        assert_eq!(
+            assemble_file(symbolic),
+            assemble_file(plain),
+            "{}",
+            symbolic
+        );
+    }
+}
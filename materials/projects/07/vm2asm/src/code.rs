@@ -1,9 +1,11 @@
-use crate::parser::{Command, CommandType};
+use crate::command::{Command, Op, Segment, SourceCommand};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct AssemblyInstruction {
     pub instruction: String,
-    pub command: Command,
+    /// command translated, None for the code added by the translator
+    /// (bootstrap and final loop)
+    pub command: Option<SourceCommand>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -12,6 +14,8 @@ pub struct AssemblyGenerator {
     pub should_bootstrap: bool,
     pub last_function: String,
     pub last_return: usize,
+    /// number of comparisons (eq, gt, lt) translated in the program
+    pub last_comparison: usize,
 }
 
 pub struct AssemblyConfiguration {
@@ -25,6 +29,7 @@ impl AssemblyGenerator {
             should_bootstrap: config.bootstrap,
             last_function: "default".to_string(),
             last_return: 0,
+            last_comparison: 0,
         }
     }
 
@@ -38,26 +43,24 @@ impl AssemblyGenerator {
         instruction
     }
 
+    /// Returns the address of a register free for the translator, only
+    /// R13, R14 and R15 are
     fn pick_cache(num: u8) -> String {
         let mut instruction = String::new();
 
-        if num > 15 || num < 13 {
-            panic!("cache not available: you can only access R13, R14, R15");
-        }
+        debug_assert!((13..=15).contains(&num), "cache not available: R{}", num);
 
         instruction.push_str(format!("@R{}\n", num).as_str());
 
         instruction
     }
 
-    fn return_segment(segment: &str) -> &str {
-        match segment {
-            "local" => "LCL",
-            "argument" => "ARG",
-            "this" => "THIS",
-            "that" => "THAT",
-            _ => panic!("segment not found"),
-        }
+    /// Returns the register with the base address of a segment, only
+    /// called for local, argument, this and that
+    fn return_segment(segment: Segment) -> &'static str {
+        segment
+            .base_pointer()
+            .expect("segment without base pointer")
     }
 
     fn increase_stack() -> String {
@@ -118,6 +121,67 @@ impl AssemblyGenerator {
         instruction
     }
 
+    /// Calls a function: pushes the return address and the segments of
+    /// the caller, repositions ARG and LCL and jumps to the function
+    ///
+    /// # Arguments
+    ///
+    /// * `function_name` - The VM name of the function (e.g. Sys.init)
+    /// * `num_args` - The number of arguments already pushed to the stack
+    /// * `return_address` - The label placed after the call
+    fn call_function(function_name: &str, num_args: u16, return_address: &str) -> String {
+        let mut instruction: String = String::new();
+
+        // push returnAddress
+        instruction.push_str(format!("@{}\n", return_address).as_str());
+        instruction.push_str("D=A\n");
+
+        // push address and increase stack
+        instruction.push_str(Self::push_latest_to_stack().as_str());
+        instruction.push_str(Self::increase_stack().as_str());
+
+        // save segments in memory
+        for segment in ["LCL", "ARG", "THIS", "THAT"] {
+            // push LCL
+            instruction.push_str(format!("@{}\n", segment).as_str());
+            instruction.push_str("D=M\n");
+
+            // push LCL to stack
+            instruction.push_str(Self::push_latest_to_stack().as_str());
+            instruction.push_str(Self::increase_stack().as_str());
+        }
+
+        // reposition ARG
+        instruction.push_str("@SP\n");
+        instruction.push_str("D=M\n");
+
+        // get number of arguments + 5
+        instruction.push_str(format!("@{}\n", num_args + 5).as_str());
+
+        // ARG(address) = SP - (num_args + 5)
+        instruction.push_str("D=D-A\n");
+
+        // get the address of ARG
+        instruction.push_str("@ARG\n");
+        instruction.push_str("M=D\n");
+
+        // reposition LCL
+        instruction.push_str("@SP\n");
+        instruction.push_str("D=M\n");
+
+        // get the address of LCL
+        instruction.push_str("@LCL\n");
+        instruction.push_str("M=D\n");
+
+        // add call to function
+        instruction.push_str(Self::call(function_name).as_str());
+
+        // add return label
+        instruction.push_str(format!("({})\n", return_address).as_str());
+
+        instruction
+    }
+
     fn set_stack_pointer_to(value: u16) -> String {
         let mut instruction = String::new();
 
@@ -141,647 +205,463 @@ impl AssemblyGenerator {
         bytes
     }
 
-    fn generate_label(&mut self, command: &Command) -> String {
-        let label = command.arg_1.as_ref().unwrap();
-        let file_name = command.classname.as_ref().unwrap();
-
-        format!("{}.{}${}", file_name, self.last_function, label)
+    fn generate_label(&mut self, classname: &str, label: &str) -> String {
+        format!("{}.{}${}", classname, self.last_function, label)
     }
 
-    fn generate_return(&mut self, command: &Command) -> String {
-        let file_name = command.classname.as_ref().unwrap();
+    fn generate_return(&mut self, classname: &str) -> String {
         format!(
             "{}.{}$ret.{}",
-            file_name, self.last_function, self.last_return
+            classname, self.last_function, self.last_return
         )
     }
 
-    pub fn process_commands(&mut self, commands: &Vec<Command>) {
-        let mut instructions: Vec<AssemblyInstruction> = Vec::new();
-        let mut index_label: u8 = 0;
+    /// Returns the start and end labels of a comparison, unique in the
+    /// program and named after the file and the function like the other
+    /// labels (e.g. `Main.default$EQ_START_3` and `Main.default$EQ_END_3`)
+    fn generate_comparison(&mut self, classname: &str, kind: &str) -> (String, String) {
+        self.last_comparison += 1;
+
+        (
+            format!(
+                "{}.{}${}_START_{}",
+                classname, self.last_function, kind, self.last_comparison
+            ),
+            format!(
+                "{}.{}${}_END_{}",
+                classname, self.last_function, kind, self.last_comparison
+            ),
+        )
+    }
 
-        // if bootstrap, should add the code of:
-        // 1. SP=256
-        // 2. call Sys.init
-        if self.should_bootstrap {
-            instructions.push(AssemblyInstruction {
-                instruction: Self::set_stack_pointer_to(256),
-                command: Command {
-                    command_type: CommandType::CPush,
-                    arg_1: Some("bootstrap".to_string()),
-                    arg_2: None,
-                    classname: None,
-                },
-            });
+    /// Translates the files of a program as a single unit: the bootstrap
+    /// once, the commands of every file in the given order and a single
+    /// infinite loop at the end
+    ///
+    /// # Arguments
+    ///
+    /// * `files` - The commands of each .vm file of the program
+    pub fn process_program(&mut self, files: &[Vec<SourceCommand>]) {
+        self.process_commands(&files.concat())
+    }
 
-            instructions.push(AssemblyInstruction {
-                instruction: Self::call("Sys.init"),
-                command: Command {
-                    command_type: CommandType::CCall,
-                    arg_1: Some("bootstrap".to_string()),
-                    arg_2: None,
-                    classname: None,
-                },
-            });
-        }
+    /// Translates a valid command to assembly (see `Command::validate`)
+    fn translate_command(&mut self, reference_command: &SourceCommand) -> String {
+        let classname = reference_command.classname.as_str();
+        let mut instruction = String::new();
 
-        for command in commands.iter() {
-            let reference_command = command.clone();
-
-            // check the command type
-            match reference_command.command_type {
-                CommandType::CPush => {
-                    let segment = reference_command.arg_1.as_ref().unwrap();
-                    let index = reference_command.arg_2.as_ref().unwrap();
-
-                    assert!(
-                        index.parse::<u32>().is_ok(),
-                        "index must be a number u32 [unsigned integer]"
-                    );
-
-                    let mut instruction = String::new();
-
-                    match segment.as_str() {
-                        "constant" => {
-                            // push the constant to the stack
-                            instruction.push_str(format!("@{}\n", index).as_str());
-                            instruction.push_str("D=A\n");
-                        }
-                        "local" | "argument" | "this" | "that" => {
-                            // 1. get index value
-                            instruction.push_str(format!("@{}\n", index).as_str());
-                            instruction.push_str("D=A\n");
-                            // 2. get segment at index (segment[base+index])
-                            instruction
-                                .push_str(format!("@{}\n", Self::return_segment(segment)).as_str());
-                            instruction.push_str("A=D+A\n");
-                            instruction.push_str("D=M\n"); // RAM[base+segment]
-                        }
-                        "pointer" => {
-                            // 1. check if index 0 or 1
-                            match index.as_str() {
-                                "0" => {
-                                    // should push value of THIS to stack
-                                    instruction.push_str(
-                                        format!("@{}\n", Self::return_segment("this")).as_str(),
-                                    );
-                                    instruction.push_str("D=M\n"); // RAM[this]
-                                }
-                                "1" => {
-                                    // should push value of THIS to stack
-                                    instruction.push_str(
-                                        format!("@{}\n", Self::return_segment("that")).as_str(),
-                                    );
-                                    instruction.push_str("D=M\n"); // RAM[that]
-                                }
-                                _ => panic!("invalid pointer instruction"),
-                            }
-                        }
-                        "temp" => {
-                            // should access i[0-7] RAM location
-                            let temp_base = 5;
-                            let temp_index = index.parse::<u8>().unwrap();
-
-                            assert!(temp_index < 8, "temp index must be between 0 and 7");
-
-                            instruction.push_str(format!("@{}\n", temp_base + temp_index).as_str());
-                            instruction.push_str("D=M\n");
-                        }
-                        "static" => {
-                            // should access static i[16-255] RAM location
-                            let static_base = 16;
-                            let static_index = index.parse::<u8>().unwrap();
-
-                            assert!(static_index < 16, "static index must be between 16 and 255");
-
-                            // this will pick the underlying file name
-                            let classname = reference_command.classname.as_ref().unwrap();
-
-                            instruction.push_str(
-                                format!("@{}.{}\n", classname, static_base + static_index).as_str(),
-                            );
-                            instruction.push_str("D=M\n");
-                        }
-                        _ => {
-                            panic!("segment not valid");
-                        }
+        // check the command type
+        match &reference_command.command {
+            Command::Push { segment, index } => {
+                match segment {
+                    Segment::Constant => {
+                        // push the constant to the stack
+                        instruction.push_str(format!("@{}\n", index).as_str());
+                        instruction.push_str("D=A\n");
                     }
-
-                    // push latest value available at RAM[A] to stack
-                    instruction.push_str(Self::push_latest_to_stack().as_str());
-
-                    // increase the stack
-                    instruction.push_str(Self::increase_stack().as_str());
-
-                    // add the instruction to the list
-                    instructions.push(AssemblyInstruction {
-                        instruction,
-                        command: reference_command,
-                    });
-                }
-                CommandType::CPop => {
-                    let segment = reference_command.arg_1.as_ref().unwrap();
-                    let index = reference_command.arg_2.as_ref().unwrap();
-                    let default_cache: u8 = 13;
-
-                    assert!(index.parse::<u8>().is_ok(), "index must be a number u8");
-
-                    let mut instruction = String::new();
-
-                    match segment.as_str() {
-                        "local" | "argument" | "this" | "that" => {
-                            // get the segment
-                            instruction
-                                .push_str(format!("@{}\n", Self::return_segment(segment)).as_str());
-                            instruction.push_str("D=M\n");
-
-                            // get the index value and add it to the segment
-                            instruction.push_str(format!("@{}\n", index).as_str());
-                            instruction.push_str("D=D+A\n");
-
-                            // store the value in R13
-                            instruction.push_str(Self::pick_cache(default_cache).as_str());
-                            instruction.push_str("M=D\n");
-
-                            // pop value from stack
-                            instruction.push_str(Self::pop_from_stack().as_str());
-
-                            // get the latest value from the stack
-                            instruction.push_str("D=M\n");
-
-                            // push latest to R13
-                            instruction.push_str(
-                                Self::push_latest_to(format!("R{}", default_cache).as_str())
-                                    .as_str(),
-                            );
-                        }
-                        "pointer" => {
-                            // pop value from stack
-                            instruction.push_str(Self::pop_from_stack().as_str());
-
-                            // get the latest value from the stack
-                            instruction.push_str("D=M\n");
-
-                            // 1. check if index 0 or 1
-                            match index.as_str() {
-                                "0" => {
-                                    instruction.push_str(
-                                        format!("@{}\n", Self::return_segment("this")).as_str(),
-                                    );
-                                    instruction.push_str("M=D\n");
-                                }
-                                "1" => {
-                                    instruction.push_str(
-                                        format!("@{}\n", Self::return_segment("that")).as_str(),
-                                    );
-                                    instruction.push_str("M=D\n");
-                                }
-                                _ => panic!("invalid pointer instruction"),
-                            }
-                        }
-                        "temp" => {
-                            // pop value from stack
-                            instruction.push_str(Self::pop_from_stack().as_str());
-
-                            // get the latest value from the stack
-                            instruction.push_str("D=M\n");
-
-                            // should access i[0-7] RAM location
-                            let temp_base = 5;
-                            let temp_index = index.parse::<u8>().unwrap();
-
-                            assert!(temp_index < 8, "temp index must be between 0 and 7");
-
-                            instruction.push_str(format!("@{}\n", temp_base + temp_index).as_str());
-                            instruction.push_str("M=D\n");
-                        }
-                        "static" => {
-                            // pop value from stack
-                            instruction.push_str(Self::pop_from_stack().as_str());
-
-                            // get the latest value from the stack
-                            instruction.push_str("D=M\n");
-
-                            // should access static i[16-255] RAM location
-                            let static_base = 16;
-                            let static_index = index.parse::<u8>().unwrap();
-
-                            assert!(static_index < 16, "static index must be between 16 and 255");
-
-                            // this will pick the underlying file name
-                            let classname = reference_command.classname.as_ref().unwrap();
-
-                            instruction.push_str(
-                                format!("@{}.{}\n", classname, static_base + static_index).as_str(),
-                            );
-                            instruction.push_str("M=D\n");
-                        }
-                        _ => {
-                            panic!("segment not valid");
-                        }
+                    Segment::Local | Segment::Argument | Segment::This | Segment::That => {
+                        // 1. get index value
+                        instruction.push_str(format!("@{}\n", index).as_str());
+                        instruction.push_str("D=A\n");
+                        // 2. get segment at index (segment[base+index])
+                        instruction
+                            .push_str(format!("@{}\n", Self::return_segment(*segment)).as_str());
+                        instruction.push_str("A=D+M\n");
+                        instruction.push_str("D=M\n"); // RAM[base+segment]
+                    }
+                    Segment::Pointer => {
+                        // pointer 0 is THIS and pointer 1 is THAT
+                        let pointer = if *index == 0 {
+                            Segment::This
+                        } else {
+                            Segment::That
+                        };
+
+                        instruction
+                            .push_str(format!("@{}\n", Self::return_segment(pointer)).as_str());
+                        instruction.push_str("D=M\n"); // RAM[this] or RAM[that]
                     }
+                    Segment::Temp => {
+                        // should access i[0-7] RAM location
+                        let temp_base = 5;
 
-                    // add the instruction to the list
-                    instructions.push(AssemblyInstruction {
-                        instruction,
-                        command: reference_command,
-                    });
-                }
-                // if it's arithmetic, we only have one argument and we need to
-                // check what's the operation based on that
-                CommandType::CArithmetic => {
-                    let mut instruction = String::new();
-                    let operation = reference_command.arg_1.as_ref().unwrap();
-
-                    match operation.as_str() {
-                        "add" => {
-                            // 1. pop value from stack
-                            // 2. pop value from stack
-                            // 3. add the two values
-                            instruction.push_str(Self::pop_from_stack().as_str());
-                            instruction.push_str("D=M\n");
-                            instruction.push_str(Self::pop_from_stack().as_str());
-                            instruction.push_str("A=M\n");
-                            instruction.push_str("D=A+D\n");
-                            instruction.push_str(Self::push_latest_to_stack().as_str());
-                            instruction.push_str(Self::increase_stack().as_str());
-                        }
-                        "sub" => {
-                            // 1. pop value from stack
-                            // 2. pop value from stack
-                            // 3. subtract the two values
-                            instruction.push_str(Self::pop_from_stack().as_str());
-                            instruction.push_str("D=M\n");
-                            instruction.push_str(Self::pop_from_stack().as_str());
-                            instruction.push_str("A=M\n");
-                            instruction.push_str("D=A-D\n");
-                            instruction.push_str(Self::push_latest_to_stack().as_str());
-                            instruction.push_str(Self::increase_stack().as_str());
-                        }
-                        "neg" => {
-                            // 1. pop value from stack
-                            // 2. negate the value
-                            instruction.push_str(Self::decrease_stack().as_str());
-                            instruction.push_str("M=-M\n");
-                        }
-                        "eq" => {
-                            index_label += 1;
-                            let start_tag = format!("EQ_START_{}", index_label);
-                            index_label += 1;
-                            let end_tag = format!("EQ_END_{}", index_label);
-
-                            // 1. pop value from stack
-                            // 2. pop value from stack
-                            // 3. compare the two values
-                            // 4. if equal, push -1 to stack, else push 0
-                            instruction.push_str(Self::pop_from_stack().as_str());
-                            instruction.push_str("D=M\n");
-                            instruction.push_str(Self::pop_from_stack().as_str());
-                            instruction.push_str("A=M\n");
-                            instruction.push_str("D=A-D\n");
-                            instruction.push_str(format!("@{}\n", start_tag).as_str());
-                            instruction.push_str("D;JEQ\n");
-                            instruction.push_str("D=0\n");
-                            instruction.push_str(Self::push_latest_to_stack().as_str());
-                            instruction.push_str(Self::increase_stack().as_str());
-                            instruction.push_str(format!("@{}\n", end_tag).as_str());
-                            instruction.push_str("0;JMP\n");
-                            instruction.push_str(format!("({})\n", start_tag).as_str());
-                            instruction.push_str("D=-1\n");
-                            instruction.push_str(Self::push_latest_to_stack().as_str());
-                            instruction.push_str(Self::increase_stack().as_str());
-                            instruction.push_str(format!("({})\n", end_tag).as_str());
-                        }
-                        "gt" => {
-                            index_label += 1;
-                            let start_tag = format!("GT_START_{}", index_label);
-                            index_label += 1;
-                            let end_tag = format!("GT_END_{}", index_label);
-
-                            // 1. pop value from stack
-                            // 2. pop value from stack
-                            // 3. compare the two values
-                            // 4. if greater, push -1 to stack, else push 0
-                            instruction.push_str(Self::pop_from_stack().as_str());
-                            instruction.push_str("D=M\n");
-                            instruction.push_str(Self::pop_from_stack().as_str());
-                            instruction.push_str("A=M\n");
-                            instruction.push_str("D=A-D\n");
-
-                            instruction.push_str(format!("@{}\n", start_tag).as_str());
-                            instruction.push_str("D;JGT\n");
-                            instruction.push_str("D=0\n");
-                            instruction.push_str(Self::push_latest_to_stack().as_str());
-                            instruction.push_str(Self::increase_stack().as_str());
-
-                            instruction.push_str(format!("@{}\n", end_tag).as_str());
-                            instruction.push_str("0;JMP\n");
-                            instruction.push_str(format!("({})\n", start_tag).as_str());
-                            instruction.push_str("D=-1\n");
-                            instruction.push_str(Self::push_latest_to_stack().as_str());
-                            instruction.push_str(Self::increase_stack().as_str());
-                            instruction.push_str(format!("({})\n", end_tag).as_str());
-                        }
-                        "lt" => {
-                            index_label += 1;
-                            let start_tag = format!("LT_START_{}", index_label);
-                            index_label += 1;
-                            let end_tag = format!("LT_END_{}", index_label);
-
-                            // 1. pop value from stack
-                            // 3. compare the two values
-                            // 4. if less, push -1 to stack, else push 0
-                            instruction.push_str(Self::pop_from_stack().as_str());
-                            instruction.push_str("D=M\n");
-                            instruction.push_str(Self::pop_from_stack().as_str());
-                            instruction.push_str("A=M\n");
-                            instruction.push_str("D=A-D\n");
-
-                            instruction.push_str(format!("@{}\n", start_tag).as_str());
-                            instruction.push_str("D;JLT\n");
-                            instruction.push_str("D=0\n");
-                            instruction.push_str(Self::push_latest_to_stack().as_str());
-                            instruction.push_str(Self::increase_stack().as_str());
-
-                            instruction.push_str(format!("@{}\n", end_tag).as_str());
-                            instruction.push_str("0;JMP\n");
-                            instruction.push_str(format!("({})\n", start_tag).as_str());
-                            instruction.push_str("D=-1\n");
-                            instruction.push_str(Self::push_latest_to_stack().as_str());
-                            instruction.push_str(Self::increase_stack().as_str());
-                            instruction.push_str(format!("({})\n", end_tag).as_str());
-                        }
-                        "and" => {
-                            // 1. pop value from stack
-                            // 2. pop value from stack
-                            // 3. and the two values
-                            instruction.push_str(Self::pop_from_stack().as_str());
-                            instruction.push_str("D=M\n");
-                            instruction.push_str(Self::pop_from_stack().as_str());
-                            instruction.push_str("A=M\n");
-                            instruction.push_str("D=D&A\n");
-                            instruction.push_str(Self::push_latest_to_stack().as_str());
-                            instruction.push_str(Self::increase_stack().as_str());
-                        }
-                        "or" => {
-                            // 1. pop value from stack
-                            // 2. pop value from stack
-                            // 3. or the two values
-                            instruction.push_str(Self::pop_from_stack().as_str());
-                            instruction.push_str("D=M\n");
-                            instruction.push_str(Self::pop_from_stack().as_str());
-                            instruction.push_str("A=M\n");
-                            instruction.push_str("D=D|A\n");
-                            instruction.push_str(Self::push_latest_to_stack().as_str());
-                            instruction.push_str(Self::increase_stack().as_str());
-                        }
-                        "not" => {
-                            // 1. pop value from stack
-                            // 2. not the value
-                            instruction.push_str(Self::decrease_stack().as_str());
-                            instruction.push_str("M=!M\n");
-                        }
-                        _ => {
-                            panic!("{} implemented yet", operation);
-                        }
+                        instruction.push_str(format!("@{}\n", temp_base + index).as_str());
+                        instruction.push_str("D=M\n");
                     }
+                    Segment::Static => {
+                        // should access static i[16-255] RAM location, the
+                        // symbol uses the underlying file name
+                        let static_base = 16;
 
-                    instructions.push(AssemblyInstruction {
-                        instruction,
-                        command: reference_command,
-                    });
+                        instruction
+                            .push_str(format!("@{}.{}\n", classname, static_base + index).as_str());
+                        instruction.push_str("D=M\n");
+                    }
                 }
-                CommandType::CLabel => {
-                    let mut instruction: String = String::new();
 
-                    let label = self.generate_label(&reference_command);
+                // push latest value available at RAM[A] to stack
+                instruction.push_str(Self::push_latest_to_stack().as_str());
 
-                    instruction.push_str(format!("({})\n", label).as_str());
+                // increase the stack
+                instruction.push_str(Self::increase_stack().as_str());
+            }
+            Command::Pop { segment, index } => {
+                let default_cache: u8 = 13;
+
+                match segment {
+                    Segment::Local | Segment::Argument | Segment::This | Segment::That => {
+                        // get the segment
+                        instruction
+                            .push_str(format!("@{}\n", Self::return_segment(*segment)).as_str());
+                        instruction.push_str("D=M\n");
 
-                    instructions.push(AssemblyInstruction {
-                        instruction,
-                        command: reference_command,
-                    })
-                }
-                CommandType::CIf => {
-                    let mut instruction: String = String::new();
+                        // get the index value and add it to the segment
+                        instruction.push_str(format!("@{}\n", index).as_str());
+                        instruction.push_str("D=D+A\n");
 
-                    let label = self.generate_label(&reference_command);
+                        // store the value in R13
+                        instruction.push_str(Self::pick_cache(default_cache).as_str());
+                        instruction.push_str("M=D\n");
 
-                    instruction.push_str(Self::pop_from_stack().as_str());
-                    instruction.push_str("D=M\n");
-                    instruction.push_str(format!("@{}\n", label).as_str());
-                    instruction.push_str("D;JNE\n");
+                        // pop value from stack
+                        instruction.push_str(Self::pop_from_stack().as_str());
 
-                    instructions.push(AssemblyInstruction {
-                        instruction,
-                        command: reference_command,
-                    })
-                }
-                CommandType::CGoto => {
-                    let mut instruction: String = String::new();
+                        // get the latest value from the stack
+                        instruction.push_str("D=M\n");
 
-                    let file_name = reference_command.classname.as_ref().unwrap();
-                    let label = reference_command.arg_1.as_ref().unwrap();
-                    let label = format!("{}.{}${}", file_name, self.last_function, label);
+                        // push latest to R13
+                        instruction.push_str(
+                            Self::push_latest_to(format!("R{}", default_cache).as_str()).as_str(),
+                        );
+                    }
+                    Segment::Pointer => {
+                        // pop value from stack
+                        instruction.push_str(Self::pop_from_stack().as_str());
 
-                    instruction.push_str(format!("@{}\n", label).as_str());
-                    instruction.push_str("0;JMP\n");
+                        // get the latest value from the stack
+                        instruction.push_str("D=M\n");
 
-                    instructions.push(AssemblyInstruction {
-                        instruction,
-                        command: reference_command,
-                    })
-                }
-                CommandType::CFunction => {
-                    let function_name = reference_command.arg_1.as_ref().unwrap();
-                    let num_locals = reference_command
-                        .arg_2
-                        .as_ref()
-                        .unwrap()
-                        .parse::<u16>()
-                        .unwrap();
-
-                    let classname = reference_command.classname.as_ref();
-
-                    let label = if let Some(classname) = classname {
-                        // if this is a function from an entry point
-                        format!("{}.{}", classname, function_name)
-                    } else {
-                        function_name.clone()
-                    };
-
-                    let mut instruction: String = String::new();
-
-                    // add label of function
-                    instruction.push_str(format!("({})\n", label).as_str());
-
-                    // iterate over locals
-                    for _ in 0..num_locals {
-                        // push 0 to stack
-                        instruction.push_str("@0\n");
-                        instruction.push_str("D=A\n");
-                        instruction.push_str(Self::push_latest_to_stack().as_str());
+                        // pointer 0 is THIS and pointer 1 is THAT
+                        let pointer = if *index == 0 {
+                            Segment::This
+                        } else {
+                            Segment::That
+                        };
 
-                        // increase the stack
-                        instruction.push_str(Self::increase_stack().as_str());
+                        instruction
+                            .push_str(format!("@{}\n", Self::return_segment(pointer)).as_str());
+                        instruction.push_str("M=D\n");
                     }
+                    Segment::Temp => {
+                        // pop value from stack
+                        instruction.push_str(Self::pop_from_stack().as_str());
 
-                    // copy function name to the last function being called
-                    self.last_function = function_name.clone();
+                        // get the latest value from the stack
+                        instruction.push_str("D=M\n");
 
-                    instructions.push(AssemblyInstruction {
-                        instruction,
-                        command: reference_command,
-                    })
-                }
-                CommandType::CCall => {
-                    let mut instruction: String = String::new();
-                    let return_address = self.generate_return(&reference_command);
+                        // should access i[0-7] RAM location
+                        let temp_base = 5;
 
-                    // push returnAddress
-                    instruction.push_str(format!("@{}\n", return_address).as_str());
-                    instruction.push_str("D=A\n");
-
-                    // push address and increase stack
-                    instruction.push_str(Self::push_latest_to_stack().as_str());
-                    instruction.push_str(Self::increase_stack().as_str());
+                        instruction.push_str(format!("@{}\n", temp_base + index).as_str());
+                        instruction.push_str("M=D\n");
+                    }
+                    Segment::Static => {
+                        // pop value from stack
+                        instruction.push_str(Self::pop_from_stack().as_str());
 
-                    // save segments in memory
-                    for segment in vec!["LCL", "ARG", "THIS", "THAT"] {
-                        // push LCL
-                        instruction.push_str(format!("@{}\n", segment).as_str());
+                        // get the latest value from the stack
                         instruction.push_str("D=M\n");
 
-                        // push LCL to stack
-                        instruction.push_str(Self::push_latest_to_stack().as_str());
-                        instruction.push_str(Self::increase_stack().as_str());
-                    }
+                        // should access static i[16-255] RAM location, the
+                        // symbol uses the underlying file name
+                        let static_base = 16;
 
-                    // reposition ARG
-                    instruction.push_str("@SP\n");
+                        instruction
+                            .push_str(format!("@{}.{}\n", classname, static_base + index).as_str());
+                        instruction.push_str("M=D\n");
+                    }
+                    Segment::Constant => {
+                        unreachable!("pop constant is rejected by Command::validate")
+                    }
+                }
+            }
+            // if it's arithmetic, we only have one argument and we need to
+            // check what's the operation based on that
+            Command::Arithmetic(operation) => match operation {
+                Op::Add => {
+                    // 1. pop value from stack
+                    // 2. pop value from stack
+                    // 3. add the two values
+                    instruction.push_str(Self::pop_from_stack().as_str());
+                    instruction.push_str("D=M\n");
+                    instruction.push_str(Self::pop_from_stack().as_str());
+                    instruction.push_str("A=M\n");
+                    instruction.push_str("D=A+D\n");
+                    instruction.push_str(Self::push_latest_to_stack().as_str());
+                    instruction.push_str(Self::increase_stack().as_str());
+                }
+                Op::Sub => {
+                    // 1. pop value from stack
+                    // 2. pop value from stack
+                    // 3. subtract the two values
+                    instruction.push_str(Self::pop_from_stack().as_str());
                     instruction.push_str("D=M\n");
+                    instruction.push_str(Self::pop_from_stack().as_str());
+                    instruction.push_str("A=M\n");
+                    instruction.push_str("D=A-D\n");
+                    instruction.push_str(Self::push_latest_to_stack().as_str());
+                    instruction.push_str(Self::increase_stack().as_str());
+                }
+                Op::Neg => {
+                    // 1. pop value from stack
+                    // 2. negate the value
+                    instruction.push_str(Self::decrease_stack().as_str());
+                    instruction.push_str("M=-M\n");
+                }
+                Op::Eq => {
+                    let (start_tag, end_tag) = self.generate_comparison(classname, "EQ");
 
-                    // get number of arguments
-                    let num_args = reference_command
-                        .arg_2
-                        .as_ref()
-                        .unwrap()
-                        .parse::<u16>()
-                        .unwrap();
+                    // 1. pop value from stack
+                    // 2. pop value from stack
+                    // 3. compare the two values
+                    // 4. if equal, push -1 to stack, else push 0
+                    instruction.push_str(Self::pop_from_stack().as_str());
+                    instruction.push_str("D=M\n");
+                    instruction.push_str(Self::pop_from_stack().as_str());
+                    instruction.push_str("A=M\n");
+                    instruction.push_str("D=A-D\n");
+                    instruction.push_str(format!("@{}\n", start_tag).as_str());
+                    instruction.push_str("D;JEQ\n");
+                    instruction.push_str("D=0\n");
+                    instruction.push_str(Self::push_latest_to_stack().as_str());
+                    instruction.push_str(Self::increase_stack().as_str());
+                    instruction.push_str(format!("@{}\n", end_tag).as_str());
+                    instruction.push_str("0;JMP\n");
+                    instruction.push_str(format!("({})\n", start_tag).as_str());
+                    instruction.push_str("D=-1\n");
+                    instruction.push_str(Self::push_latest_to_stack().as_str());
+                    instruction.push_str(Self::increase_stack().as_str());
+                    instruction.push_str(format!("({})\n", end_tag).as_str());
+                }
+                Op::Gt => {
+                    let (start_tag, end_tag) = self.generate_comparison(classname, "GT");
 
-                    // get number of arguments + 5
-                    instruction.push_str(format!("@{}\n", num_args + 5).as_str());
+                    // 1. pop value from stack
+                    // 2. pop value from stack
+                    // 3. compare the two values
+                    // 4. if greater, push -1 to stack, else push 0
+                    instruction.push_str(Self::pop_from_stack().as_str());
+                    instruction.push_str("D=M\n");
+                    instruction.push_str(Self::pop_from_stack().as_str());
+                    instruction.push_str("A=M\n");
+                    instruction.push_str("D=A-D\n");
 
-                    // ARG(address) = SP - (num_args + 5)
-                    instruction.push_str("D=D-A\n");
+                    instruction.push_str(format!("@{}\n", start_tag).as_str());
+                    instruction.push_str("D;JGT\n");
+                    instruction.push_str("D=0\n");
+                    instruction.push_str(Self::push_latest_to_stack().as_str());
+                    instruction.push_str(Self::increase_stack().as_str());
 
-                    // get the address of ARG
-                    instruction.push_str("@ARG\n");
-                    instruction.push_str("M=D\n");
+                    instruction.push_str(format!("@{}\n", end_tag).as_str());
+                    instruction.push_str("0;JMP\n");
+                    instruction.push_str(format!("({})\n", start_tag).as_str());
+                    instruction.push_str("D=-1\n");
+                    instruction.push_str(Self::push_latest_to_stack().as_str());
+                    instruction.push_str(Self::increase_stack().as_str());
+                    instruction.push_str(format!("({})\n", end_tag).as_str());
+                }
+                Op::Lt => {
+                    let (start_tag, end_tag) = self.generate_comparison(classname, "LT");
 
-                    // reposition LCL
-                    instruction.push_str("@SP\n");
+                    // 1. pop value from stack
+                    // 3. compare the two values
+                    // 4. if less, push -1 to stack, else push 0
+                    instruction.push_str(Self::pop_from_stack().as_str());
                     instruction.push_str("D=M\n");
+                    instruction.push_str(Self::pop_from_stack().as_str());
+                    instruction.push_str("A=M\n");
+                    instruction.push_str("D=A-D\n");
 
-                    // get the address of LCL
-                    instruction.push_str("@LCL\n");
-                    instruction.push_str("M=D\n");
+                    instruction.push_str(format!("@{}\n", start_tag).as_str());
+                    instruction.push_str("D;JLT\n");
+                    instruction.push_str("D=0\n");
+                    instruction.push_str(Self::push_latest_to_stack().as_str());
+                    instruction.push_str(Self::increase_stack().as_str());
 
-                    // jump to function
-                    let function_name: String = format!(
-                        "{}.{}",
-                        reference_command.classname.as_ref().unwrap(),
-                        reference_command.arg_1.as_ref().unwrap()
-                    );
+                    instruction.push_str(format!("@{}\n", end_tag).as_str());
+                    instruction.push_str("0;JMP\n");
+                    instruction.push_str(format!("({})\n", start_tag).as_str());
+                    instruction.push_str("D=-1\n");
+                    instruction.push_str(Self::push_latest_to_stack().as_str());
+                    instruction.push_str(Self::increase_stack().as_str());
+                    instruction.push_str(format!("({})\n", end_tag).as_str());
+                }
+                Op::And => {
+                    // 1. pop value from stack
+                    // 2. pop value from stack
+                    // 3. and the two values
+                    instruction.push_str(Self::pop_from_stack().as_str());
+                    instruction.push_str("D=M\n");
+                    instruction.push_str(Self::pop_from_stack().as_str());
+                    instruction.push_str("A=M\n");
+                    instruction.push_str("D=D&A\n");
+                    instruction.push_str(Self::push_latest_to_stack().as_str());
+                    instruction.push_str(Self::increase_stack().as_str());
+                }
+                Op::Or => {
+                    // 1. pop value from stack
+                    // 2. pop value from stack
+                    // 3. or the two values
+                    instruction.push_str(Self::pop_from_stack().as_str());
+                    instruction.push_str("D=M\n");
+                    instruction.push_str(Self::pop_from_stack().as_str());
+                    instruction.push_str("A=M\n");
+                    instruction.push_str("D=D|A\n");
+                    instruction.push_str(Self::push_latest_to_stack().as_str());
+                    instruction.push_str(Self::increase_stack().as_str());
+                }
+                Op::Not => {
+                    // 1. pop value from stack
+                    // 2. not the value
+                    instruction.push_str(Self::decrease_stack().as_str());
+                    instruction.push_str("M=!M\n");
+                }
+            },
+            Command::Label(label) => {
+                let label = self.generate_label(classname, label);
 
-                    // add call to function
-                    instruction.push_str(Self::call(function_name.as_str()).as_str());
+                instruction.push_str(format!("({})\n", label).as_str());
+            }
+            Command::IfGoto(label) => {
+                let label = self.generate_label(classname, label);
 
-                    // add return label
-                    instruction.push_str(format!("({})\n", return_address).as_str());
+                instruction.push_str(Self::pop_from_stack().as_str());
+                instruction.push_str("D=M\n");
+                instruction.push_str(format!("@{}\n", label).as_str());
+                instruction.push_str("D;JNE\n");
+            }
+            Command::Goto(label) => {
+                let label = self.generate_label(classname, label);
 
-                    // increase return count
-                    self.last_return += 1;
+                instruction.push_str(format!("@{}\n", label).as_str());
+                instruction.push_str("0;JMP\n");
+            }
+            Command::Function { name, nlocals } => {
+                // add label of function, the VM name (e.g. Class1.set) is
+                // already global so calls from other files can reach it
+                instruction.push_str(format!("({})\n", name).as_str());
+
+                // iterate over locals
+                for _ in 0..*nlocals {
+                    // push 0 to stack
+                    instruction.push_str("@0\n");
+                    instruction.push_str("D=A\n");
+                    instruction.push_str(Self::push_latest_to_stack().as_str());
 
-                    instructions.push(AssemblyInstruction {
-                        instruction,
-                        command: reference_command,
-                    })
+                    // increase the stack
+                    instruction.push_str(Self::increase_stack().as_str());
                 }
-                CommandType::CReturn => {
-                    let mut instruction: String = String::new();
 
-                    // FRAME = LCL
-                    instruction.push_str("@LCL\n");
-                    instruction.push_str("D=M\n");
+                // copy function name to the last function being called
+                self.last_function = name.clone();
+            }
+            Command::Call { name, nargs } => {
+                let return_address = self.generate_return(classname);
 
-                    // store FRAME in R13
-                    instruction.push_str("@R13\n");
-                    instruction.push_str("M=D\n");
+                instruction
+                    .push_str(Self::call_function(name, *nargs, return_address.as_str()).as_str());
 
-                    // RET = *(FRAME-5)
-                    instruction.push_str("@5\n");
+                // increase return count
+                self.last_return += 1;
+            }
+            Command::Return => {
+                // FRAME = LCL
+                instruction.push_str("@LCL\n");
+                instruction.push_str("D=M\n");
+
+                // store FRAME in R13
+                instruction.push_str("@R13\n");
+                instruction.push_str("M=D\n");
+
+                // RET = *(FRAME-5)
+                instruction.push_str("@5\n");
+                instruction.push_str("A=D-A\n");
+                instruction.push_str("D=M\n");
+
+                // store RET in R14 ( we use R14 to store the return address )
+                instruction.push_str("@R14\n");
+                instruction.push_str("M=D\n");
+
+                // *ARG = pop()
+                instruction.push_str(Self::pop_from_stack().as_str());
+                instruction.push_str("D=M\n");
+
+                // modify the pointer of ARG
+                instruction.push_str("@ARG\n");
+                instruction.push_str("A=M\n");
+                instruction.push_str("M=D\n");
+
+                // SP = ARG + 1
+                instruction.push_str("@ARG\n");
+                instruction.push_str("D=M+1\n");
+                instruction.push_str("@SP\n");
+                instruction.push_str("M=D\n");
+
+                for (index, segment) in (1..).zip(["THAT", "THIS", "ARG", "LCL"]) {
+                    // restore THAT, THIS, ARG, LCL
+                    instruction.push_str("@R13\n");
+                    instruction.push_str("D=M\n");
+                    instruction.push_str(format!("@{}\n", index).as_str());
                     instruction.push_str("A=D-A\n");
                     instruction.push_str("D=M\n");
-
-                    // store RET in R14 ( we use R14 to store the return address )
-                    instruction.push_str("@R14\n");
+                    instruction.push_str(format!("@{}\n", segment).as_str());
                     instruction.push_str("M=D\n");
+                }
 
-                    // *ARG = pop()
-                    instruction.push_str(Self::pop_from_stack().as_str());
-                    instruction.push_str("D=M\n");
+                // goto retAddress from FRAME[5]
+                instruction.push_str("@R14\n");
+                instruction.push_str("A=M\n");
+                instruction.push_str("0;JMP\n");
+            }
+        }
 
-                    // modify the pointer of ARG
-                    instruction.push_str("@ARG\n");
-                    instruction.push_str("A=M\n");
-                    instruction.push_str("M=D\n");
+        instruction
+    }
 
-                    // SP = ARG + 1
-                    instruction.push_str("@ARG\n");
-                    instruction.push_str("D=M+1\n");
-                    instruction.push_str("@SP\n");
-                    instruction.push_str("M=D\n");
+    /// Translates the commands of a program, the commands must be valid
+    /// like the ones returned by the parser
+    pub fn process_commands(&mut self, commands: &[SourceCommand]) {
+        let mut instructions: Vec<AssemblyInstruction> = Vec::new();
 
-                    let mut index = 1;
-                    for segment in vec!["THAT", "THIS", "ARG", "LCL"] {
-                        // restore THAT, THIS, ARG, LCL
-                        instruction.push_str("@R13\n");
-                        instruction.push_str("D=M\n");
-                        instruction.push_str(format!("@{}\n", index).as_str());
-                        instruction.push_str("A=D-A\n");
-                        instruction.push_str("D=M\n");
-                        instruction.push_str(format!("@{}\n", segment).as_str());
-                        instruction.push_str("M=D\n");
+        // if bootstrap, should add the code of:
+        // 1. SP=256
+        // 2. call Sys.init 0 (with a full frame, SP is 261 in Sys.init)
+        if self.should_bootstrap {
+            instructions.push(AssemblyInstruction {
+                instruction: Self::set_stack_pointer_to(256),
+                command: None,
+            });
 
-                        // increase index
-                        index += 1;
-                    }
+            instructions.push(AssemblyInstruction {
+                instruction: Self::call_function("Sys.init", 0, "Bootstrap$ret"),
+                command: None,
+            });
+        }
 
-                    // goto retAddress from FRAME[5]
-                    instruction.push_str("@R14\n");
-                    instruction.push_str("A=M\n");
-                    instruction.push_str("0;JMP\n");
+        for reference_command in commands.iter() {
+            let instruction = self.translate_command(reference_command);
 
-                    // add instruction to the list
-                    instructions.push(AssemblyInstruction {
-                        instruction,
-                        command: reference_command,
-                    })
-                }
-            }
+            // add the instruction to the list
+            instructions.push(AssemblyInstruction {
+                instruction,
+                command: Some(reference_command.clone()),
+            });
         }
 
         // add infinite loop to the end of the program
         instructions.push(AssemblyInstruction {
             instruction: Self::add_infinite_loop(),
-            command: Command {
-                command_type: CommandType::CArithmetic,
-                arg_1: Some("end".to_string()),
-                arg_2: None,
-                classname: None,
-            },
+            command: None,
         });
 
         self.instructions = instructions;
@@ -799,24 +679,21 @@ mod tests {
         // push local 8
         // add
         let commands = vec![
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("local".to_string()),
-                arg_2: Some("2".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("local".to_string()),
-                arg_2: Some("8".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CArithmetic,
-                arg_1: Some("add".to_string()),
-                arg_2: None,
-                classname: None,
-            },
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Local,
+                    index: 2,
+                },
+            ),
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Local,
+                    index: 8,
+                },
+            ),
+            SourceCommand::new("Root", Command::Arithmetic(Op::Add)),
         ];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
@@ -830,12 +707,13 @@ mod tests {
     fn process_push_command() {
         // initialize a list of valid commands
         // push local 2
-        let commands = vec![Command {
-            command_type: CommandType::CPush,
-            arg_1: Some("local".to_string()),
-            arg_2: Some("2".to_string()),
-            classname: None,
-        }];
+        let commands = vec![SourceCommand::new(
+            "Root",
+            Command::Push {
+                segment: Segment::Local,
+                index: 2,
+            },
+        )];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
 
@@ -844,37 +722,21 @@ mod tests {
         assert_eq!(generator.instructions.len(), 2);
         assert_eq!(
             generator.instructions[0].instruction,
-            "@2\nD=A\n@LCL\nA=D+A\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1\n"
+            "@2\nD=A\n@LCL\nA=D+M\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1\n"
         );
     }
 
-    #[test]
-    #[should_panic]
-    fn should_fail_on_negative_index() {
-        // initialize a list of valid commands
-        // push local -2
-        let commands = vec![Command {
-            command_type: CommandType::CPush,
-            arg_1: Some("local".to_string()),
-            arg_2: Some("-2".to_string()),
-            classname: None,
-        }];
-
-        let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
-
-        generator.process_commands(&commands);
-    }
-
     #[test]
     fn process_pop_command() {
         // initialize a list of valid commands
         // pop local 2
-        let commands = vec![Command {
-            command_type: CommandType::CPop,
-            arg_1: Some("local".to_string()),
-            arg_2: Some("2".to_string()),
-            classname: None,
-        }];
+        let commands = vec![SourceCommand::new(
+            "Root",
+            Command::Pop {
+                segment: Segment::Local,
+                index: 2,
+            },
+        )];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
 
@@ -891,12 +753,13 @@ mod tests {
     fn process_push_static_command() {
         // initialize a list of valid commands
         // push static 2
-        let commands = vec![Command {
-            command_type: CommandType::CPush,
-            arg_1: Some("static".to_string()),
-            arg_2: Some("2".to_string()),
-            classname: Some("test".to_string()),
-        }];
+        let commands = vec![SourceCommand::new(
+            "test",
+            Command::Push {
+                segment: Segment::Static,
+                index: 2,
+            },
+        )];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
 
@@ -913,12 +776,13 @@ mod tests {
     fn process_push_pointer_command() {
         // initialize a list of valid commands
         // push pointer 0
-        let commands = vec![Command {
-            command_type: CommandType::CPush,
-            arg_1: Some("pointer".to_string()),
-            arg_2: Some("0".to_string()),
-            classname: None,
-        }];
+        let commands = vec![SourceCommand::new(
+            "Root",
+            Command::Push {
+                segment: Segment::Pointer,
+                index: 0,
+            },
+        )];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
 
@@ -935,12 +799,13 @@ mod tests {
     fn process_push_temp_command() {
         // initialize a list of valid commands
         // push temp 2
-        let commands = vec![Command {
-            command_type: CommandType::CPush,
-            arg_1: Some("temp".to_string()),
-            arg_2: Some("2".to_string()),
-            classname: None,
-        }];
+        let commands = vec![SourceCommand::new(
+            "Root",
+            Command::Push {
+                segment: Segment::Temp,
+                index: 2,
+            },
+        )];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
 
@@ -957,12 +822,13 @@ mod tests {
     fn process_pop_pointer_command() {
         // initialize a list of valid commands
         // pop pointer 0
-        let commands = vec![Command {
-            command_type: CommandType::CPop,
-            arg_1: Some("pointer".to_string()),
-            arg_2: Some("0".to_string()),
-            classname: None,
-        }];
+        let commands = vec![SourceCommand::new(
+            "Root",
+            Command::Pop {
+                segment: Segment::Pointer,
+                index: 0,
+            },
+        )];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
 
@@ -979,12 +845,13 @@ mod tests {
     fn process_pop_temp_command() {
         // initialize a list of valid commands
         // pop temp 2
-        let commands = vec![Command {
-            command_type: CommandType::CPop,
-            arg_1: Some("temp".to_string()),
-            arg_2: Some("2".to_string()),
-            classname: None,
-        }];
+        let commands = vec![SourceCommand::new(
+            "Root",
+            Command::Pop {
+                segment: Segment::Temp,
+                index: 2,
+            },
+        )];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
 
@@ -1000,24 +867,21 @@ mod tests {
     #[test]
     fn process_arithmetic_add_command() {
         let commands = vec![
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("7".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("8".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CArithmetic,
-                arg_1: Some("add".to_string()),
-                arg_2: None,
-                classname: None,
-            },
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Constant,
+                    index: 7,
+                },
+            ),
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Constant,
+                    index: 8,
+                },
+            ),
+            SourceCommand::new("Root", Command::Arithmetic(Op::Add)),
         ];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
@@ -1042,24 +906,21 @@ mod tests {
     #[test]
     fn process_arithmetic_sub_command() {
         let commands = vec![
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("7".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("8".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CArithmetic,
-                arg_1: Some("sub".to_string()),
-                arg_2: None,
-                classname: None,
-            },
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Constant,
+                    index: 7,
+                },
+            ),
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Constant,
+                    index: 8,
+                },
+            ),
+            SourceCommand::new("Root", Command::Arithmetic(Op::Sub)),
         ];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
@@ -1084,18 +945,14 @@ mod tests {
     #[test]
     fn process_arithmetic_neg_command() {
         let commands = vec![
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("7".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CArithmetic,
-                arg_1: Some("neg".to_string()),
-                arg_2: None,
-                classname: None,
-            },
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Constant,
+                    index: 7,
+                },
+            ),
+            SourceCommand::new("Root", Command::Arithmetic(Op::Neg)),
         ];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
@@ -1113,24 +970,21 @@ mod tests {
     #[test]
     fn process_arithmetic_eq_command() {
         let commands = vec![
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("7".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("8".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CArithmetic,
-                arg_1: Some("eq".to_string()),
-                arg_2: None,
-                classname: None,
-            },
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Constant,
+                    index: 7,
+                },
+            ),
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Constant,
+                    index: 8,
+                },
+            ),
+            SourceCommand::new("Root", Command::Arithmetic(Op::Eq)),
         ];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
@@ -1151,24 +1005,21 @@ mod tests {
     #[test]
     fn process_arithmetic_gt_command_with_bootstrap() {
         let commands = vec![
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("7".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("8".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CArithmetic,
-                arg_1: Some("gt".to_string()),
-                arg_2: None,
-                classname: None,
-            },
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Constant,
+                    index: 7,
+                },
+            ),
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Constant,
+                    index: 8,
+                },
+            ),
+            SourceCommand::new("Root", Command::Arithmetic(Op::Gt)),
         ];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: true });
@@ -1181,7 +1032,10 @@ mod tests {
             generator.instructions[0].instruction,
             "@256\nD=A\n@SP\nM=D\n"
         );
-        assert_eq!(generator.instructions[1].instruction, "@Sys.init\n0;JMP\n");
+        assert_eq!(
+            generator.instructions[1].instruction,
+            "@Bootstrap$ret\nD=A\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@LCL\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@ARG\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@THIS\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@THAT\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@SP\nD=M\n@5\nD=D-A\n@ARG\nM=D\n@SP\nD=M\n@LCL\nM=D\n@Sys.init\n0;JMP\n(Bootstrap$ret)\n"
+        );
         assert_eq!(
             generator.instructions[2].instruction,
             "@7\nD=A\n@SP\nA=M\nM=D\n@SP\nM=M+1\n"
@@ -1195,24 +1049,21 @@ mod tests {
     #[test]
     fn process_arithmetic_gt_command() {
         let commands = vec![
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("7".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("8".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CArithmetic,
-                arg_1: Some("gt".to_string()),
-                arg_2: None,
-                classname: None,
-            },
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Constant,
+                    index: 7,
+                },
+            ),
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Constant,
+                    index: 8,
+                },
+            ),
+            SourceCommand::new("Root", Command::Arithmetic(Op::Gt)),
         ];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
@@ -1233,24 +1084,21 @@ mod tests {
     #[test]
     fn process_arithmetic_lt_command() {
         let commands = vec![
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("7".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("8".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CArithmetic,
-                arg_1: Some("lt".to_string()),
-                arg_2: None,
-                classname: None,
-            },
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Constant,
+                    index: 7,
+                },
+            ),
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Constant,
+                    index: 8,
+                },
+            ),
+            SourceCommand::new("Root", Command::Arithmetic(Op::Lt)),
         ];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
@@ -1271,24 +1119,21 @@ mod tests {
     #[test]
     fn process_arithmetic_and_command() {
         let commands = vec![
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("7".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("8".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CArithmetic,
-                arg_1: Some("and".to_string()),
-                arg_2: None,
-                classname: None,
-            },
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Constant,
+                    index: 7,
+                },
+            ),
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Constant,
+                    index: 8,
+                },
+            ),
+            SourceCommand::new("Root", Command::Arithmetic(Op::And)),
         ];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
@@ -1313,24 +1158,21 @@ mod tests {
     #[test]
     fn process_arithmetic_or_command() {
         let commands = vec![
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("7".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("8".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CArithmetic,
-                arg_1: Some("or".to_string()),
-                arg_2: None,
-                classname: None,
-            },
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Constant,
+                    index: 7,
+                },
+            ),
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Constant,
+                    index: 8,
+                },
+            ),
+            SourceCommand::new("Root", Command::Arithmetic(Op::Or)),
         ];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
@@ -1355,18 +1197,14 @@ mod tests {
     #[test]
     fn process_arithmetic_not_command() {
         let commands = vec![
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("7".to_string()),
-                classname: None,
-            },
-            Command {
-                command_type: CommandType::CArithmetic,
-                arg_1: Some("not".to_string()),
-                arg_2: None,
-                classname: None,
-            },
+            SourceCommand::new(
+                "Root",
+                Command::Push {
+                    segment: Segment::Constant,
+                    index: 7,
+                },
+            ),
+            SourceCommand::new("Root", Command::Arithmetic(Op::Not)),
         ];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
@@ -1396,14 +1234,65 @@ mod tests {
         );
     }
 
+    #[test]
+    fn pick_cache_and_segment() {
+        assert_eq!(AssemblyGenerator::pick_cache(13), "@R13\n");
+        assert_eq!(AssemblyGenerator::return_segment(Segment::That), "THAT");
+    }
+
+    #[test]
+    fn process_program_with_several_files() {
+        let file = |classname: &str| {
+            vec![SourceCommand::new(
+                classname,
+                Command::Push {
+                    segment: Segment::Static,
+                    index: 0,
+                },
+            )]
+        };
+
+        let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: true });
+
+        generator.process_program(&[file("Class1"), file("Class2")]);
+
+        let program = String::from_utf8(generator.instructions_to_bytes()).unwrap();
+
+        assert_eq!(program.matches("@Sys.init\n").count(), 1);
+        assert_eq!(program.matches("(END)\n").count(), 1);
+        assert!(program.find("@Class1.16\n").unwrap() < program.find("@Class2.16\n").unwrap());
+    }
+
+    #[test]
+    fn process_comparisons_with_unique_labels() {
+        let comparison =
+            |op: Op, classname: &str| SourceCommand::new(classname, Command::Arithmetic(op));
+
+        let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
+
+        generator.process_program(&[
+            vec![comparison(Op::Eq, "Class1"), comparison(Op::Lt, "Class1")],
+            vec![comparison(Op::Eq, "Class2")],
+        ]);
+
+        assert!(generator.instructions[0]
+            .instruction
+            .contains("(Class1.default$EQ_START_1)\n"));
+        assert!(generator.instructions[1]
+            .instruction
+            .contains("(Class1.default$LT_END_2)\n"));
+        assert!(generator.instructions[2]
+            .instruction
+            .contains("(Class2.default$EQ_START_3)\n"));
+        assert_eq!(generator.last_comparison, 3);
+    }
+
     #[test]
     fn process_label_command() {
-        let commands = vec![Command {
-            command_type: CommandType::CLabel,
-            arg_1: Some("loopLabel".to_string()),
-            arg_2: None,
-            classname: Some("SimpleFunction".to_string()),
-        }];
+        let commands = vec![SourceCommand::new(
+            "SimpleFunction",
+            Command::Label("loopLabel".to_string()),
+        )];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
 
@@ -1418,12 +1307,10 @@ mod tests {
 
     #[test]
     fn process_goto_command() {
-        let commands = vec![Command {
-            command_type: CommandType::CGoto,
-            arg_1: Some("loopLabel".to_string()),
-            arg_2: None,
-            classname: Some("SimpleFunction".to_string()),
-        }];
+        let commands = vec![SourceCommand::new(
+            "SimpleFunction",
+            Command::Goto("loopLabel".to_string()),
+        )];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
 
@@ -1438,12 +1325,10 @@ mod tests {
 
     #[test]
     fn process_if_command() {
-        let commands = vec![Command {
-            command_type: CommandType::CIf,
-            arg_1: Some("loopLabel".to_string()),
-            arg_2: None,
-            classname: Some("SimpleFunction".to_string()),
-        }];
+        let commands = vec![SourceCommand::new(
+            "SimpleFunction",
+            Command::IfGoto("loopLabel".to_string()),
+        )];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
 
@@ -1459,32 +1344,13 @@ mod tests {
 
     #[test]
     fn process_function_command() {
-        let commands = vec![Command {
-            command_type: CommandType::CFunction,
-            arg_1: Some("SimpleFunction".to_string()),
-            arg_2: Some("2".to_string()),
-            classname: Some("test".to_string()),
-        }];
-
-        let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
-
-        generator.process_commands(&commands);
-
-        assert_eq!(generator.instructions.len(), 2);
-        assert_eq!(
-            generator.instructions[0].instruction,
-            "(test.SimpleFunction)\n@0\nD=A\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@0\nD=A\n@SP\nA=M\nM=D\n@SP\nM=M+1\n"
-        );
-    }
-
-    #[test]
-    fn process_function_command_without_classname() {
-        let commands = vec![Command {
-            command_type: CommandType::CFunction,
-            arg_1: Some("SimpleFunction".to_string()),
-            arg_2: Some("2".to_string()),
-            classname: None,
-        }];
+        let commands = vec![SourceCommand::new(
+            "test",
+            Command::Function {
+                name: "SimpleFunction".to_string(),
+                nlocals: 2,
+            },
+        )];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
 
@@ -1499,12 +1365,13 @@ mod tests {
 
     #[test]
     fn process_call_command() {
-        let commands = vec![Command {
-            command_type: CommandType::CCall,
-            arg_1: Some("SimpleFunction".to_string()),
-            arg_2: Some("2".to_string()),
-            classname: Some("test".to_string()),
-        }];
+        let commands = vec![SourceCommand::new(
+            "test",
+            Command::Call {
+                name: "SimpleFunction".to_string(),
+                nargs: 2,
+            },
+        )];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
 
@@ -1513,18 +1380,13 @@ mod tests {
         assert_eq!(generator.instructions.len(), 2);
         assert_eq!(
             generator.instructions[0].instruction,
-            "@test.default$ret.0\nD=A\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@LCL\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@ARG\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@THIS\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@THAT\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@SP\nD=M\n@7\nD=D-A\n@ARG\nM=D\n@SP\nD=M\n@LCL\nM=D\n@test.SimpleFunction\n0;JMP\n(test.default$ret.0)\n"
+            "@test.default$ret.0\nD=A\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@LCL\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@ARG\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@THIS\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@THAT\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@SP\nD=M\n@7\nD=D-A\n@ARG\nM=D\n@SP\nD=M\n@LCL\nM=D\n@SimpleFunction\n0;JMP\n(test.default$ret.0)\n"
         );
     }
 
     #[test]
     fn process_return_command() {
-        let commands = vec![Command {
-            command_type: CommandType::CReturn,
-            arg_1: None,
-            arg_2: None,
-            classname: None,
-        }];
+        let commands = vec![SourceCommand::new("Root", Command::Return)];
 
         let mut generator = AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
 
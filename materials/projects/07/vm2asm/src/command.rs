@@ -0,0 +1,294 @@
+use std::fmt;
+
+/// largest value of an A-instruction, constants and indexes must fit in it
+pub const MAX_INDEX: u16 = 32767;
+
+/// Memory segments of the VM
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Segment {
+    Constant,
+    Local,
+    Argument,
+    This,
+    That,
+    Pointer,
+    Temp,
+    Static,
+}
+
+impl Segment {
+    /// Returns the segment with a VM name (e.g. `local`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "constant" => Some(Self::Constant),
+            "local" => Some(Self::Local),
+            "argument" => Some(Self::Argument),
+            "this" => Some(Self::This),
+            "that" => Some(Self::That),
+            "pointer" => Some(Self::Pointer),
+            "temp" => Some(Self::Temp),
+            "static" => Some(Self::Static),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Constant => "constant",
+            Self::Local => "local",
+            Self::Argument => "argument",
+            Self::This => "this",
+            Self::That => "that",
+            Self::Pointer => "pointer",
+            Self::Temp => "temp",
+            Self::Static => "static",
+        }
+    }
+
+    /// Returns the largest index of the segment
+    pub fn max_index(&self) -> u16 {
+        match self {
+            Self::Pointer => 1,
+            Self::Temp => 7,
+            // statics use the RAM from 16 to 255
+            Self::Static => 239,
+            _ => MAX_INDEX,
+        }
+    }
+
+    /// Returns the register holding the base address of the segment, only
+    /// local, argument, this and that have one
+    pub fn base_pointer(&self) -> Option<&'static str> {
+        match self {
+            Self::Local => Some("LCL"),
+            Self::Argument => Some("ARG"),
+            Self::This => Some("THIS"),
+            Self::That => Some("THAT"),
+            _ => None,
+        }
+    }
+}
+
+/// Arithmetic and logical operations of the VM
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Op {
+    Add,
+    Sub,
+    Neg,
+    Eq,
+    Gt,
+    Lt,
+    And,
+    Or,
+    Not,
+}
+
+impl Op {
+    /// Returns the operation with a VM name (e.g. `add`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "add" => Some(Self::Add),
+            "sub" => Some(Self::Sub),
+            "neg" => Some(Self::Neg),
+            "eq" => Some(Self::Eq),
+            "gt" => Some(Self::Gt),
+            "lt" => Some(Self::Lt),
+            "and" => Some(Self::And),
+            "or" => Some(Self::Or),
+            "not" => Some(Self::Not),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Add => "add",
+            Self::Sub => "sub",
+            Self::Neg => "neg",
+            Self::Eq => "eq",
+            Self::Gt => "gt",
+            Self::Lt => "lt",
+            Self::And => "and",
+            Self::Or => "or",
+            Self::Not => "not",
+        }
+    }
+}
+
+/// Command of the VM language, built by the parser once its arguments
+/// are valid (other commands are checked with `validate`) and formatted
+/// back as VM code with `Display`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Command {
+    Arithmetic(Op),
+    Push { segment: Segment, index: u16 },
+    Pop { segment: Segment, index: u16 },
+    Label(String),
+    Goto(String),
+    IfGoto(String),
+    Function { name: String, nlocals: u16 },
+    Call { name: String, nargs: u16 },
+    Return,
+}
+
+impl Command {
+    /// Checks the arguments of a command built outside of the parser: no
+    /// pop to the constant segment, indexes inside their segment and
+    /// counts that fit in an A-instruction
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Pop {
+                segment: Segment::Constant,
+                ..
+            } => Err(String::from("Cannot pop to the constant segment")),
+            Self::Push { segment, index } | Self::Pop { segment, index }
+                if *index > segment.max_index() =>
+            {
+                Err(format!(
+                    "Index out of range for {} (0-{}): {}",
+                    segment.name(),
+                    segment.max_index(),
+                    index
+                ))
+            }
+            Self::Function { nlocals: count, .. } | Self::Call { nargs: count, .. }
+                if *count > MAX_INDEX =>
+            {
+                Err(format!("Invalid count: {}", count))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Arithmetic(op) => write!(formatter, "{}", op.name()),
+            Self::Push { segment, index } => {
+                write!(formatter, "push {} {}", segment.name(), index)
+            }
+            Self::Pop { segment, index } => write!(formatter, "pop {} {}", segment.name(), index),
+            Self::Label(label) => write!(formatter, "label {}", label),
+            Self::Goto(label) => write!(formatter, "goto {}", label),
+            Self::IfGoto(label) => write!(formatter, "if-goto {}", label),
+            Self::Function { name, nlocals } => write!(formatter, "function {} {}", name, nlocals),
+            Self::Call { name, nargs } => write!(formatter, "call {} {}", name, nargs),
+            Self::Return => write!(formatter, "return"),
+        }
+    }
+}
+
+/// Command with the class (.vm file) it belongs to, which names its
+/// statics and labels, and the line it was read from
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SourceCommand {
+    pub command: Command,
+    pub classname: String,
+    /// text of the line of the .vm file, for diagnostics and annotations
+    pub source: String,
+    /// number of the line of the .vm file (from 1), 0 for the commands
+    /// that were not read from a file
+    pub line: usize,
+}
+
+impl SourceCommand {
+    /// Creates a command of a class that was not read from a file, its
+    /// source is the command formatted as VM code
+    ///
+    /// # Arguments
+    ///
+    /// * `classname` - The class of the command (e.g. Main)
+    /// * `command` - The command
+    pub fn new(classname: &str, command: Command) -> Self {
+        Self {
+            source: command.to_string(),
+            classname: classname.to_string(),
+            line: 0,
+            command,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_commands_as_vm_code() {
+        let commands = [
+            Command::Push {
+                segment: Segment::Constant,
+                index: 7,
+            },
+            Command::Pop {
+                segment: Segment::Temp,
+                index: 2,
+            },
+            Command::Arithmetic(Op::Lt),
+            Command::IfGoto("LOOP".to_string()),
+            Command::Function {
+                name: "Main.main".to_string(),
+                nlocals: 2,
+            },
+            Command::Call {
+                name: "Math.multiply".to_string(),
+                nargs: 2,
+            },
+            Command::Return,
+        ];
+
+        let lines: Vec<String> = commands.iter().map(|command| command.to_string()).collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "push constant 7",
+                "pop temp 2",
+                "lt",
+                "if-goto LOOP",
+                "function Main.main 2",
+                "call Math.multiply 2",
+                "return"
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_commands() {
+        let push = |segment: Segment, index: u16| Command::Push { segment, index };
+
+        assert_eq!(push(Segment::Temp, 7).validate(), Ok(()));
+        assert_eq!(push(Segment::Constant, MAX_INDEX).validate(), Ok(()));
+        assert_eq!(
+            push(Segment::Temp, 65535).validate(),
+            Err(String::from("Index out of range for temp (0-7): 65535"))
+        );
+        assert_eq!(
+            Command::Pop {
+                segment: Segment::Constant,
+                index: 0
+            }
+            .validate(),
+            Err(String::from("Cannot pop to the constant segment"))
+        );
+        assert_eq!(
+            Command::Call {
+                name: "Main.run".to_string(),
+                nargs: 65535
+            }
+            .validate(),
+            Err(String::from("Invalid count: 65535"))
+        );
+    }
+
+    #[test]
+    fn find_segments_and_operations_by_name() {
+        assert_eq!(Segment::from_name("that"), Some(Segment::That));
+        assert_eq!(Segment::from_name("stack"), None);
+        assert_eq!(Segment::Temp.max_index(), 7);
+        assert_eq!(Segment::Argument.base_pointer(), Some("ARG"));
+        assert_eq!(Op::from_name("not"), Some(Op::Not));
+        assert_eq!(Op::from_name("mul"), None);
+    }
+}
@@ -0,0 +1,54 @@
+use std::fmt;
+
+/// Error found while reading a .vm file, lines and columns start at 1
+#[derive(Debug, PartialEq, Clone)]
+pub struct VmError {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl VmError {
+    /// Creates an error at a position of a file
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The name of the .vm file
+    /// * `line` - The line of the error
+    /// * `column` - The column where the wrong token starts
+    /// * `message` - The description of the error
+    pub fn new(file: &str, line: usize, column: usize, message: &str) -> Self {
+        Self {
+            file: file.to_string(),
+            line,
+            column,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for VmError {
+    /// Formats the error as `file:line:column: message`
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            formatter,
+            "{}:{}:{}: {}",
+            self.file, self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for VmError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_error_with_position() {
+        let error = VmError::new("Main.vm", 3, 6, "Unknown segment: stack");
+
+        assert_eq!(error.to_string(), "Main.vm:3:6: Unknown segment: stack");
+    }
+}
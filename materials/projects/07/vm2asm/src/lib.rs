@@ -2,3 +2,5 @@ pub mod parser;
 pub mod logs;
 pub mod utils;
 pub mod code;
+pub mod command;
+pub mod error;
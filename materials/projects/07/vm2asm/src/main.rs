@@ -1,10 +1,12 @@
 use clap::Parser as ClapParser;
 use code::AssemblyConfiguration;
-use logs::log_command;
+use logs::{log_command, log_error};
 use std::path::Path;
 
 // module definitions
 mod code;
+mod command;
+mod error;
 mod logs;
 mod parser;
 mod utils;
@@ -43,8 +45,11 @@ pub fn main() {
     // get inputs from path
     let inputs = utils::get_inputs_from_path(&input);
 
-    // vector to store all the instrcutions across the files
-    let mut instructions = Vec::new();
+    // commands of every file, translated together as a single program
+    let mut files = Vec::new();
+
+    // errors of all the files, reported together before translating
+    let mut errors = Vec::new();
 
     for input in inputs {
         // read the contents of the input file to be translated to commands
@@ -57,34 +62,53 @@ pub fn main() {
             .unwrap_or("");
 
         // initialize the parser with class_name and content
-        let mut parser = parser::Parser::new(&input_content, &input_file);
+        let mut parser = parser::Parser::new(&input_content, input_file);
 
         // parse input
-        parser.parse();
-
-        // get the commands
-        let commands = parser.get_fields();
-
-        // generate assembly generator
-        let mut generator = code::AssemblyGenerator::new(AssemblyConfiguration { bootstrap });
+        if let Err(file_errors) = parser.parse() {
+            errors.extend(file_errors);
+            continue;
+        }
 
-        // generate assembly instructions from commands
-        generator.process_commands(&commands);
+        // keep the commands of the file
+        files.push(parser.get_fields().to_vec());
+    }
 
-        // get the assembly instructions
-        for instruction in generator.instructions.iter() {
-            log_command(&format!(
-                "{:?} >> {:?} from {:?}",
-                parser.get_base_name(),
-                instruction.instruction,
-                instruction.command
-            ));
+    if !errors.is_empty() {
+        for error in errors.iter() {
+            log_error(&error.to_string());
         }
 
-        // add isntructions into instructions vector
-        instructions.extend(generator.instructions_to_bytes());
+        log_error(&format!(
+            "{} error(s) found, nothing translated",
+            errors.len()
+        ));
+        std::process::exit(1);
+    }
+
+    // generate a single assembly generator for the whole program
+    let mut generator = code::AssemblyGenerator::new(AssemblyConfiguration { bootstrap });
+
+    // generate assembly instructions from the commands of all the files
+    generator.process_program(&files);
+
+    // get the assembly instructions
+    for instruction in generator.instructions.iter() {
+        // the commands added by the translator (bootstrap, end) have no line
+        let source = instruction
+            .command
+            .as_ref()
+            .map_or("-", |command| command.source.as_str());
+
+        log_command(&format!(
+            "{:?} >> {:?}",
+            source.trim(),
+            instruction.instruction
+        ));
     }
 
+    let instructions = generator.instructions_to_bytes();
+
     // save the output of all the instructions
     utils::save_file(&output, &instructions);
 }
@@ -1,37 +1,18 @@
 use crate::{
+    command::{Command, Op, Segment, SourceCommand},
+    error::VmError,
     logs::{log_info, log_success},
     utils,
 };
 
-#[derive(Debug, PartialEq, Clone)]
-pub enum CommandType {
-    CArithmetic,
-    CPush,
-    CPop,
-    CFunction,
-    CReturn,
-    CCall,
-    CLabel,
-    CGoto,
-    CIf,
-}
-
-#[derive(Debug, PartialEq, Clone)]
-pub struct Command {
-    pub arg_1: Option<String>,
-    pub arg_2: Option<String>, // only for push, pop, function, call
-    pub command_type: CommandType,
-    pub classname: Option<String>,
-}
-
 pub struct Parser {
-    pub commands: Vec<Command>,
+    pub commands: Vec<SourceCommand>,
     pub input: String,
     filename: String,
 }
 
 impl Parser {
-    pub fn get_fields(&self) -> &Vec<Command> {
+    pub fn get_fields(&self) -> &Vec<SourceCommand> {
         &self.commands
     }
 
@@ -51,142 +32,212 @@ impl Parser {
         }
 
         // return the name_assigned capitalize on the first letter
-        return utils::capitalize_n_letters(&name_assigned, 1);
+        utils::capitalize_n_letters(name_assigned, 1)
+    }
+
+    /// Returns the name of the file shown in the errors
+    fn file_name(&self) -> String {
+        if self.filename.is_empty() {
+            return format!("{}.vm", self.get_base_name());
+        }
+
+        format!("{}.vm", self.filename)
+    }
+
+    /// Returns the number of arguments of a command, or None if the
+    /// command does not exist
+    fn arity(command: &str) -> Option<usize> {
+        match command {
+            "label" | "goto" | "if-goto" => Some(1),
+            "push" | "pop" | "function" | "call" => Some(2),
+            "return" => Some(0),
+            _ => Op::from_name(command).map(|_| 0),
+        }
+    }
+
+    /// Checks that a symbol (label or function name) can be used in the
+    /// assembly: letters, digits, `_`, `.`, `$` and `:`, not starting with
+    /// a digit
+    fn is_symbol(name: &str) -> bool {
+        !name.starts_with(|char: char| char.is_ascii_digit())
+            && name
+                .chars()
+                .all(|char| char.is_ascii_alphanumeric() || "_.$:".contains(char))
+    }
+
+    /// Builds the command of a line once its arguments are valid
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The number of the line (from 1)
+    /// * `tokens` - The column and the text of each token of the line
+    fn parse_command(&self, line: usize, tokens: &[(usize, &str)]) -> Result<Command, VmError> {
+        let error = |column: usize, message: String| {
+            Err(VmError::new(&self.file_name(), line, column, &message))
+        };
+
+        let (column, command) = tokens[0];
+        let arguments = &tokens[1..];
+
+        let Some(arity) = Self::arity(command) else {
+            return error(column, format!("Unknown command: {}", command));
+        };
+
+        if arguments.len() != arity {
+            return error(
+                column,
+                format!(
+                    "{} expects {} argument(s), found {}",
+                    command,
+                    arity,
+                    arguments.len()
+                ),
+            );
+        }
+
+        // the column where the errors of the command are reported
+        let (column, command) = match command {
+            "push" | "pop" => {
+                let (segment_column, segment_name) = arguments[0];
+                let (index_column, index) = arguments[1];
+
+                let Some(segment) = Segment::from_name(segment_name) else {
+                    return error(segment_column, format!("Unknown segment: {}", segment_name));
+                };
+
+                let index = match index.parse::<i64>() {
+                    Ok(value) if value < 0 => {
+                        return error(
+                            index_column,
+                            format!("Index must not be negative: {}", index),
+                        )
+                    }
+                    Ok(value) if value <= u16::MAX as i64 => value as u16,
+                    _ => return error(index_column, format!("Invalid index: {}", index)),
+                };
+
+                if command == "push" {
+                    (index_column, Command::Push { segment, index })
+                } else if segment == Segment::Constant {
+                    (segment_column, Command::Pop { segment, index })
+                } else {
+                    (index_column, Command::Pop { segment, index })
+                }
+            }
+            "function" | "call" => {
+                let (name_column, name) = arguments[0];
+                let (count_column, count) = arguments[1];
+
+                if !Self::is_symbol(name) {
+                    return error(name_column, format!("Invalid function name: {}", name));
+                }
+
+                let Ok(count) = count.parse::<u16>() else {
+                    return error(count_column, format!("Invalid count: {}", count));
+                };
+                let name = name.to_string();
+
+                if command == "function" {
+                    (
+                        count_column,
+                        Command::Function {
+                            name,
+                            nlocals: count,
+                        },
+                    )
+                } else {
+                    (count_column, Command::Call { name, nargs: count })
+                }
+            }
+            "label" | "goto" | "if-goto" => {
+                let (label_column, label) = arguments[0];
+
+                if !Self::is_symbol(label) {
+                    return error(label_column, format!("Invalid label: {}", label));
+                }
+
+                let label = label.to_string();
+
+                (
+                    label_column,
+                    match command {
+                        "label" => Command::Label(label),
+                        "goto" => Command::Goto(label),
+                        _ => Command::IfGoto(label),
+                    },
+                )
+            }
+            "return" => (column, Command::Return),
+            // the other commands without arguments are operations
+            _ => (column, Command::Arithmetic(Op::from_name(command).unwrap())),
+        };
+
+        match command.validate() {
+            Ok(()) => Ok(command),
+            Err(message) => error(column, message),
+        }
     }
 
-    fn _parse_simple(&mut self) {
+    fn _parse_simple(&mut self) -> Result<(), Vec<VmError>> {
         log_info("Parsing input file");
         log_info(format!("Class name: {:?}", self.get_base_name()).as_str());
 
+        let input = self.input.clone();
+        let classname = self.get_base_name();
+        let mut errors: Vec<VmError> = Vec::new();
+
         // should parse input and get commands into self.commands vector
-        for line in self.input.lines() {
-            // discard empty lines
-            let line = line.trim();
-            if line.is_empty() || line.starts_with("//") {
+        for (index, line) in input.lines().enumerate() {
+            // drop the comment at the end of the line, even without a space
+            // before it (e.g. `push constant 7// x`)
+            let code = line.split("//").next().unwrap_or("");
+
+            // split the rest in tokens (spaces or tabs) with the column, from
+            // 1, where each one starts in the line
+            let tokens: Vec<(usize, &str)> = code
+                .split_whitespace()
+                .map(|token| (token.as_ptr() as usize - line.as_ptr() as usize + 1, token))
+                .collect();
+
+            // discard empty lines and lines with only a comment
+            if tokens.is_empty() {
                 continue;
             }
 
-            // we should split spaces in line
-            let line_parts: Vec<&str> = line.split_whitespace().collect();
-
-            // get the command type and arguments ( part[1] and part[2] )
-            match line_parts.get(0) {
-                Some(&"push") => self.commands.push(Command {
-                    command_type: CommandType::CPush,
-                    arg_1: Some(line_parts[1].to_string()),
-                    arg_2: Some(line_parts[2].to_string()),
-                    classname: None,
-                }),
-                Some(&"pop") => self.commands.push(Command {
-                    command_type: CommandType::CPop,
-                    arg_1: Some(line_parts[1].to_string()),
-                    arg_2: Some(line_parts[2].to_string()),
-                    classname: None,
-                }),
-                Some(&"add") => self.commands.push(Command {
-                    command_type: CommandType::CArithmetic,
-                    arg_1: Some("add".to_string()),
-                    arg_2: None,
-                    classname: None,
-                }),
-                Some(&"sub") => self.commands.push(Command {
-                    command_type: CommandType::CArithmetic,
-                    arg_1: Some("sub".to_string()),
-                    arg_2: None,
-                    classname: None,
-                }),
-                Some(&"eq") => self.commands.push(Command {
-                    command_type: CommandType::CArithmetic,
-                    arg_1: Some("eq".to_string()),
-                    arg_2: None,
-                    classname: None,
-                }),
-                Some(&"lt") => self.commands.push(Command {
-                    command_type: CommandType::CArithmetic,
-                    arg_1: Some("lt".to_string()),
-                    arg_2: None,
-                    classname: None,
-                }),
-                Some(&"gt") => self.commands.push(Command {
-                    command_type: CommandType::CArithmetic,
-                    arg_1: Some("gt".to_string()),
-                    arg_2: None,
-                    classname: None,
-                }),
-                Some(&"neg") => self.commands.push(Command {
-                    command_type: CommandType::CArithmetic,
-                    arg_1: Some("neg".to_string()),
-                    arg_2: None,
-                    classname: None,
-                }),
-                Some(&"and") => self.commands.push(Command {
-                    command_type: CommandType::CArithmetic,
-                    arg_1: Some("and".to_string()),
-                    arg_2: None,
-                    classname: None,
+            // keep going after an error to report all the errors of the file
+            match self.parse_command(index + 1, &tokens) {
+                Ok(command) => self.commands.push(SourceCommand {
+                    source: line.trim_end_matches('\r').to_string(),
+                    line: index + 1,
+                    ..SourceCommand::new(&classname, command)
                 }),
-                Some(&"or") => self.commands.push(Command {
-                    command_type: CommandType::CArithmetic,
-                    arg_1: Some("or".to_string()),
-                    arg_2: None,
-                    classname: None,
-                }),
-                Some(&"not") => self.commands.push(Command {
-                    command_type: CommandType::CArithmetic,
-                    arg_1: Some("not".to_string()),
-                    arg_2: None,
-                    classname: None,
-                }),
-                Some(&"label") => self.commands.push(Command {
-                    command_type: CommandType::CLabel,
-                    arg_1: Some(line_parts[1].to_string()),
-                    arg_2: None,
-                    classname: Some(self.get_base_name()),
-                }),
-                Some(&"if-goto") => self.commands.push(Command {
-                    command_type: CommandType::CIf,
-                    arg_1: Some(line_parts[1].to_string()),
-                    arg_2: None,
-                    classname: Some(self.get_base_name()),
-                }),
-                Some(&"goto") => self.commands.push(Command {
-                    command_type: CommandType::CGoto,
-                    arg_1: Some(line_parts[1].to_string()),
-                    arg_2: None,
-                    classname: Some(self.get_base_name()),
-                }),
-                Some(&"call") => self.commands.push(Command {
-                    command_type: CommandType::CCall,
-                    arg_1: Some(line_parts[1].to_string()), // function name
-                    arg_2: Some(line_parts[2].to_string()), // args
-                    classname: Some(self.get_base_name()),
-                }),
-                Some(&"function") => self.commands.push(Command {
-                    command_type: CommandType::CFunction,
-                    arg_1: Some(line_parts[1].to_string()), // function name
-                    arg_2: Some(line_parts[2].to_string()), // vars
-                    classname: Some(self.get_base_name()),
-                }),
-                Some(&"return") => self.commands.push(Command {
-                    command_type: CommandType::CReturn,
-                    arg_1: None,
-                    arg_2: None,
-                    classname: Some(self.get_base_name()),
-                }),
-                _ => continue,
-            };
+                Err(error) => errors.push(error),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
         }
 
         log_success("Parse completed successfully");
+
+        Ok(())
     }
 
-    pub fn parse(&mut self) {
+    /// Parses the input into commands, returns every error found in the
+    /// input instead of stopping at the first one
+    pub fn parse(&mut self) -> Result<(), Vec<VmError>> {
         if self.input.is_empty() {
-            panic!("Input should be defined before trying to parse");
+            return Err(vec![VmError::new(
+                &self.file_name(),
+                1,
+                1,
+                "Empty input, nothing to parse",
+            )]);
         }
 
         // call the parse method
-        self._parse_simple();
+        self._parse_simple()
     }
 }
 
@@ -214,24 +265,111 @@ mod tests {
     #[test]
     fn should_discard_empty_lines() {
         let mut parser = Parser::new("\n\n\n", "");
-        parser.parse();
+        parser.parse().unwrap();
 
         assert_eq!(parser.commands.len(), 0);
     }
 
+    #[test]
+    fn parse_comments_tabs_and_blank_lines() {
+        let input = "// header\r\n\r\n  \t\r\npush\tconstant 7 // seven\r\npush constant 8// eight\r\n\tadd\t//sum\r\n";
+        let mut parser = Parser::new(input, "");
+        parser.parse().unwrap();
+
+        let commands: Vec<String> = parser
+            .commands
+            .iter()
+            .map(|command| command.command.to_string())
+            .collect();
+        let sources: Vec<&str> = parser
+            .commands
+            .iter()
+            .map(|command| command.source.as_str())
+            .collect();
+
+        assert_eq!(commands, vec!["push constant 7", "push constant 8", "add"]);
+        assert_eq!(
+            sources,
+            vec![
+                "push\tconstant 7 // seven",
+                "push constant 8// eight",
+                "\tadd\t//sum"
+            ]
+        );
+    }
+
+    #[test]
+    fn report_errors_with_positions() {
+        let input = "push constant\npop pointer 2\n  push temp 8\npush constant -1\nfoo 1\npop constant 0\npush stack 0\nadd";
+        let mut parser = Parser::new(input, "main");
+
+        let errors: Vec<String> = parser
+            .parse()
+            .unwrap_err()
+            .iter()
+            .map(|error| error.to_string())
+            .collect();
+
+        assert_eq!(
+            errors,
+            vec![
+                "main.vm:1:1: push expects 2 argument(s), found 1",
+                "main.vm:2:13: Index out of range for pointer (0-1): 2",
+                "main.vm:3:13: Index out of range for temp (0-7): 8",
+                "main.vm:4:15: Index must not be negative: -1",
+                "main.vm:5:1: Unknown command: foo",
+                "main.vm:6:5: Cannot pop to the constant segment",
+                "main.vm:7:6: Unknown segment: stack",
+            ]
+        );
+    }
+
+    #[test]
+    fn report_invalid_labels_and_counts() {
+        let mut parser = Parser::new("label 1LOOP\ncall Main.run x\nfunction Main.run 2", "");
+
+        let errors = parser.parse().unwrap_err();
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[0],
+            VmError::new("Root.vm", 1, 7, "Invalid label: 1LOOP")
+        );
+        assert_eq!(
+            errors[1],
+            VmError::new("Root.vm", 2, 15, "Invalid count: x")
+        );
+    }
+
+    #[test]
+    fn report_validation_errors_at_their_argument() {
+        let mut parser = Parser::new("function Main.run 40000\npush static 70000\n", "");
+
+        assert_eq!(
+            parser.parse(),
+            Err(vec![
+                VmError::new("Root.vm", 1, 19, "Invalid count: 40000"),
+                VmError::new("Root.vm", 2, 13, "Invalid index: 70000"),
+            ])
+        );
+    }
+
     #[test]
     fn parse_push_command() {
         let mut parser = Parser::new("push constant 7", "");
-        parser.parse();
+        parser.parse().unwrap();
 
         assert_eq!(parser.commands.len(), 1);
         assert_eq!(
             parser.commands[0],
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("7".to_string()),
-                classname: None,
+            SourceCommand {
+                command: Command::Push {
+                    segment: Segment::Constant,
+                    index: 7
+                },
+                classname: "Root".to_string(),
+                source: "push constant 7".to_string(),
+                line: 1,
             }
         );
     }
@@ -239,42 +377,62 @@ mod tests {
     #[test]
     fn parse_pop_command() {
         let mut parser = Parser::new("pop local 0", "");
-        parser.parse();
+        parser.parse().unwrap();
 
         assert_eq!(parser.commands.len(), 1);
         assert_eq!(
             parser.commands[0],
-            Command {
-                command_type: CommandType::CPop,
-                arg_1: Some("local".to_string()),
-                arg_2: Some("0".to_string()),
-                classname: None,
+            SourceCommand {
+                command: Command::Pop {
+                    segment: Segment::Local,
+                    index: 0
+                },
+                classname: "Root".to_string(),
+                source: "pop local 0".to_string(),
+                line: 1,
             }
         );
     }
 
+    #[test]
+    fn parse_commands_with_classname() {
+        let mut parser = Parser::new("push static 0\npop static 1\nadd", "class1");
+        parser.parse().unwrap();
+
+        assert!(parser
+            .commands
+            .iter()
+            .all(|command| command.classname == "Class1"));
+    }
+
     #[test]
     fn parse_mutliple_commands() {
         let mut parser = Parser::new("push constant 7\npop local 0", "");
-        parser.parse();
+        parser.parse().unwrap();
 
         assert_eq!(parser.commands.len(), 2);
         assert_eq!(
             parser.commands[0],
-            Command {
-                command_type: CommandType::CPush,
-                arg_1: Some("constant".to_string()),
-                arg_2: Some("7".to_string()),
-                classname: None,
+            SourceCommand {
+                command: Command::Push {
+                    segment: Segment::Constant,
+                    index: 7
+                },
+                classname: "Root".to_string(),
+                source: "push constant 7".to_string(),
+                line: 1,
             }
         );
         assert_eq!(
             parser.commands[1],
-            Command {
-                command_type: CommandType::CPop,
-                arg_1: Some("local".to_string()),
-                arg_2: Some("0".to_string()),
-                classname: None,
+            SourceCommand {
+                command: Command::Pop {
+                    segment: Segment::Local,
+                    index: 0
+                },
+                classname: "Root".to_string(),
+                source: "pop local 0".to_string(),
+                line: 2,
             }
         );
     }
@@ -282,25 +440,25 @@ mod tests {
     #[test]
     fn parse_multiple_arithmetic_commands() {
         let mut parser = Parser::new("add\nsub\n", "");
-        parser.parse();
+        parser.parse().unwrap();
 
         assert_eq!(parser.commands.len(), 2);
         assert_eq!(
             parser.commands[0],
-            Command {
-                command_type: CommandType::CArithmetic,
-                arg_1: Some("add".to_string()),
-                arg_2: None,
-                classname: None,
+            SourceCommand {
+                command: Command::Arithmetic(Op::Add),
+                classname: "Root".to_string(),
+                source: "add".to_string(),
+                line: 1,
             }
         );
         assert_eq!(
             parser.commands[1],
-            Command {
-                command_type: CommandType::CArithmetic,
-                arg_1: Some("sub".to_string()),
-                arg_2: None,
-                classname: None,
+            SourceCommand {
+                command: Command::Arithmetic(Op::Sub),
+                classname: "Root".to_string(),
+                source: "sub".to_string(),
+                line: 2,
             }
         );
     }
@@ -308,16 +466,16 @@ mod tests {
     #[test]
     fn parse_label_command() {
         let mut parser = Parser::new("label LOOP_START", "");
-        parser.parse();
+        parser.parse().unwrap();
 
         assert_eq!(parser.commands.len(), 1);
         assert_eq!(
             parser.commands[0],
-            Command {
-                command_type: CommandType::CLabel,
-                arg_1: Some("LOOP_START".to_string()),
-                arg_2: None,
-                classname: Some("Root".to_string()),
+            SourceCommand {
+                command: Command::Label("LOOP_START".to_string()),
+                classname: "Root".to_string(),
+                source: "label LOOP_START".to_string(),
+                line: 1,
             }
         );
     }
@@ -325,16 +483,16 @@ mod tests {
     #[test]
     fn parse_if_goto_command() {
         let mut parser = Parser::new("if-goto LOOP_START", "");
-        parser.parse();
+        parser.parse().unwrap();
 
         assert_eq!(parser.commands.len(), 1);
         assert_eq!(
             parser.commands[0],
-            Command {
-                command_type: CommandType::CIf,
-                arg_1: Some("LOOP_START".to_string()),
-                arg_2: None,
-                classname: Some("Root".to_string()),
+            SourceCommand {
+                command: Command::IfGoto("LOOP_START".to_string()),
+                classname: "Root".to_string(),
+                source: "if-goto LOOP_START".to_string(),
+                line: 1,
             }
         );
     }
@@ -342,16 +500,16 @@ mod tests {
     #[test]
     fn parse_goto_command() {
         let mut parser = Parser::new("goto LOOP_START", "");
-        parser.parse();
+        parser.parse().unwrap();
 
         assert_eq!(parser.commands.len(), 1);
         assert_eq!(
             parser.commands[0],
-            Command {
-                command_type: CommandType::CGoto,
-                arg_1: Some("LOOP_START".to_string()),
-                arg_2: None,
-                classname: Some("Root".to_string()),
+            SourceCommand {
+                command: Command::Goto("LOOP_START".to_string()),
+                classname: "Root".to_string(),
+                source: "goto LOOP_START".to_string(),
+                line: 1,
             }
         );
     }
@@ -359,16 +517,19 @@ mod tests {
     #[test]
     fn parse_call_command() {
         let mut parser = Parser::new("call function 2", "test");
-        parser.parse();
+        parser.parse().unwrap();
 
         assert_eq!(parser.commands.len(), 1);
         assert_eq!(
             parser.commands[0],
-            Command {
-                command_type: CommandType::CCall,
-                arg_1: Some("function".to_string()),
-                arg_2: Some("2".to_string()),
-                classname: Some("Test".to_string()),
+            SourceCommand {
+                command: Command::Call {
+                    name: "function".to_string(),
+                    nargs: 2
+                },
+                classname: "Test".to_string(),
+                source: "call function 2".to_string(),
+                line: 1,
             }
         );
     }
@@ -376,16 +537,19 @@ mod tests {
     #[test]
     fn parse_function_command() {
         let mut parser = Parser::new("function hello 2", "test");
-        parser.parse();
+        parser.parse().unwrap();
 
         assert_eq!(parser.commands.len(), 1);
         assert_eq!(
             parser.commands[0],
-            Command {
-                command_type: CommandType::CFunction,
-                arg_1: Some("hello".to_string()),
-                arg_2: Some("2".to_string()),
-                classname: Some("Test".to_string()),
+            SourceCommand {
+                command: Command::Function {
+                    name: "hello".to_string(),
+                    nlocals: 2
+                },
+                classname: "Test".to_string(),
+                source: "function hello 2".to_string(),
+                line: 1,
             }
         );
     }
@@ -393,24 +557,47 @@ mod tests {
     #[test]
     fn parse_arithmetic_command() {
         let mut parser = Parser::new("add", "");
-        parser.parse();
+        parser.parse().unwrap();
 
         assert_eq!(parser.commands.len(), 1);
         assert_eq!(
             parser.commands[0],
-            Command {
-                command_type: CommandType::CArithmetic,
-                arg_1: Some("add".to_string()),
-                arg_2: None,
-                classname: None,
+            SourceCommand {
+                command: Command::Arithmetic(Op::Add),
+                classname: "Root".to_string(),
+                source: "add".to_string(),
+                line: 1,
             }
         );
     }
 
     #[test]
-    #[should_panic(expected = "Input should be defined before trying to parse")]
+    fn should_fail_on_negative_index() {
+        let mut parser = Parser::new("push local -2", "");
+
+        assert_eq!(
+            parser.parse(),
+            Err(vec![VmError::new(
+                "Root.vm",
+                1,
+                12,
+                "Index must not be negative: -2"
+            )])
+        );
+    }
+
+    #[test]
     fn fail_at_parsing_empty_input() {
-        let mut parser = Parser::new("", "");
-        parser.parse();
+        let mut parser = Parser::new("", "main");
+
+        assert_eq!(
+            parser.parse(),
+            Err(vec![VmError::new(
+                "main.vm",
+                1,
+                1,
+                "Empty input, nothing to parse"
+            )])
+        );
     }
 }
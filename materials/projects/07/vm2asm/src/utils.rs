@@ -35,8 +35,7 @@ pub fn header_info(app_name: &str, version: &str, input: &str) {
 /// * `input` - The input file to read.
 pub fn read_file(input: &str) -> String {
     log_info(format!("reading file: {}", input).as_str());
-    let input_content = std::fs::read_to_string(input).expect("failed to read file");
-    input_content
+    std::fs::read_to_string(input).expect("failed to read file")
 }
 
 /// Saves content to a file.
@@ -45,7 +44,7 @@ pub fn read_file(input: &str) -> String {
 ///
 /// * `input` - The input file to read.
 ///
-pub fn save_file(output: &str, content: &Vec<u8>) {
+pub fn save_file(output: &str, content: &[u8]) {
     let mut file = std::fs::File::create(output).expect("failed to create file");
 
     for byte in content.iter() {
@@ -63,7 +62,7 @@ pub fn save_file(output: &str, content: &Vec<u8>) {
 ///
 /// # Returns
 ///
-/// * A vector of strings containing the inputs.
+/// * A vector of strings containing the inputs, sorted by path.
 pub fn get_inputs_from_path(path: &str) -> Vec<String> {
     let mut inputs: Vec<String> = Vec::new();
 
@@ -72,7 +71,7 @@ pub fn get_inputs_from_path(path: &str) -> Vec<String> {
         inputs.push(path.to_string());
     } else {
         // read content of the folder under input and get all .vm files
-        let files = read_dir(&path);
+        let files = read_dir(path);
 
         // match and assess if the directory is readable
         match files {
@@ -99,6 +98,10 @@ pub fn get_inputs_from_path(path: &str) -> Vec<String> {
         }
     }
 
+    // read_dir gives no particular order, sort the files so the program
+    // is always translated the same way
+    inputs.sort();
+
     inputs
 }
 
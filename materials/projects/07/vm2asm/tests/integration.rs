@@ -0,0 +1,319 @@
+use hackcpu::script::{ScriptResult, ScriptRunner};
+use std::path::Path;
+use vm2asm::command::{Command, Op, Segment, SourceCommand};
+use vm2asm::{code::AssemblyConfiguration, utils};
+
+#[test]
+fn create_virtual_machine_and_parse() {
+    vm2asm::logs::log_command("create_virtual_machine_and_parse");
+
+    // create a new virtual machine
+    let mut parser = vm2asm::parser::Parser::new("add\nsub\nneg\npush local 2", "");
+
+    // parse the virtual machine
+    parser.parse().unwrap();
+
+    // get the commands
+    let commands = parser.get_fields();
+
+    let base_name: String = parser.get_base_name();
+
+    assert_eq!(base_name, utils::capitalize_n_letters("root", 1));
+
+    // check the commands
+    assert_eq!(commands.len(), 4);
+
+    // check the first command
+    assert_eq!(
+        commands[0],
+        SourceCommand {
+            command: Command::Arithmetic(Op::Add),
+            classname: "Root".to_string(),
+            source: "add".to_string(),
+            line: 1,
+        }
+    );
+
+    // check the second command
+    assert_eq!(
+        commands[1],
+        SourceCommand {
+            command: Command::Arithmetic(Op::Sub),
+            classname: "Root".to_string(),
+            source: "sub".to_string(),
+            line: 2,
+        }
+    );
+
+    // check the third command
+    assert_eq!(
+        commands[2],
+        SourceCommand {
+            command: Command::Arithmetic(Op::Neg),
+            classname: "Root".to_string(),
+            source: "neg".to_string(),
+            line: 3,
+        }
+    );
+
+    // check the fourth command
+    assert_eq!(
+        commands[3],
+        SourceCommand {
+            command: Command::Push {
+                segment: Segment::Local,
+                index: 2
+            },
+            classname: "Root".to_string(),
+            source: "push local 2".to_string(),
+            line: 4,
+        }
+    );
+
+    // generate a assembly generator based on those commands
+    let mut generator =
+        vm2asm::code::AssemblyGenerator::new(AssemblyConfiguration { bootstrap: false });
+
+    // process commands
+    generator.process_commands(commands);
+
+    assert_eq!(generator.instructions.len(), 5);
+}
+
+#[test]
+fn get_directory_inputs_in_order() {
+    let inputs = utils::get_inputs_from_path("../../08/FunctionCalls/StaticsTest");
+
+    let names: Vec<&str> = inputs
+        .iter()
+        .map(|input| Path::new(input).file_name().unwrap().to_str().unwrap())
+        .collect();
+
+    assert_eq!(names, vec!["Class1.vm", "Class2.vm", "Sys.vm"]);
+}
+
+/// Translates the .vm files of a directory as a single program (with the
+/// bootstrap for the programs that start in Sys.init)
+fn translate_directory(directory: &Path, bootstrap: bool) -> Vec<u8> {
+    let mut files = Vec::new();
+
+    for input in utils::get_inputs_from_path(&directory.display().to_string()) {
+        let input_content = utils::read_file(&input);
+
+        let input_file = Path::new(&input)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("");
+
+        let mut parser = vm2asm::parser::Parser::new(&input_content, input_file);
+        parser.parse().unwrap();
+
+        files.push(parser.get_fields().to_vec());
+    }
+
+    let mut generator = vm2asm::code::AssemblyGenerator::new(AssemblyConfiguration { bootstrap });
+    generator.process_program(&files);
+
+    generator.instructions_to_bytes()
+}
+
+/// Translates the .vm files of a course test, runs its .tst script with
+/// the hackcpu emulator and returns the result of the comparison
+fn run_course_test(directory: &str, name: &str, bootstrap: bool) -> ScriptResult {
+    let directory = Path::new(directory);
+    let instructions = translate_directory(directory, bootstrap);
+
+    // the script loads the program and the compare file from its own directory
+    let output = std::env::temp_dir().join(format!("vm2asm-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&output).unwrap();
+    std::fs::write(output.join(format!("{}.asm", name)), &instructions).unwrap();
+    std::fs::copy(
+        directory.join(format!("{}.cmp", name)),
+        output.join(format!("{}.cmp", name)),
+    )
+    .unwrap();
+
+    let script_file = directory.join(format!("{}.tst", name));
+    let script = utils::read_file(script_file.to_str().unwrap());
+    let result = ScriptRunner::new(&script, &output).run().unwrap();
+
+    std::fs::remove_dir_all(&output).unwrap();
+
+    result
+}
+
+#[test]
+fn translate_simple_add() {
+    let result = run_course_test("../StackArithmetic/SimpleAdd", "SimpleAdd", false);
+    assert_eq!(result.failure, None);
+}
+
+#[test]
+fn translate_stack_test() {
+    let result = run_course_test("../StackArithmetic/StackTest", "StackTest", false);
+    assert_eq!(result.failure, None);
+}
+
+#[test]
+fn translate_basic_test() {
+    let result = run_course_test("../MemoryAccess/BasicTest", "BasicTest", false);
+    assert_eq!(result.failure, None);
+}
+
+#[test]
+fn translate_pointer_test() {
+    let result = run_course_test("../MemoryAccess/PointerTest", "PointerTest", false);
+    assert_eq!(result.failure, None);
+}
+
+#[test]
+fn translate_static_test() {
+    let result = run_course_test("../MemoryAccess/StaticTest", "StaticTest", false);
+    assert_eq!(result.failure, None);
+}
+
+#[test]
+fn translate_simple_function() {
+    let result = run_course_test(
+        "../../08/FunctionCalls/SimpleFunction",
+        "SimpleFunction",
+        false,
+    );
+    assert_eq!(result.failure, None);
+}
+
+#[test]
+fn translate_nested_call() {
+    let result = run_course_test("../../08/FunctionCalls/NestedCall", "NestedCall", false);
+    assert_eq!(result.failure, None);
+}
+
+#[test]
+fn translate_statics_test() {
+    let result = run_course_test("../../08/FunctionCalls/StaticsTest", "StaticsTest", true);
+    assert_eq!(result.failure, None);
+}
+
+#[test]
+fn translate_fibonacci_element() {
+    let result = run_course_test(
+        "../../08/FunctionCalls/FibonacciElement",
+        "FibonacciElement",
+        true,
+    );
+    assert_eq!(result.failure, None);
+}
+
+#[test]
+fn translate_comparisons_in_several_files() {
+    let directory = std::env::temp_dir().join(format!("vm2asm-comparisons-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+
+    // more comparisons than a byte can count, in two files and functions
+    let mut main = String::from("function Main.count 0\npush constant 0\n");
+    for index in 0..150 {
+        main.push_str(&format!(
+            "push constant {}\npush constant 100\nlt\nadd\n",
+            index
+        ));
+    }
+    main.push_str("return\n");
+
+    let sys = "function Sys.init 0\ncall Main.count 0\npop temp 0\npush constant 5\npush constant 5\neq\npush constant 3\npush constant 4\ngt\nsub\npop temp 1\nlabel LOOP\ngoto LOOP\n";
+
+    std::fs::write(directory.join("Main.vm"), main).unwrap();
+    std::fs::write(directory.join("Sys.vm"), sys).unwrap();
+
+    let instructions = translate_directory(&directory, true);
+    let program = String::from_utf8(instructions.clone()).unwrap();
+
+    // every comparison label is defined once in the program
+    let labels: Vec<&str> = program
+        .lines()
+        .filter(|line| line.contains("_START_") && line.starts_with('('))
+        .collect();
+    let unique: std::collections::HashSet<&&str> = labels.iter().collect();
+    assert_eq!(labels.len(), 152);
+    assert_eq!(unique.len(), labels.len());
+
+    std::fs::write(directory.join("Comparisons.asm"), &instructions).unwrap();
+
+    let result = ScriptRunner::new(
+        "load Comparisons.asm,\noutput-list RAM[5]%D1.6.1 RAM[6]%D1.6.1;\nrepeat 20000 { ticktock; }\noutput;",
+        &directory,
+    )
+    .run()
+    .unwrap();
+
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    // 100 comparisons are true (-1) and eq - gt is -1 - 0
+    assert_eq!(result.failure, None);
+    assert_eq!(result.output.lines().nth(1), Some("|   -100 |     -1 |"));
+}
+
+#[test]
+fn translate_segment_push_through_the_base_address() {
+    let directory = std::env::temp_dir().join(format!("vm2asm-push-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let push = "push local 2\npop temp 0\npush argument 1\npop temp 1\npush this 2\npop temp 2\npush that 3\npop temp 3\n";
+    std::fs::write(directory.join("Push.vm"), push).unwrap();
+
+    let instructions = translate_directory(&directory, false);
+    std::fs::write(directory.join("Push.asm"), &instructions).unwrap();
+
+    // the values live at base + index, not at the address of the base pointer + index
+    let result = ScriptRunner::new(
+        "load Push.asm,\noutput-list RAM[5]%D1.6.1 RAM[6]%D1.6.1 RAM[7]%D1.6.1 RAM[8]%D1.6.1;\nset RAM[0] 256, set RAM[1] 300, set RAM[2] 400, set RAM[3] 3000, set RAM[4] 3010,\nset RAM[302] 12, set RAM[401] 34, set RAM[3002] 56, set RAM[3013] 78;\nrepeat 100 { ticktock; }\noutput;",
+        &directory,
+    )
+    .run()
+    .unwrap();
+
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(result.failure, None);
+    assert_eq!(
+        result.output.lines().nth(1),
+        Some("|     12 |     34 |     56 |     78 |")
+    );
+}
+
+#[test]
+fn translate_top_level_comparisons_in_several_files() {
+    let directory = std::env::temp_dir().join(format!("vm2asm-top-level-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+
+    // both files compare outside of any function
+    std::fs::write(
+        directory.join("First.vm"),
+        "push constant 7\npush constant 7\neq\npop temp 0\n",
+    )
+    .unwrap();
+    std::fs::write(
+        directory.join("Second.vm"),
+        "push constant 3\npush constant 4\nlt\npop temp 1\n",
+    )
+    .unwrap();
+
+    let instructions = translate_directory(&directory, false);
+    let program = String::from_utf8(instructions.clone()).unwrap();
+
+    assert_eq!(program.matches("(First.default$EQ_START_1)\n").count(), 1);
+    assert_eq!(program.matches("(Second.default$LT_START_2)\n").count(), 1);
+
+    std::fs::write(directory.join("TopLevel.asm"), &instructions).unwrap();
+
+    let result = ScriptRunner::new(
+        "load TopLevel.asm,\noutput-list RAM[5]%D1.6.1 RAM[6]%D1.6.1;\nset RAM[0] 256;\nrepeat 100 { ticktock; }\noutput;",
+        &directory,
+    )
+    .run()
+    .unwrap();
+
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(result.failure, None);
+    assert_eq!(result.output.lines().nth(1), Some("|     -1 |     -1 |"));
+}